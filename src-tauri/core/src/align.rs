@@ -0,0 +1,166 @@
+use std::collections::HashSet;
+
+use crate::lrc::{normalize_text_key, LrcLine};
+
+/// Global timing map `t' = stretch * t + offset_ms` from reference time to audio time.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeMap {
+  pub offset_ms: f64,
+  pub stretch: f64,
+  /// Number of reference lines the fit was based on.
+  pub support: usize,
+}
+
+impl TimeMap {
+  pub fn apply(&self, ms: i64) -> i64 {
+    (ms as f64 * self.stretch + self.offset_ms).round() as i64
+  }
+}
+
+fn words(s: &str) -> HashSet<String> {
+  normalize_text_key(s)
+    .split_whitespace()
+    .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_string())
+    .filter(|w| !w.is_empty())
+    .collect()
+}
+
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+  if a.is_empty() || b.is_empty() {
+    return 0.0;
+  }
+  a.intersection(b).count() as f64 / a.union(b).count() as f64
+}
+
+/// Pair each reference line with the most similar transcribed line.
+/// Only confident, order-preserving matches are kept.
+pub(crate) fn text_anchors(reference: &[LrcLine], heard: &[LrcLine]) -> Vec<(f64, f64)> {
+  let heard_words: Vec<HashSet<String>> = heard.iter().map(|l| words(&l.text)).collect();
+  let mut pairs = Vec::new();
+  let mut from = 0;
+
+  for r in reference {
+    let rw = words(&r.text);
+    let best = (from..heard.len())
+      .map(|j| (j, similarity(&rw, &heard_words[j])))
+      .filter(|(_, s)| *s >= 0.5)
+      .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    if let Some((j, _)) = best {
+      pairs.push((r.ms as f64, heard[j].ms as f64));
+      from = j + 1;
+    }
+  }
+  pairs
+}
+
+fn fit_line(pairs: &[(f64, f64)]) -> Option<(f64, f64)> {
+  let n = pairs.len() as f64;
+  if pairs.len() < 2 {
+    return None;
+  }
+  let mx = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+  let my = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+  let sxx: f64 = pairs.iter().map(|p| (p.0 - mx).powi(2)).sum();
+  let sxy: f64 = pairs.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+  if sxx <= f64::EPSILON {
+    return None;
+  }
+  let stretch = sxy / sxx;
+  Some((stretch, my - stretch * mx))
+}
+
+/// Offset-only fallback: slide the reference line starts over the transcribed ones
+/// and keep the shift where the most starts coincide (±150 ms).
+fn cross_correlate(reference: &[LrcLine], heard: &[LrcLine], max_shift_ms: i64) -> Option<TimeMap> {
+  let mut best: Option<(i64, usize)> = None;
+  let mut shift = -max_shift_ms;
+
+  while shift <= max_shift_ms {
+    let hits = reference
+      .iter()
+      .filter(|r| heard.iter().any(|h| (r.ms + shift - h.ms).abs() <= 150))
+      .count();
+    if best.map(|(_, b)| hits > b).unwrap_or(true) {
+      best = Some((shift, hits));
+    }
+    shift += 50;
+  }
+
+  best
+    .filter(|(_, hits)| *hits > 0)
+    .map(|(shift, hits)| TimeMap { offset_ms: shift as f64, stretch: 1.0, support: hits })
+}
+
+/// Estimate how `reference` timings map onto the audio that produced `heard`.
+///
+/// Lines whose text matches give anchor pairs for a least-squares offset + stretch fit,
+/// refit once without outliers. With too few anchors (or an implausible stretch) this
+/// falls back to cross-correlating line starts for a pure offset.
+pub fn estimate(reference: &[LrcLine], heard: &[LrcLine]) -> Option<TimeMap> {
+  let mut pairs = text_anchors(reference, heard);
+
+  if pairs.len() >= 3 {
+    if let Some((stretch, offset)) = fit_line(&pairs) {
+      pairs.retain(|(x, y)| (stretch * x + offset - y).abs() <= 1000.0);
+    }
+    if let Some((stretch, offset)) = fit_line(&pairs) {
+      if (0.9..=1.1).contains(&stretch) {
+        return Some(TimeMap { offset_ms: offset, stretch, support: pairs.len() });
+      }
+    }
+  }
+
+  cross_correlate(reference, heard, 30_000)
+}
+
+/// Re-time `reference` with `map`, keeping its text.
+pub fn apply(reference: &[LrcLine], map: &TimeMap) -> Vec<LrcLine> {
+  reference
+    .iter()
+    .map(|l| LrcLine {
+      ms: map.apply(l.ms).max(0),
+      text: l.text.clone(),
+      end_ms: l.end_ms.map(|e| map.apply(e).max(0)),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn line(ms: i64, text: &str) -> LrcLine {
+    LrcLine { ms, text: text.into(), end_ms: None }
+  }
+
+  const WORDS: [&str; 5] =
+    ["hold on tight", "the night is young", "we keep on running", "under city lights", "never look back"];
+
+  #[test]
+  fn fits_offset_and_stretch_from_matching_text() {
+    let reference: Vec<LrcLine> = WORDS.iter().enumerate().map(|(i, w)| line(i as i64 * 4_000, w)).collect();
+    // Sung 2 s later and 5% slower.
+    let heard: Vec<LrcLine> = reference.iter().map(|l| line((l.ms as f64 * 1.05) as i64 + 2_000, &l.text)).collect();
+    let map = estimate(&reference, &heard).unwrap();
+    assert_eq!(map.support, 5);
+    assert!((map.stretch - 1.05).abs() < 0.001);
+    assert!((map.offset_ms - 2_000.0).abs() < 5.0);
+    assert_eq!(apply(&reference, &map)[4].ms, heard[4].ms);
+  }
+
+  #[test]
+  fn falls_back_to_cross_correlating_starts() {
+    let reference: Vec<LrcLine> = (0..4).map(|i| line(i * 3_000, "la la")).collect();
+    let heard: Vec<LrcLine> = (0..4).map(|i| line(i * 3_000 + 1_500, "mm")).collect();
+    let map = estimate(&reference, &heard).unwrap();
+    // Starts within 150 ms count as coinciding.
+    assert!((map.offset_ms - 1_500.0).abs() <= 150.0);
+    assert_eq!((map.stretch, map.support), (1.0, 4));
+  }
+
+  #[test]
+  fn nothing_to_align_gives_none() {
+    assert!(estimate(&[line(0, "a")], &[]).is_none());
+  }
+}
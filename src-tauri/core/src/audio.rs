@@ -0,0 +1,158 @@
+use std::path::Path;
+
+/// Decoded PCM audio, mixed down to mono and scaled to [-1, 1].
+pub struct Wav {
+  pub sample_rate: u32,
+  pub samples: Vec<f32>,
+}
+
+/// Read a 16-bit PCM WAV (what `run_ffmpeg_to_wav` writes).
+pub fn read_wav(path: &Path) -> Result<Wav, String> {
+  let raw = std::fs::read(path).map_err(|e| format!("Read WAV failed: {e}"))?;
+  if raw.len() < 12 || &raw[0..4] != b"RIFF" || &raw[8..12] != b"WAVE" {
+    return Err("Not a RIFF/WAVE file".into());
+  }
+
+  let u16_at = |i: usize| u16::from_le_bytes([raw[i], raw[i + 1]]);
+  let u32_at = |i: usize| u32::from_le_bytes([raw[i], raw[i + 1], raw[i + 2], raw[i + 3]]);
+
+  let mut fmt: Option<(u16, u16, u32, u16)> = None; // (format, channels, rate, bits)
+  let mut pos = 12;
+
+  // Walk chunks: ffmpeg writes a LIST chunk between fmt and data.
+  while pos + 8 <= raw.len() {
+    let id = &raw[pos..pos + 4];
+    let size = u32_at(pos + 4) as usize;
+    let body = pos + 8;
+    let body_end = (body + size).min(raw.len());
+
+    if id == b"fmt " && size >= 16 {
+      fmt = Some((u16_at(body), u16_at(body + 2), u32_at(body + 4), u16_at(body + 14)));
+    } else if id == b"data" {
+      let (format, channels, sample_rate, bits) = fmt.ok_or("WAV data chunk before fmt chunk")?;
+      // 1 = PCM, 0xFFFE = WAVE_FORMAT_EXTENSIBLE (PCM sub-format in practice)
+      if !matches!(format, 1 | 0xFFFE) || bits != 16 || channels == 0 {
+        return Err(format!("Unsupported WAV encoding (format {format}, {bits}-bit)"));
+      }
+
+      let channels = channels as usize;
+      let frames = &raw[body..body_end];
+      let mut samples = Vec::with_capacity(frames.len() / (2 * channels));
+      for frame in frames.chunks_exact(2 * channels) {
+        let sum: f32 = frame
+          .chunks_exact(2)
+          .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+          .sum();
+        samples.push(sum / channels as f32);
+      }
+
+      return Ok(Wav { sample_rate, samples });
+    }
+
+    // Chunks are word-aligned.
+    pos = body + size + (size & 1);
+  }
+
+  Err("WAV has no data chunk".into())
+}
+
+const HOP_MS: i64 = 10;
+
+/// Frame energies in dB, one per 10 ms hop.
+pub fn energy_db(wav: &Wav) -> Vec<f32> {
+  let hop = (wav.sample_rate as usize * HOP_MS as usize / 1000).max(1);
+  wav
+    .samples
+    .chunks(hop)
+    .map(|f| {
+      let ms = f.iter().map(|x| x * x).sum::<f32>() / f.len() as f32;
+      10.0 * (ms + 1e-10).log10()
+    })
+    .collect()
+}
+
+/// Energy-rise onsets in milliseconds.
+///
+/// An onset is a frame whose energy jumps well above the quietest of the preceding
+/// 50 ms, is a local maximum of that rise, and is clearly above the track's noise floor.
+pub fn detect_onsets(wav: &Wav) -> Vec<i64> {
+  let e = energy_db(wav);
+  if e.len() < 8 {
+    return Vec::new();
+  }
+
+  let mut sorted = e.clone();
+  sorted.sort_by(|a, b| a.total_cmp(b));
+  let floor = sorted[sorted.len() / 5] + 10.0;
+
+  let rise: Vec<f32> = (0..e.len())
+    .map(|i| {
+      let lo = i.saturating_sub(5);
+      let min_prev = e[lo..i].iter().copied().fold(f32::INFINITY, f32::min);
+      if min_prev.is_finite() {
+        (e[i] - min_prev).max(0.0)
+      } else {
+        0.0
+      }
+    })
+    .collect();
+
+  let mut onsets = Vec::new();
+  for i in 1..rise.len() - 1 {
+    let peak = rise[i] >= rise[i - 1] && rise[i] > rise[i + 1];
+    if peak && rise[i] >= 6.0 && e[i] > floor {
+      onsets.push(i as i64 * HOP_MS);
+    }
+  }
+  onsets
+}
+
+// 200 and 60 BPM in 10 ms hops.
+const MIN_BEAT_HOPS: usize = 30;
+const MAX_BEAT_HOPS: usize = 100;
+
+/// Beat times in milliseconds.
+///
+/// The tempo is the strongest autocorrelation lag of the energy-rise envelope between 60
+/// and 200 BPM, leaning towards 120 so half- and double-time don't win on noise. The grid
+/// starts at the best-scoring phase, and each beat is nudged onto the nearest rise peak
+/// so the grid follows a drifting tempo.
+pub fn detect_beats(wav: &Wav) -> Vec<i64> {
+  let e = energy_db(wav);
+  if e.len() < 4 * MAX_BEAT_HOPS {
+    return Vec::new();
+  }
+
+  let rise: Vec<f32> = std::iter::once(0.0)
+    .chain(e.windows(2).map(|w| (w[1] - w[0]).max(0.0)))
+    .collect();
+  let mean = rise.iter().sum::<f32>() / rise.len() as f32;
+  let centered: Vec<f32> = rise.iter().map(|r| r - mean).collect();
+
+  let score = |lag: usize| {
+    let ac: f32 = centered.iter().zip(&centered[lag..]).map(|(a, b)| a * b).sum::<f32>() / (centered.len() - lag) as f32;
+    let octaves = (lag as f32 / 50.0).log2();
+    ac * (-0.5 * octaves * octaves).exp()
+  };
+  let Some(period) = (MIN_BEAT_HOPS..=MAX_BEAT_HOPS).max_by(|&a, &b| score(a).total_cmp(&score(b))) else {
+    return Vec::new();
+  };
+  if score(period) <= 0.0 {
+    // No repeating pulse (speech, rubato piano).
+    return Vec::new();
+  }
+
+  let phase_score = |phase: usize| rise[phase..].iter().step_by(period).sum::<f32>();
+  let phase = (0..period).max_by(|&a, &b| phase_score(a).total_cmp(&phase_score(b))).unwrap_or(0);
+
+  let mut beats = Vec::new();
+  let mut t = phase;
+  while t < rise.len() {
+    let lo = t.saturating_sub(2);
+    let hi = (t + 3).min(rise.len());
+    let beat = (lo..hi).max_by(|&a, &b| rise[a].total_cmp(&rise[b])).unwrap_or(t);
+    beats.push(beat as i64 * HOP_MS);
+    t = beat + period;
+  }
+  beats
+}
@@ -0,0 +1,101 @@
+use crate::lrc::{normalize_text_key, LrcLine};
+
+/// One slice of a long input, in source-time milliseconds.
+/// `len_ms` includes the overlap shared with the next chunk.
+#[derive(Clone, Debug)]
+pub struct Chunk {
+  pub start_ms: u64,
+  pub len_ms: u64,
+}
+
+/// Split `duration_ms` into chunks of `chunk_ms` plus `overlap_ms` of lookahead.
+/// Returns an empty plan when the input is short enough for a single whisper call.
+pub fn plan_chunks(duration_ms: u64, chunk_ms: u64, overlap_ms: u64) -> Vec<Chunk> {
+  if chunk_ms == 0 || duration_ms <= chunk_ms + overlap_ms {
+    return Vec::new();
+  }
+
+  let mut chunks = Vec::new();
+  let mut start = 0;
+  while start < duration_ms {
+    let len = (chunk_ms + overlap_ms).min(duration_ms - start);
+    chunks.push(Chunk { start_ms: start, len_ms: len });
+    start += chunk_ms;
+  }
+  chunks
+}
+
+/// Shift each chunk's lines to source time and stitch them together.
+///
+/// Each seam is cut at the middle of the overlap: a chunk keeps lines before the cut,
+/// its successor keeps lines from the cut on. Whisper often repeats the seam line in
+/// both chunks with slightly different timing, so identical text within the overlap
+/// window is dropped as well.
+pub fn merge_chunks(parts: Vec<(Chunk, Vec<LrcLine>)>, overlap_ms: u64) -> Vec<LrcLine> {
+  let cuts: Vec<i64> = parts
+    .iter()
+    .skip(1)
+    .map(|(c, _)| (c.start_ms + overlap_ms / 2) as i64)
+    .collect();
+
+  let mut out: Vec<LrcLine> = Vec::new();
+
+  for (i, (chunk, lines)) in parts.into_iter().enumerate() {
+    let lo = if i == 0 { i64::MIN } else { cuts[i - 1] };
+    let hi = cuts.get(i).copied().unwrap_or(i64::MAX);
+
+    for mut l in lines {
+      l.ms += chunk.start_ms as i64;
+      l.end_ms = l.end_ms.map(|e| e + chunk.start_ms as i64);
+      if l.ms < lo || l.ms >= hi {
+        continue;
+      }
+
+      let dup = i > 0
+        && (l.ms - lo).abs() <= overlap_ms as i64
+        && out.iter().rev().take(3).any(|p| {
+          (l.ms - p.ms).abs() <= overlap_ms as i64
+            && normalize_text_key(&p.text) == normalize_text_key(&l.text)
+        });
+      if dup {
+        continue;
+      }
+
+      out.push(l);
+    }
+  }
+
+  out.sort_by_key(|x| x.ms);
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn line(ms: i64, text: &str) -> LrcLine {
+    LrcLine { ms, text: text.into(), end_ms: None }
+  }
+
+  #[test]
+  fn short_inputs_are_not_chunked() {
+    assert!(plan_chunks(65_000, 60_000, 5_000).is_empty());
+    assert!(plan_chunks(600_000, 0, 5_000).is_empty());
+  }
+
+  #[test]
+  fn chunks_overlap_and_end_with_the_input() {
+    let plan: Vec<(u64, u64)> = plan_chunks(150_000, 60_000, 5_000).iter().map(|c| (c.start_ms, c.len_ms)).collect();
+    assert_eq!(plan, [(0, 65_000), (60_000, 65_000), (120_000, 30_000)]);
+  }
+
+  #[test]
+  fn merge_cuts_seams_and_drops_repeated_lines() {
+    let parts = vec![
+      (Chunk { start_ms: 0, len_ms: 65_000 }, vec![line(1_000, "first"), line(61_000, "seam line")]),
+      (Chunk { start_ms: 60_000, len_ms: 30_000 }, vec![line(1_200, "Seam line"), line(10_000, "second")]),
+    ];
+    let merged: Vec<(i64, String)> = merge_chunks(parts, 5_000).into_iter().map(|l| (l.ms, l.text)).collect();
+    assert_eq!(merged, [(1_000, "first".into()), (61_000, "seam line".into()), (70_000, "second".into())]);
+  }
+}
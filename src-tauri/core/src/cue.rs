@@ -0,0 +1,162 @@
+/// A parsed .cue sheet: album-level fields plus the tracks of each referenced file.
+#[derive(Clone, Debug, Default)]
+pub struct CueSheet {
+  pub performer: Option<String>,
+  pub title: Option<String>,
+  pub files: Vec<CueFile>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CueFile {
+  /// As written in the sheet, usually relative to the .cue's folder.
+  pub name: String,
+  pub tracks: Vec<CueTrack>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CueTrack {
+  pub number: u32,
+  pub title: Option<String>,
+  pub performer: Option<String>,
+  /// `INDEX 01` position within its file.
+  pub start_ms: u64,
+}
+
+// `FILE "Album Name.flac" WAVE` / `TITLE "x"` / `TITLE x`
+fn quoted_or_word(rest: &str) -> String {
+  let rest = rest.trim();
+  match rest.strip_prefix('"') {
+    Some(inner) => inner.split('"').next().unwrap_or_default().to_string(),
+    None => rest.split_whitespace().next().unwrap_or_default().to_string(),
+  }
+}
+
+// mm:ss:ff with 75 frames per second.
+fn parse_msf(s: &str) -> Option<u64> {
+  let mut parts = s.trim().split(':');
+  let m: u64 = parts.next()?.parse().ok()?;
+  let sec: u64 = parts.next()?.parse().ok()?;
+  let f: u64 = parts.next()?.parse().ok()?;
+  Some(m * 60_000 + sec * 1000 + f * 1000 / 75)
+}
+
+/// Parse a cue sheet. Unknown commands (REM, FLAGS, ISRC, …) are ignored; tracks
+/// without an `INDEX 01` are dropped.
+pub fn parse_cue(input: &str) -> CueSheet {
+  let mut sheet = CueSheet::default();
+  let mut track: Option<(CueTrack, bool)> = None;
+
+  let flush = |sheet: &mut CueSheet, track: &mut Option<(CueTrack, bool)>| {
+    if let (Some((t, true)), Some(file)) = (track.take(), sheet.files.last_mut()) {
+      file.tracks.push(t);
+    }
+  };
+
+  for line in input.trim_start_matches('\u{feff}').lines() {
+    let line = line.trim();
+    let (cmd, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+
+    match cmd.to_ascii_uppercase().as_str() {
+      "FILE" => {
+        flush(&mut sheet, &mut track);
+        // The file type (WAVE, MP3, …) follows the name; drop it.
+        let name = if rest.trim_start().starts_with('"') {
+          quoted_or_word(rest)
+        } else {
+          rest.rsplit_once(char::is_whitespace).map(|(n, _)| n).unwrap_or(rest).trim().to_string()
+        };
+        sheet.files.push(CueFile { name, tracks: Vec::new() });
+      }
+      "TRACK" => {
+        flush(&mut sheet, &mut track);
+        let number = rest.split_whitespace().next().and_then(|n| n.parse().ok()).unwrap_or(0);
+        track = Some((CueTrack { number, ..Default::default() }, false));
+      }
+      "TITLE" => match &mut track {
+        Some((t, _)) => t.title = Some(quoted_or_word(rest)),
+        None => sheet.title = Some(quoted_or_word(rest)),
+      },
+      "PERFORMER" => match &mut track {
+        Some((t, _)) => t.performer = Some(quoted_or_word(rest)),
+        None => sheet.performer = Some(quoted_or_word(rest)),
+      },
+      "INDEX" => {
+        let mut parts = rest.split_whitespace();
+        if let (Some("01"), Some(pos), Some((t, seen))) = (parts.next(), parts.next(), &mut track) {
+          if let Some(ms) = parse_msf(pos) {
+            t.start_ms = ms;
+            *seen = true;
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+  flush(&mut sheet, &mut track);
+
+  sheet
+}
+
+/// Fill a track name template: `{track}` (zero-padded to 2), `{title}`, `{performer}`
+/// and `{album}`. Missing fields fall back to "Track NN" / album-level values.
+pub fn track_name(template: &str, sheet: &CueSheet, track: &CueTrack) -> String {
+  let number = format!("{:02}", track.number);
+  let title = track.title.clone().unwrap_or_else(|| format!("Track {number}"));
+  let performer = track.performer.as_ref().or(sheet.performer.as_ref()).cloned().unwrap_or_default();
+  let album = sheet.title.clone().unwrap_or_default();
+
+  template
+    .replace("{track}", &number)
+    .replace("{title}", &title)
+    .replace("{performer}", &performer)
+    .replace("{album}", &album)
+    .trim()
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SHEET: &str = "\u{feff}REM GENRE Rock
+PERFORMER \"The Band\"
+TITLE \"Live Album\"
+FILE \"Live Album.flac\" WAVE
+  TRACK 01 AUDIO
+    TITLE \"Opener\"
+    INDEX 00 00:00:00
+    INDEX 01 00:00:32
+  TRACK 02 AUDIO
+    TITLE Encore
+    PERFORMER \"Guest\"
+    INDEX 01 04:10:15
+  TRACK 03 AUDIO
+    TITLE \"No index\"
+FILE bonus.wav WAVE
+  TRACK 04 AUDIO
+    INDEX 01 00:00:00
+";
+
+  #[test]
+  fn parses_files_tracks_and_positions() {
+    let sheet = parse_cue(SHEET);
+    assert_eq!(sheet.performer.as_deref(), Some("The Band"));
+    assert_eq!(sheet.title.as_deref(), Some("Live Album"));
+    assert_eq!(sheet.files.len(), 2);
+    assert_eq!((sheet.files[0].name.as_str(), sheet.files[1].name.as_str()), ("Live Album.flac", "bonus.wav"));
+
+    let tracks = &sheet.files[0].tracks;
+    assert_eq!(tracks.len(), 2, "a track without INDEX 01 is dropped");
+    assert_eq!((tracks[0].number, tracks[0].start_ms), (1, 426));
+    assert_eq!((tracks[1].title.as_deref(), tracks[1].start_ms), (Some("Encore"), 250_200));
+    assert_eq!(tracks[1].performer.as_deref(), Some("Guest"));
+  }
+
+  #[test]
+  fn track_names_fall_back_to_album_fields() {
+    let sheet = parse_cue(SHEET);
+    let bonus = &sheet.files[1].tracks[0];
+    let name = track_name("{track} - {performer} - {title} ({album})", &sheet, bonus);
+    assert_eq!(name, "04 - The Band - Track 04 (Live Album)");
+  }
+}
@@ -0,0 +1,41 @@
+use crate::parse::Segment;
+use crate::lrc::LrcLine;
+
+/// Speaker changes as `(start_ms, speaker)` from tinydiarize turn flags.
+/// tinydiarize only marks turns, so voices are assumed to alternate (duets, interviews).
+pub fn speaker_turns(segments: &[Segment]) -> Vec<(i64, usize)> {
+  let mut turns: Vec<(i64, usize)> = Vec::new();
+  let mut speaker = 0;
+
+  for seg in segments {
+    if turns.last().map(|t| t.1 != speaker).unwrap_or(true) {
+      turns.push((seg.start_ms as i64, speaker));
+    }
+    if seg.speaker_turn_next {
+      speaker = 1 - speaker;
+    }
+  }
+  turns
+}
+
+/// Prefix each line with its voice (`v1: `, `v2: `), the duet convention most players read.
+pub fn label_lines(mut lines: Vec<LrcLine>, turns: &[(i64, usize)]) -> Vec<LrcLine> {
+  if turns.is_empty() {
+    return lines;
+  }
+
+  for l in lines.iter_mut() {
+    if l.text.trim().is_empty() {
+      continue;
+    }
+    // Line starts may have been nudged earlier than the segment start; allow some slack.
+    let speaker = turns
+      .iter()
+      .rev()
+      .find(|(start, _)| *start <= l.ms + 250)
+      .map(|t| t.1)
+      .unwrap_or(turns[0].1);
+    l.text = format!("v{}: {}", speaker + 1, l.text.trim());
+  }
+  lines
+}
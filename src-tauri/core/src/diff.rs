@@ -0,0 +1,100 @@
+use serde::Serialize;
+
+use crate::lrc::{self, LrcLine};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+  /// Same words (timing may differ).
+  Same,
+  /// Both have a line here, with different words.
+  Changed,
+  OnlyA,
+  OnlyB,
+}
+
+/// One row of a side-by-side view of two transcripts.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffRow {
+  pub kind: DiffKind,
+  pub a: Option<LrcLine>,
+  pub b: Option<LrcLine>,
+}
+
+fn key(text: &str) -> String {
+  lrc::words(text).join(" ")
+}
+
+// Unmatched lines between two anchors sit side by side as changes; the longer side's
+// leftovers are one-sided.
+fn flush(rows: &mut Vec<DiffRow>, a: &[LrcLine], b: &[LrcLine]) {
+  for i in 0..a.len().max(b.len()) {
+    let (a, b) = (a.get(i).cloned(), b.get(i).cloned());
+    let kind = match (&a, &b) {
+      (Some(_), Some(_)) => DiffKind::Changed,
+      (Some(_), None) => DiffKind::OnlyA,
+      _ => DiffKind::OnlyB,
+    };
+    rows.push(DiffRow { kind, a, b });
+  }
+}
+
+/// Line-aligned diff: the longest run of lines with matching words (ignoring case and
+/// punctuation) anchors the alignment, everything in between is paired up in order.
+pub fn diff_lines(a: &[LrcLine], b: &[LrcLine]) -> Vec<DiffRow> {
+  let ka: Vec<String> = a.iter().map(|l| key(&l.text)).collect();
+  let kb: Vec<String> = b.iter().map(|l| key(&l.text)).collect();
+
+  // lcs[i][j] = longest common subsequence of ka[i..] and kb[j..]
+  let mut lcs = vec![vec![0usize; kb.len() + 1]; ka.len() + 1];
+  for i in (0..ka.len()).rev() {
+    for j in (0..kb.len()).rev() {
+      lcs[i][j] = if ka[i] == kb[j] {
+        lcs[i + 1][j + 1] + 1
+      } else {
+        lcs[i + 1][j].max(lcs[i][j + 1])
+      };
+    }
+  }
+
+  let mut rows = Vec::with_capacity(a.len().max(b.len()));
+  let (mut i, mut j) = (0, 0);
+  let (mut from_a, mut from_b) = (0, 0);
+  while i < ka.len() && j < kb.len() {
+    if ka[i] == kb[j] {
+      flush(&mut rows, &a[from_a..i], &b[from_b..j]);
+      rows.push(DiffRow {
+        kind: DiffKind::Same,
+        a: Some(a[i].clone()),
+        b: Some(b[j].clone()),
+      });
+      i += 1;
+      j += 1;
+      (from_a, from_b) = (i, j);
+    } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+      i += 1;
+    } else {
+      j += 1;
+    }
+  }
+  flush(&mut rows, &a[from_a..], &b[from_b..]);
+  rows
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn line(ms: i64, text: &str) -> LrcLine {
+    LrcLine { ms, text: text.into(), end_ms: None }
+  }
+
+  #[test]
+  fn non_ascii_lines_are_compared_by_their_words() {
+    let a = [line(0, "Не уходи"), line(1000, "Ça va")];
+    let b = [line(0, "не уходи!"), line(1000, "Ça ira")];
+    let kinds: Vec<DiffKind> = diff_lines(&a, &b).iter().map(|r| r.kind).collect();
+    assert_eq!(kinds, [DiffKind::Same, DiffKind::Changed]);
+  }
+}
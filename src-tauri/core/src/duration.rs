@@ -0,0 +1,49 @@
+use crate::lrc::LrcLine;
+
+/// Merge lyric lines shown for less than `min_ms` into the lines after them, so nothing
+/// flashes by too fast to read (0 = off).
+pub fn merge_short_lines(lines: Vec<LrcLine>, min_ms: i64) -> Vec<LrcLine> {
+  if min_ms <= 0 {
+    return lines;
+  }
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len());
+
+  for l in lines {
+    if let Some(prev) = out.last_mut() {
+      let flashes = !prev.text.trim().is_empty() && l.ms - prev.ms < min_ms;
+      if flashes && l.text.trim().is_empty() {
+        // A blank right after a short line would blank it out too; keep it up instead.
+        continue;
+      }
+      if flashes {
+        prev.text = format!("{} {}", prev.text.trim_end(), l.text.trim());
+        prev.end_ms = prev.end_ms.max(l.end_ms);
+        continue;
+      }
+    }
+    out.push(l);
+  }
+
+  out
+}
+
+/// End lyric lines that would stay up longer than `max_ms` (a line before a long solo)
+/// with a blank line, at the line's end when that's known and sooner. LRC only:
+/// subtitle cues are capped in `lrc::timed_lines` (0 = off).
+pub fn cap_long_lines(lines: Vec<LrcLine>, max_ms: i64) -> Vec<LrcLine> {
+  if max_ms <= 0 {
+    return lines;
+  }
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len());
+
+  for (i, l) in lines.iter().enumerate() {
+    out.push(l.clone());
+    let Some(next) = lines.get(i + 1) else { continue };
+    if !l.text.trim().is_empty() && next.ms - l.ms > max_ms {
+      let at = l.end_ms.filter(|&e| e > l.ms).map_or(l.ms + max_ms, |e| e.min(l.ms + max_ms));
+      out.push(LrcLine { ms: at, text: String::new(), end_ms: None });
+    }
+  }
+
+  out
+}
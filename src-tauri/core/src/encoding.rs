@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+/// Byte encoding of written lyric files. Some older Chinese/Japanese players only read
+/// files that start with a byte-order mark.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputEncoding {
+  #[default]
+  Utf8,
+  Utf8Bom,
+  /// UTF-16 little-endian with a BOM, what Windows calls "Unicode".
+  Utf16le,
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+pub fn encode(text: &str, encoding: OutputEncoding) -> Vec<u8> {
+  let text = text.trim_start_matches('\u{feff}');
+  match encoding {
+    OutputEncoding::Utf8 => text.as_bytes().to_vec(),
+    OutputEncoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+    OutputEncoding::Utf16le => {
+      let mut out = vec![0xFF, 0xFE];
+      out.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+      out
+    }
+  }
+}
+
+/// The encoding a file was written in, judged by its BOM, so edits can keep it.
+/// UTF-16BE is rare enough that it is rewritten as little-endian.
+pub fn detect(bytes: &[u8]) -> OutputEncoding {
+  if bytes.starts_with(UTF8_BOM) {
+    OutputEncoding::Utf8Bom
+  } else if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+    OutputEncoding::Utf16le
+  } else {
+    OutputEncoding::Utf8
+  }
+}
+
+// chardetng's guess; without a TLD hint it leans on the byte statistics alone.
+fn guess(bytes: &[u8]) -> &'static encoding_rs::Encoding {
+  let mut detector = chardetng::EncodingDetector::new();
+  detector.feed(bytes, true);
+  detector.guess(None, true)
+}
+
+// The code page text that isn't valid UTF-8 is in. UTF-8 cut off mid-character (a
+// truncated download) is still UTF-8, as is whatever chardetng takes for it.
+fn code_page(bytes: &[u8]) -> &'static encoding_rs::Encoding {
+  match std::str::from_utf8(bytes) {
+    Err(e) if e.error_len().is_some() => guess(bytes),
+    _ => encoding_rs::UTF_8,
+  }
+}
+
+/// The legacy code page ("GBK", "Shift_JIS", "windows-1252", ...) text that is neither
+/// valid UTF-8 nor BOM-marked most likely uses; `None` for Unicode files.
+pub fn legacy_charset(bytes: &[u8]) -> Option<&'static str> {
+  let unicode = bytes.starts_with(UTF8_BOM) || bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]);
+  if unicode {
+    return None;
+  }
+  Some(code_page(bytes)).filter(|e| *e != encoding_rs::UTF_8).map(|e| e.name())
+}
+
+/// Text of a lyric or subtitle file without its BOM: UTF-8, UTF-16 with a BOM, or
+/// whatever legacy code page community files come in (GBK, Shift-JIS, Windows-1252),
+/// detected and transcoded. Invalid sequences in damaged UTF-8 become U+FFFD.
+pub fn decode(bytes: &[u8]) -> String {
+  let utf16 = |body: &[u8], from: fn([u8; 2]) -> u16| {
+    let units: Vec<u16> = body.chunks_exact(2).map(|c| from([c[0], c[1]])).collect();
+    String::from_utf16_lossy(&units)
+  };
+  if let Some(body) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+    utf16(body, u16::from_le_bytes)
+  } else if let Some(body) = bytes.strip_prefix(&[0xFE, 0xFF]) {
+    utf16(body, u16::from_be_bytes)
+  } else {
+    let body = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    // A UTF-8 BOM with broken sequences after it is damaged UTF-8, not a code page.
+    let encoding = if body.len() < bytes.len() { encoding_rs::UTF_8 } else { code_page(body) };
+    encoding.decode_without_bom_handling(body).0.into_owned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn encode_and_detect_agree() {
+    for enc in [OutputEncoding::Utf8, OutputEncoding::Utf8Bom, OutputEncoding::Utf16le] {
+      let bytes = encode("[00:01.00]héllo", enc);
+      assert_eq!(detect(&bytes), enc);
+      assert_eq!(decode(&bytes), "[00:01.00]héllo");
+    }
+  }
+
+  #[test]
+  fn decode_handles_utf16_be() {
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend("ok".encode_utf16().flat_map(u16::to_be_bytes));
+    assert_eq!(decode(&bytes), "ok");
+  }
+
+  #[test]
+  fn decode_transcodes_legacy_code_pages() {
+    let (gbk, _, _) = encoding_rs::GBK.encode("[00:01.00]你好世界，我们一起唱歌吧\n[00:05.00]明天会更好");
+    assert_eq!(legacy_charset(&gbk), Some("GBK"));
+    assert!(decode(&gbk).contains("你好世界"));
+
+    let (latin, _, _) = encoding_rs::WINDOWS_1252.encode("[00:01.00]Café crème, déjà vu à la française");
+    assert!(decode(&latin).contains("Café crème"));
+    assert_eq!(legacy_charset("plain ascii".as_bytes()), None);
+  }
+
+  #[test]
+  fn decode_transcodes_mostly_ascii_windows_1252() {
+    let text = "[00:01.00]We met at the little place on the corner\n".repeat(50) + "[04:10.00]Back at the café\n";
+    let (latin, _, _) = encoding_rs::WINDOWS_1252.encode(&text);
+    assert_eq!(legacy_charset(&latin), Some("windows-1252"));
+    assert!(decode(&latin).ends_with("Back at the café\n"));
+  }
+
+  #[test]
+  fn decode_keeps_damaged_utf8() {
+    let mut bytes = "[00:01.00]你好世界，我们一起唱歌吧 ".repeat(20).into_bytes();
+    bytes.push(0xE4);
+    assert_eq!(legacy_charset(&bytes), None);
+    let text = decode(&bytes);
+    assert!(text.contains("你好世界") && text.ends_with('\u{fffd}'));
+  }
+}
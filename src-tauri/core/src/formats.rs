@@ -0,0 +1,126 @@
+use crate::linebreak::TimedLine;
+
+pub fn to_lrc(lines: &[TimedLine]) -> String {
+  let mut out = String::new();
+  for l in lines {
+    out.push_str(&format!("[{}]{}\n", fmt_lrc_time(l.start_ms), l.text));
+  }
+  out
+}
+
+fn fmt_lrc_time(ms: u64) -> String {
+  // [mm:ss.xx] where xx is centiseconds
+  let total_cs = ms / 10;
+  let cs = total_cs % 100;
+  let total_s = total_cs / 100;
+  let s = total_s % 60;
+  let m = total_s / 60;
+  format!("{:02}:{:02}.{:02}", m, s, cs)
+}
+
+
+pub fn to_srt(lines: &[TimedLine]) -> String {
+  let mut out = String::new();
+  for (i, l) in lines.iter().enumerate() {
+    out.push_str(&format!(
+      "{}\n{} --> {}\n{}\n\n",
+      i + 1,
+      fmt_cue_time(l.start_ms, ','),
+      fmt_cue_time(l.end_ms, ','),
+      l.text
+    ));
+  }
+  out
+}
+
+pub fn to_vtt(lines: &[TimedLine]) -> String {
+  let mut out = String::from("WEBVTT\n\n");
+  for l in lines {
+    out.push_str(&format!(
+      "{} --> {}\n{}\n\n",
+      fmt_cue_time(l.start_ms, '.'),
+      fmt_cue_time(l.end_ms, '.'),
+      l.text
+    ));
+  }
+  out
+}
+
+fn fmt_cue_time(ms: u64, sep: char) -> String {
+  // hh:mm:ss,mmm (SRT) / hh:mm:ss.mmm (VTT)
+  let h = ms / 3_600_000;
+  let m = (ms / 60_000) % 60;
+  let s = (ms / 1000) % 60;
+  format!("{:02}:{:02}:{:02}{}{:03}", h, m, s, sep, ms % 1000)
+}
+
+fn parse_cue_time(s: &str) -> Option<u64> {
+  // hh:mm:ss,mmm (SRT) / [hh:]mm:ss.mmm (VTT)
+  let (clock, frac) = s.trim().split_once([',', '.'])?;
+  let parts: Vec<u64> = clock.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+  let (h, m, sec) = match parts.as_slice() {
+    [h, m, s] => (*h, *m, *s),
+    [m, s] => (0, *m, *s),
+    _ => return None,
+  };
+  let ms: u64 = format!("{frac:0<3}").get(..3)?.parse().ok()?;
+  Some(((h * 60 + m) * 60 + sec) * 1000 + ms)
+}
+
+// Cue text is markup: <i>, <c.color>, karaoke <00:01.000> stamps, SRT's {\an8}.
+fn strip_cue_markup(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut depth: Option<char> = None;
+  for c in text.chars() {
+    match (depth, c) {
+      (None, '<') => depth = Some('>'),
+      (None, '{') => depth = Some('}'),
+      (Some(close), c) if c == close => depth = None,
+      (Some(_), _) => {}
+      (None, c) => out.push(c),
+    }
+  }
+  out
+    .replace("&amp;", "&")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&nbsp;", " ")
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Cues of an SRT or WebVTT file, multi-line cue text joined with spaces. Headers,
+/// NOTE/STYLE blocks and cue settings are ignored.
+pub fn parse_subtitles(input: &str) -> Vec<TimedLine> {
+  let mut out = Vec::new();
+  let mut lines = input.lines().peekable();
+
+  while let Some(line) = lines.next() {
+    let Some((start, rest)) = line.split_once("-->") else {
+      continue;
+    };
+    let end = rest.split_whitespace().next().unwrap_or_default();
+    let (Some(start_ms), Some(end_ms)) = (parse_cue_time(start), parse_cue_time(end)) else {
+      continue;
+    };
+
+    let mut text = Vec::new();
+    while let Some(l) = lines.peek() {
+      if l.trim().is_empty() {
+        break;
+      }
+      text.push(strip_cue_markup(l));
+      lines.next();
+    }
+
+    out.push(TimedLine {
+      start_ms,
+      end_ms: end_ms.max(start_ms),
+      text: text.join(" ").trim().to_string(),
+    });
+  }
+
+  out.sort_by_key(|l| l.start_ms);
+  out
+}
@@ -0,0 +1,29 @@
+//! Transcript processing behind LyricTime, independent of Tauri: LRC parsing and
+//! cleaning, hybrid/chunk merging, alignment, line-breaking and output formats.
+//! Progress is reported through the [`progress::Progress`] trait.
+
+pub mod align;
+pub mod audio;
+pub mod chunk;
+pub mod cue;
+pub mod diff;
+pub mod duration;
+pub mod diarize;
+pub mod encoding;
+pub mod formats;
+pub mod linebreak;
+pub mod lrc;
+pub mod merge;
+pub mod options;
+pub mod parse;
+pub mod pipeline;
+pub mod postprocess;
+pub mod preserve;
+pub mod progress;
+pub mod punctuate;
+pub mod romanize;
+pub mod score;
+pub mod sections;
+pub mod textnorm;
+pub mod validate;
+pub mod vocabulary;
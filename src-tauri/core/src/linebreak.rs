@@ -1,4 +1,4 @@
-use super::parse::Segment;
+use crate::parse::Segment;
 
 #[derive(Debug, Clone)]
 pub struct TimedLine {
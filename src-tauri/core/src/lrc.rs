@@ -0,0 +1,765 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::formats;
+use crate::linebreak::TimedLine;
+use crate::options::OutputFormat;
+use crate::postprocess;
+
+/* -------------------- Hybrid+ merge helpers -------------------- */
+
+/// One timed line: start in milliseconds plus its text.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LrcLine {
+  pub ms: i64,
+  pub text: String,
+  /// When the line stops being sung, if known (whisper's segment end, a subtitle cue's
+  /// end). LRC files only store starts; see `lines_to_timed_lrc`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub end_ms: Option<i64>,
+}
+
+pub fn normalize_text_key(s: &str) -> String {
+  let t = s.trim().to_ascii_lowercase();
+  let mut out = String::with_capacity(t.len());
+  let mut last_space = false;
+
+  for ch in t.chars() {
+    let is_space = ch.is_whitespace();
+    if is_space {
+      if !last_space {
+        out.push(' ');
+      }
+      last_space = true;
+      continue;
+    }
+
+    if ch.is_ascii_alphanumeric() || matches!(ch, '\'' | '-' | ',' | '.' | '?' | '!') {
+      out.push(ch);
+      last_space = false;
+    }
+  }
+
+  out.trim().to_string()
+}
+
+/// Lowercased words of `s` for comparing transcripts, in any script: "Ça va, l'été"
+/// gives "ça", "va", "l'été".
+pub fn words(s: &str) -> Vec<String> {
+  s.to_lowercase()
+    .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’')
+    .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+    .filter(|w| !w.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+pub fn word_count(s: &str) -> usize {
+  s.split_whitespace().filter(|w| !w.is_empty()).count()
+}
+
+pub fn parse_ts_to_ms(ts: &str) -> Option<i64> {
+  // expects like [mm:ss.xx] or [mm:ss.xxx]
+  let t = ts.trim().trim_start_matches('[').trim_end_matches(']');
+  let mut parts = t.split(':');
+  let mm = parts.next()?.parse::<i64>().ok()?;
+  let rest = parts.next()?;
+  let mut parts2 = rest.split('.');
+  let ss = parts2.next()?.parse::<i64>().ok()?;
+  let frac = parts2.next().unwrap_or("0");
+
+  let frac_ms = match frac.len() {
+    0 => 0,
+    1 => frac.parse::<i64>().ok()? * 100,
+    2 => frac.parse::<i64>().ok()? * 10,
+    _ => frac.get(..3)?.parse::<i64>().ok()?,
+  };
+
+  Some(mm * 60_000 + ss * 1000 + frac_ms)
+}
+
+pub fn format_ms_to_ts(ms: i64) -> String {
+  let mut ms = ms;
+  if ms < 0 {
+    ms = 0;
+  }
+  let total_seconds = ms / 1000;
+  let mm = total_seconds / 60;
+  let ss = total_seconds % 60;
+  let cs = (ms % 1000) / 10; // centiseconds
+  format!("[{:02}:{:02}.{:02}]", mm, ss, cs)
+}
+
+/// `max_line_ms` caps how long a subtitle cue stays up (0 = off); LRC lines get blank
+/// lines for that instead, see `duration::cap_long_lines`.
+pub fn render(format: OutputFormat, lines: &[LrcLine], max_line_ms: i64) -> String {
+  match format {
+    OutputFormat::Lrc => lines_to_lrc(lines),
+    OutputFormat::Srt => formats::to_srt(&timed_lines(lines, max_line_ms)),
+    OutputFormat::Vtt => formats::to_vtt(&timed_lines(lines, max_line_ms)),
+  }
+}
+
+/// Give each line an end: the next line's start, capped at its known or estimated end
+/// and at `max_ms` after its start (0 = no cap).
+pub fn timed_lines(lines: &[LrcLine], max_ms: i64) -> Vec<TimedLine> {
+  lines
+    .iter()
+    .enumerate()
+    .map(|(i, l)| {
+      let mut est_end = postprocess::estimated_end_ms(l);
+      if max_ms > 0 {
+        est_end = est_end.min(l.ms + max_ms);
+      }
+      let end = lines.get(i + 1).map(|n| n.ms.min(est_end)).unwrap_or(est_end);
+      TimedLine {
+        start_ms: l.ms.max(0) as u64,
+        end_ms: end.max(l.ms).max(0) as u64,
+        text: l.text.trim().to_string(),
+      }
+    })
+    .collect()
+}
+
+pub fn lines_to_lrc(lines: &[LrcLine]) -> String {
+  let mut out = String::new();
+  for l in lines {
+    out.push_str(&format_ms_to_ts(l.ms));
+    // Blank lines (gap markers) are a bare timestamp.
+    if !l.text.trim().is_empty() {
+      out.push(' ');
+      out.push_str(l.text.trim());
+    }
+    out.push('\n');
+  }
+  out
+}
+
+/// Transcript text between passes: `lines_to_lrc` plus each known end as a trailing
+/// `<mm:ss.xx>`, the enhanced-LRC end-of-line stamp, which `parse_lrc` reads back.
+/// Written files use `lines_to_lrc`, since most players would show the stamp.
+pub fn lines_to_timed_lrc(lines: &[LrcLine]) -> String {
+  let mut out = String::new();
+  for l in lines {
+    out.push_str(&format_ms_to_ts(l.ms));
+    if !l.text.trim().is_empty() {
+      out.push(' ');
+      out.push_str(l.text.trim());
+    }
+    if let Some(end) = l.end_ms.filter(|&e| e > l.ms) {
+      out.push(' ');
+      out.push_str(&format_inline_ts(end));
+    }
+    out.push('\n');
+  }
+  out
+}
+
+fn format_inline_ts(ms: i64) -> String {
+  format_ms_to_ts(ms).replace('[', "<").replace(']', ">")
+}
+
+// Text before a trailing `<mm:ss.xx>` end stamp, and the stamp.
+fn split_end_tag(text: &str) -> (&str, Option<i64>) {
+  let trimmed = text.trim_end();
+  let end = trimmed
+    .strip_suffix('>')
+    .and_then(|t| t.rfind('<').map(|start| (start, &t[start + 1..])))
+    .and_then(|(start, ts)| Some((start, parse_ts_to_ms(ts)?)));
+  match end {
+    Some((start, ms)) => (trimmed[..start].trim_end(), Some(ms)),
+    None => (text, None),
+  }
+}
+
+/// Timed lines with their end times where the text carries them (`lines_to_timed_lrc`).
+pub fn parse_lrc(input: &str) -> Vec<LrcLine> {
+  parse(input, false, true)
+}
+
+/// The timestamps a line starts with (`[01:00.00][02:10.00]chorus` has two) and the
+/// rest of it; none for ID tags and untimed lines.
+pub fn split_timestamps(line: &str) -> (Vec<i64>, &str) {
+  let mut rest = line;
+  let mut stamps = Vec::new();
+  while let Some(end) = rest.strip_prefix('[').and_then(|_| rest.find(']')) {
+    match parse_ts_to_ms(&rest[..=end]) {
+      Some(ms) => stamps.push(ms),
+      None => break,
+    }
+    rest = &rest[end + 1..];
+  }
+  (stamps, rest)
+}
+
+/// Like `parse_lrc`, optionally keeping bare timestamps (gap markers) as empty lines.
+/// A line with several timestamps becomes one entry per timestamp. The text is kept as
+/// written, end stamps included, so editing tools write it back unchanged.
+pub fn parse_lrc_lines(input: &str, keep_gaps: bool) -> Vec<LrcLine> {
+  parse(input, keep_gaps, false)
+}
+
+fn parse(input: &str, keep_gaps: bool, ends: bool) -> Vec<LrcLine> {
+  let mut out = Vec::new();
+  for line in input.lines() {
+    let (stamps, rest) = split_timestamps(line.trim());
+    let (text, end) = if ends { split_end_tag(rest) } else { (rest, None) };
+    let text = text.trim();
+    if text.is_empty() && !keep_gaps {
+      continue;
+    }
+    // A repeated line lasts as long each time.
+    let first = stamps.first().copied().unwrap_or(0);
+    out.extend(stamps.into_iter().map(|ms| LrcLine {
+      ms,
+      text: text.to_string(),
+      end_ms: end.map(|e| ms + e - first).filter(|&e| e > ms),
+    }));
+  }
+  out.sort_by_key(|x| x.ms);
+  out
+}
+
+/// Rewrite every line timestamp, inline `<mm:ss.xx>` stamps included, through `f`,
+/// leaving ID tags, untimed lines and line endings as they were.
+pub fn retime_lrc(input: &str, f: impl Fn(i64) -> i64) -> String {
+  let mut out = String::with_capacity(input.len());
+  for line in input.split_inclusive('\n') {
+    let body = line.trim_end_matches(['\r', '\n']);
+    let ending = &line[body.len()..];
+    let trimmed = body.trim_start_matches('\u{feff}').trim_start();
+
+    let (stamps, rest) = split_timestamps(trimmed);
+    if stamps.is_empty() {
+      out.push_str(line);
+      continue;
+    }
+    out.push_str(&body[..body.len() - trimmed.len()]);
+    for ms in stamps {
+      out.push_str(&format_ms_to_ts(f(ms)));
+    }
+    // Inline `<mm:ss.xx>` stamps: end stamps, and enhanced-LRC word stamps.
+    let mut text = rest;
+    while let Some((start, len, ms)) = text.find('<').and_then(|start| {
+      let len = text[start..].find('>')? + 1;
+      Some((start, len, parse_ts_to_ms(&text[start + 1..start + len - 1])))
+    }) {
+      out.push_str(&text[..start]);
+      match ms {
+        Some(ms) => out.push_str(&format_inline_ts(f(ms))),
+        None => out.push_str(&text[start..start + len]),
+      }
+      text = &text[start + len..];
+    }
+    out.push_str(text);
+    out.push_str(ending);
+  }
+  out
+}
+
+/// What a live display should show at a playback position.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LyricPosition {
+  /// Index of the active line; unset before the first one. An empty active line is a
+  /// gap marker: show nothing.
+  pub index: Option<usize>,
+  pub line: Option<LrcLine>,
+  pub next: Option<LrcLine>,
+  pub next_in_ms: Option<i64>,
+}
+
+/// Active and upcoming line at `position_ms`; `lines` must be sorted by time.
+pub fn lyric_at(lines: &[LrcLine], position_ms: i64) -> LyricPosition {
+  let upcoming = lines.partition_point(|l| l.ms <= position_ms);
+  let index = upcoming.checked_sub(1);
+  let next = lines.get(upcoming).cloned();
+  LyricPosition {
+    index,
+    line: index.map(|i| lines[i].clone()),
+    next_in_ms: next.as_ref().map(|n| n.ms - position_ms),
+    next,
+  }
+}
+
+/// ID tags such as `[ar:Artist]` or `[offset:+200]`, in file order.
+pub fn parse_lrc_tags(input: &str) -> Vec<(String, String)> {
+  input
+    .lines()
+    .filter_map(|line| {
+      let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+      let (key, value) = inner.split_once(':')?;
+      let key = key.trim();
+      if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+      }
+      Some((key.to_string(), value.trim().to_string()))
+    })
+    .collect()
+}
+
+fn is_offset_tag(line: &str) -> bool {
+  parse_lrc_tags(line).iter().any(|(k, _)| k.eq_ignore_ascii_case("offset"))
+}
+
+/// The `[offset:±ms]` tag's value, 0 without one. Positive shows the lyrics earlier.
+pub fn offset_tag(input: &str) -> i64 {
+  parse_lrc_tags(input)
+    .iter()
+    .find(|(k, _)| k.eq_ignore_ascii_case("offset"))
+    .and_then(|(_, v)| v.trim_start_matches('+').trim().parse().ok())
+    .unwrap_or(0)
+}
+
+/// Bake the `[offset:]` tag into the timestamps and drop it, so every time is the one
+/// players show.
+pub fn apply_offset_tag(input: &str) -> String {
+  let offset = offset_tag(input);
+  let stripped: String = input.split_inclusive('\n').filter(|l| !is_offset_tag(l)).collect();
+  if offset == 0 {
+    return stripped;
+  }
+  retime_lrc(&stripped, |ms| (ms - offset).max(0))
+}
+
+/// Set the `[offset:]` tag (replacing any there is) without touching the timestamps;
+/// 0 removes it. Goes after the other header tags.
+pub fn set_offset_tag(input: &str, offset_ms: i64) -> String {
+  let newline = if input.contains("\r\n") { "\r\n" } else { "\n" };
+  let mut out = String::with_capacity(input.len() + 16);
+  let mut pending = (offset_ms != 0).then(|| format!("[offset:{offset_ms:+}]{newline}"));
+  for line in input.split_inclusive('\n').filter(|l| !is_offset_tag(l)) {
+    if parse_lrc_tags(line).is_empty() {
+      if let Some(tag) = pending.take() {
+        out.push_str(&tag);
+      }
+    }
+    out.push_str(line);
+  }
+  out.extend(pending);
+  out
+}
+
+/// ID tag header lines followed by the timed lines.
+pub fn lines_to_lrc_with_tags(tags: &[(String, String)], lines: &[LrcLine]) -> String {
+  let mut out: String = tags.iter().map(|(k, v)| format!("[{k}:{v}]\n")).collect();
+  out.push_str(&lines_to_lrc(lines));
+  out
+}
+
+/// Replace the lines of `existing` that start in `start_ms..end_ms` with `lines`,
+/// keeping ID tags and everything outside the range.
+pub fn splice_range(existing: &str, start_ms: i64, end_ms: i64, lines: &[LrcLine]) -> String {
+  let mut kept: Vec<LrcLine> = parse_lrc_lines(existing, true)
+    .into_iter()
+    .filter(|l| l.ms < start_ms || l.ms >= end_ms)
+    .collect();
+  kept.extend(lines.iter().filter(|l| (start_ms..end_ms).contains(&l.ms)).cloned());
+  kept.sort_by_key(|l| l.ms);
+  lines_to_lrc_with_tags(&parse_lrc_tags(existing), &kept)
+}
+
+pub fn build_chant_set(lines: &[LrcLine]) -> HashSet<String> {
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  for l in lines {
+    let key = normalize_text_key(&l.text);
+    if key.is_empty() {
+      continue;
+    }
+    *counts.entry(key).or_insert(0) += 1;
+  }
+
+  let mut chant = HashSet::new();
+  for (k, c) in counts {
+    // chant heuristic: repeated short lines
+    if c >= 3 && word_count(&k) <= 4 {
+      chant.insert(k);
+    }
+  }
+  chant
+}
+
+pub(crate) fn find_nearest_within(
+  lines: &[LrcLine],
+  target_ms: i64,
+  tol_ms: i64,
+  used: &HashSet<usize>,
+) -> Option<usize> {
+  let mut best: Option<(usize, i64)> = None; // (idx, abs_diff)
+  for (i, l) in lines.iter().enumerate() {
+    if used.contains(&i) {
+      continue;
+    }
+    let d = (l.ms - target_ms).abs();
+    if d <= tol_ms {
+      match best {
+        None => best = Some((i, d)),
+        Some((_, bd)) if d < bd => best = Some((i, d)),
+        _ => {}
+      }
+    }
+  }
+  best.map(|(i, _)| i)
+}
+
+pub fn normalize_lrc_timestamps(input: &str, min_gap_ms: i64) -> String {
+  let mut lines = parse_lrc(input);
+  if lines.is_empty() {
+    return String::new();
+  }
+
+  let mut last_ms = lines[0].ms;
+  for line in lines.iter_mut().skip(1) {
+    if line.ms < last_ms {
+      line.ms = last_ms;
+    }
+    if line.ms - last_ms < min_gap_ms {
+      line.ms = last_ms + min_gap_ms;
+    }
+    last_ms = line.ms;
+  }
+
+  lines_to_timed_lrc(&lines)
+}
+
+pub fn merge_hybrid_plus(small_clean: &str, medium_clean: &str, chant_aware: bool) -> String {
+  merge_hybrid_plus_with_decisions(small_clean, medium_clean, chant_aware).0
+}
+
+/// Where a merged hybrid line came from.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MergeSource {
+  /// Both passes heard it; medium's wording won.
+  Medium,
+  /// Both passes heard it, but it repeats (a chant), so small's copy was kept.
+  ChantPreserved,
+  /// Both passes heard it; small's wording won (ensemble merge).
+  Small,
+  /// Only small heard it.
+  SmallOnly,
+  /// Only medium heard it.
+  MediumOnly,
+}
+
+/// One output line of the hybrid merge with both candidates, for review and overrides.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeDecision {
+  /// Final (normalized) timestamp.
+  pub ms: i64,
+  pub text: String,
+  pub source: MergeSource,
+  pub small_text: Option<String>,
+  pub medium_text: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub end_ms: Option<i64>,
+}
+
+pub(crate) fn only(lines: Vec<LrcLine>, source: MergeSource) -> Vec<MergeDecision> {
+  lines
+    .into_iter()
+    .map(|l| MergeDecision {
+      ms: l.ms,
+      small_text: (source == MergeSource::SmallOnly).then(|| l.text.clone()),
+      medium_text: (source == MergeSource::MediumOnly).then(|| l.text.clone()),
+      text: l.text,
+      source,
+      end_ms: l.end_ms,
+    })
+    .collect()
+}
+
+/// `merge_hybrid_plus` plus the decision taken for every output line.
+pub fn merge_hybrid_plus_with_decisions(
+  small_clean: &str,
+  medium_clean: &str,
+  chant_aware: bool,
+) -> (String, Vec<MergeDecision>) {
+  let small = parse_lrc(small_clean);
+  let medium = parse_lrc(medium_clean);
+
+  if small.is_empty() {
+    let out = normalize_lrc_timestamps(medium_clean, 250);
+    let decisions = only(parse_lrc(&out), MergeSource::MediumOnly);
+    return (out, decisions);
+  }
+  if medium.is_empty() {
+    let out = normalize_lrc_timestamps(small_clean, 250);
+    let decisions = only(parse_lrc(&out), MergeSource::SmallOnly);
+    return (out, decisions);
+  }
+
+  let chant = if chant_aware {
+    build_chant_set(&small)
+  } else {
+    HashSet::new()
+  };
+
+  let tol_ms = 300;
+  let min_gap_ms = 250;
+
+  let mut used_medium: HashSet<usize> = HashSet::new();
+  let mut merged: Vec<MergeDecision> = Vec::new();
+
+  // baseline: small order (coverage)
+  for s in &small {
+    let s_key = normalize_text_key(&s.text);
+    let is_chant = chant.contains(&s_key);
+
+    if let Some(idx) = find_nearest_within(&medium, s.ms, tol_ms, &used_medium) {
+      let m = &medium[idx];
+      let (chosen_text, source, end_ms) = if is_chant {
+        // keep small for chants to preserve repetition coverage
+        (s.text.clone(), MergeSource::ChantPreserved, s.end_ms.or(m.end_ms))
+      } else {
+        // prefer medium wording when available
+        (m.text.clone(), MergeSource::Medium, m.end_ms.or(s.end_ms))
+      };
+
+      used_medium.insert(idx);
+      merged.push(MergeDecision {
+        ms: s.ms,
+        text: chosen_text,
+        source,
+        small_text: Some(s.text.clone()),
+        medium_text: Some(m.text.clone()),
+        end_ms,
+      });
+    } else {
+      merged.extend(only(vec![s.clone()], MergeSource::SmallOnly));
+    }
+  }
+
+  // append medium-only lines (avoid chant spam)
+  for (i, m) in medium.iter().enumerate() {
+    if used_medium.contains(&i) {
+      continue;
+    }
+    let k = normalize_text_key(&m.text);
+    if chant.contains(&k) {
+      continue;
+    }
+    merged.extend(only(vec![m.clone()], MergeSource::MediumOnly));
+  }
+
+  finish_merge(merged, min_gap_ms)
+}
+
+/// Sort merged lines, drop exact duplicates, make timestamps monotonic with at least
+/// `min_gap_ms` between lines, and render them.
+pub(crate) fn finish_merge(mut merged: Vec<MergeDecision>, min_gap_ms: i64) -> (String, Vec<MergeDecision>) {
+  if merged.is_empty() {
+    return (String::new(), merged);
+  }
+  merged.sort_by_key(|x| x.ms);
+
+  // drop exact duplicates
+  let mut dedup: Vec<MergeDecision> = Vec::new();
+  for l in merged {
+    if let Some(last) = dedup.last() {
+      if last.ms == l.ms && normalize_text_key(&last.text) == normalize_text_key(&l.text) {
+        continue;
+      }
+    }
+    dedup.push(l);
+  }
+
+  // normalize timestamps (monotonic + minimum gap)
+  let mut last_ms = dedup[0].ms;
+  for d in dedup.iter_mut().skip(1) {
+    if d.ms < last_ms {
+      d.ms = last_ms;
+    }
+    if d.ms - last_ms < min_gap_ms {
+      d.ms = last_ms + min_gap_ms;
+    }
+    last_ms = d.ms;
+  }
+
+  let lines: Vec<LrcLine> = dedup
+    .iter()
+    .map(|d| LrcLine { ms: d.ms, text: d.text.clone(), end_ms: d.end_ms })
+    .collect();
+  (lines_to_timed_lrc(&lines), dedup)
+}
+
+/* -------------------- Instrumental detection -------------------- */
+
+// With VAD on, whisper emits nothing for non-speech; without it, instrumentals come back as
+// "(music)" / ♪ cue lines which clean_lrc already drops. Either way: no lyric lines left.
+pub fn is_instrumental(cleaned: &str) -> bool {
+  parse_lrc(cleaned).is_empty()
+}
+
+/* -------------------- Cleaning -------------------- */
+
+/// `keep_cues` keeps parenthetical cue lines like "(laughs)", which matter in speech transcripts.
+pub fn clean_lrc(input: &str, keep_cues: bool) -> String {
+  let mut out = String::new();
+
+  for line in input.lines() {
+    let l = line.trim();
+    if l.is_empty() {
+      continue;
+    }
+
+    // Drop metadata tags like [by:whisper.cpp], [ar:...], etc.
+    if l.starts_with('[') {
+      if let Some(end) = l.find(']') {
+        let inside = &l[1..end];
+        // If it's a tag (contains ':' and doesn't start with a digit), drop it.
+        if inside.contains(':')
+          && inside
+            .chars()
+            .next()
+            .map(|c| !c.is_ascii_digit())
+            .unwrap_or(false)
+        {
+          continue;
+        }
+      }
+    }
+
+    // Timestamp line: [mm:ss.xx]text, one line per stamp for repeated ones
+    let (stamps, rest) = split_timestamps(l);
+    if !stamps.is_empty() {
+      let (rest, end_ms) = split_end_tag(rest);
+      let mut text = rest.trim().replace('♪', "").trim().to_string();
+
+      // Drop music cue lines like "(upbeat music)"
+      if !keep_cues && text.starts_with('(') && text.ends_with(')') {
+        continue;
+      }
+
+      if text.is_empty() {
+        continue;
+      }
+
+      while text.contains("  ") {
+        text = text.replace("  ", " ");
+      }
+
+      for ms in stamps {
+        out.push_str(&format_ms_to_ts(ms));
+        out.push(' ');
+        out.push_str(text.trim());
+        if let Some(end_ms) = end_ms {
+          out.push(' ');
+          out.push_str(&format_inline_ts(end_ms));
+        }
+        out.push('\n');
+      }
+      continue;
+    }
+
+    // Otherwise keep non-timestamp lines (rare), but also strip ♪
+    let cleaned = l.replace('♪', "").trim().to_string();
+    if !cleaned.is_empty() {
+      out.push_str(&cleaned);
+      out.push('\n');
+    }
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn words_keep_non_ascii_letters() {
+    assert_eq!(words("Ça va, l'été!"), ["ça", "va", "l'été"]);
+    assert_eq!(words("Не  уходи — 'stay'"), ["не", "уходи", "stay"]);
+    assert_eq!(words("愛してる"), ["愛してる"]);
+  }
+
+  #[test]
+  fn timed_lines_cap_cue_length() {
+    let lines = [
+      LrcLine { ms: 0, text: "before the solo".into(), end_ms: Some(20_000) },
+      LrcLine { ms: 30_000, text: "after".into(), end_ms: None },
+    ];
+    assert_eq!(timed_lines(&lines, 0)[0].end_ms, 20_000);
+    assert_eq!(timed_lines(&lines, 8_000)[0].end_ms, 8_000);
+  }
+
+  #[test]
+  fn timestamps_round_trip() {
+    assert_eq!(parse_ts_to_ms("[01:02.50]"), Some(62_500));
+    assert_eq!(parse_ts_to_ms("[00:01.5]"), Some(1_500));
+    assert_eq!(parse_ts_to_ms("[00:01.234]"), Some(1_234));
+    assert_eq!(parse_ts_to_ms("[ar:x]"), None);
+    assert_eq!(format_ms_to_ts(62_500), "[01:02.50]");
+    assert_eq!(format_ms_to_ts(-5), "[00:00.00]");
+  }
+
+  #[test]
+  fn parse_expands_multi_timestamp_lines() {
+    let lines = parse_lrc("[ar:Artist]\n[00:30.00][00:10.00]chorus\n[00:20.00]verse\n");
+    let got: Vec<(i64, &str)> = lines.iter().map(|l| (l.ms, l.text.as_str())).collect();
+    assert_eq!(got, [(10_000, "chorus"), (20_000, "verse"), (30_000, "chorus")]);
+  }
+
+  #[test]
+  fn end_stamps_survive_a_round_trip() {
+    let lines = parse_lrc("[00:01.00] hello <00:02.50>\n[00:05.00] world\n");
+    assert_eq!(lines[0].text, "hello");
+    assert_eq!(lines[0].end_ms, Some(2_500));
+    assert_eq!(lines[1].end_ms, None);
+    assert_eq!(lines_to_timed_lrc(&lines), "[00:01.00] hello <00:02.50>\n[00:05.00] world\n");
+  }
+
+  #[test]
+  fn parse_lines_keeps_gaps_on_request() {
+    let input = "[00:01.00]a\n[00:02.00]\n[00:03.00]b\n";
+    assert_eq!(parse_lrc_lines(input, false).len(), 2);
+    assert_eq!(parse_lrc_lines(input, true).len(), 3);
+  }
+
+  #[test]
+  fn retime_shifts_line_and_inline_stamps() {
+    let out = retime_lrc("[ti:x]\r\n[00:01.00]<00:01.00>a <00:01.50>b\r\n", |ms| ms + 1000);
+    assert_eq!(out, "[ti:x]\r\n[00:02.00]<00:02.00>a <00:02.50>b\r\n");
+  }
+
+  #[test]
+  fn offset_tag_is_baked_in_and_set() {
+    let input = "[ar:A]\n[offset:+500]\n[00:02.00]line\n";
+    assert_eq!(offset_tag(input), 500);
+    assert_eq!(apply_offset_tag(input), "[ar:A]\n[00:01.50]line\n");
+    assert_eq!(set_offset_tag("[ar:A]\n[00:02.00]line\n", -200), "[ar:A]\n[offset:-200]\n[00:02.00]line\n");
+    assert_eq!(set_offset_tag(input, 0), "[ar:A]\n[00:02.00]line\n");
+  }
+
+  #[test]
+  fn clean_drops_tags_cues_and_notes() {
+    let raw = "[by:whisper.cpp]\n[00:01.00]  ♪ hello   there ♪\n[00:02.00] (upbeat music)\n[00:03.00] ♪\n";
+    assert_eq!(clean_lrc(raw, false), "[00:01.00] hello there\n");
+    assert_eq!(clean_lrc(raw, true), "[00:01.00] hello there\n[00:02.00] (upbeat music)\n");
+  }
+
+  #[test]
+  fn clean_lrc_splits_repeated_lines() {
+    assert_eq!(clean_lrc("[01:00.00][02:10.00] chorus ♪\n", false), "[01:00.00] chorus\n[02:10.00] chorus\n");
+  }
+
+  #[test]
+  fn normalize_enforces_order_and_gap() {
+    let out = normalize_lrc_timestamps("[00:01.00]a\n[00:01.10]b\n[00:05.00]c\n", 250);
+    let ms: Vec<i64> = parse_lrc(&out).iter().map(|l| l.ms).collect();
+    assert_eq!(ms, [1_000, 1_250, 5_000]);
+  }
+
+  #[test]
+  fn lyric_at_finds_active_and_next_line() {
+    let lines = parse_lrc("[00:01.00]a\n[00:03.00]b\n");
+    let before = lyric_at(&lines, 500);
+    assert_eq!(before.index, None);
+    assert_eq!(before.next_in_ms, Some(500));
+    let during = lyric_at(&lines, 2_000);
+    assert_eq!(during.index, Some(0));
+    assert_eq!(during.next.map(|n| n.text), Some("b".to_string()));
+  }
+}
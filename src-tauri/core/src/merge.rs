@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use crate::lrc::{
+  finish_merge, find_nearest_within, merge_hybrid_plus_with_decisions, normalize_lrc_timestamps, only, parse_lrc,
+  word_count, MergeDecision, MergeSource,
+};
+use crate::options::{MergeMode, Profile};
+
+/// Lines of the two passes closer than this are the same line.
+const MATCH_TOLERANCE_MS: i64 = 300;
+const MIN_GAP_MS: i64 = 250;
+
+/// How hybrid mode combines its small and medium passes into one transcript, with the
+/// decision taken for every output line.
+pub trait MergeStrategy {
+  fn merge(&self, small_clean: &str, medium_clean: &str) -> (String, Vec<MergeDecision>);
+}
+
+/// Medium's transcript as is; small only fills in when medium heard nothing.
+pub struct PreferMedium;
+
+impl MergeStrategy for PreferMedium {
+  fn merge(&self, small_clean: &str, medium_clean: &str) -> (String, Vec<MergeDecision>) {
+    let (clean, source) = if parse_lrc(medium_clean).is_empty() {
+      (small_clean, MergeSource::SmallOnly)
+    } else {
+      (medium_clean, MergeSource::MediumOnly)
+    };
+    let out = normalize_lrc_timestamps(clean, MIN_GAP_MS);
+    let decisions = only(parse_lrc(&out), source);
+    (out, decisions)
+  }
+}
+
+/// Small's line coverage with medium's wording; repeated chant lines keep small's copies.
+pub struct HybridPlus {
+  pub chant_aware: bool,
+}
+
+impl MergeStrategy for HybridPlus {
+  fn merge(&self, small_clean: &str, medium_clean: &str) -> (String, Vec<MergeDecision>) {
+    merge_hybrid_plus_with_decisions(small_clean, medium_clean, self.chant_aware)
+  }
+}
+
+/// Every line either pass heard. Lines both heard get the average of their timestamps and
+/// the wording with more words, since whisper drops words more often than it invents them.
+pub struct Ensemble;
+
+impl MergeStrategy for Ensemble {
+  fn merge(&self, small_clean: &str, medium_clean: &str) -> (String, Vec<MergeDecision>) {
+    let small = parse_lrc(small_clean);
+    let medium = parse_lrc(medium_clean);
+
+    let mut used_medium: HashSet<usize> = HashSet::new();
+    let mut merged: Vec<MergeDecision> = Vec::with_capacity(small.len().max(medium.len()));
+    for s in &small {
+      let Some(idx) = find_nearest_within(&medium, s.ms, MATCH_TOLERANCE_MS, &used_medium) else {
+        merged.extend(only(vec![s.clone()], MergeSource::SmallOnly));
+        continue;
+      };
+      used_medium.insert(idx);
+      let m = &medium[idx];
+      let (text, source) = if word_count(&s.text) > word_count(&m.text) {
+        (s.text.clone(), MergeSource::Small)
+      } else {
+        (m.text.clone(), MergeSource::Medium)
+      };
+      merged.push(MergeDecision {
+        ms: (s.ms + m.ms) / 2,
+        text,
+        source,
+        small_text: Some(s.text.clone()),
+        medium_text: Some(m.text.clone()),
+        // Both heard the line; it lasts until the later of their ends.
+        end_ms: s.end_ms.max(m.end_ms),
+      });
+    }
+    let medium_only = medium.iter().enumerate().filter(|(i, _)| !used_medium.contains(i));
+    merged.extend(only(medium_only.map(|(_, m)| m.clone()).collect(), MergeSource::MediumOnly));
+
+    finish_merge(merged, MIN_GAP_MS)
+  }
+}
+
+/// The strategy for `mode`. Repeated short lines are chants in songs but filler
+/// ("yeah", "right") in speech, so chant handling is for lyrics only.
+pub fn strategy(mode: MergeMode, profile: Profile) -> Box<dyn MergeStrategy> {
+  match mode {
+    MergeMode::PreferMedium => Box::new(PreferMedium),
+    MergeMode::HybridPlus => Box::new(HybridPlus {
+      chant_aware: profile == Profile::Lyrics,
+    }),
+    MergeMode::Ensemble => Box::new(Ensemble),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn texts(out: &str) -> Vec<String> {
+    parse_lrc(out).into_iter().map(|l| l.text).collect()
+  }
+
+  #[test]
+  fn prefer_medium_falls_back_to_small() {
+    let (out, decisions) = PreferMedium.merge("[00:01.00] small\n", "");
+    assert_eq!(texts(&out), ["small"]);
+    assert!(matches!(decisions[0].source, MergeSource::SmallOnly));
+
+    let (out, _) = PreferMedium.merge("[00:01.00] small\n", "[00:01.00] medium\n");
+    assert_eq!(texts(&out), ["medium"]);
+  }
+
+  #[test]
+  fn ensemble_keeps_lines_either_pass_heard() {
+    let small = "[00:01.00] hello\n[00:05.00] only small\n";
+    let medium = "[00:01.20] hello there\n[00:09.00] only medium\n";
+    let (out, decisions) = Ensemble.merge(small, medium);
+    assert_eq!(texts(&out), ["hello there", "only small", "only medium"]);
+    assert_eq!(decisions[0].ms, 1_100);
+    assert_eq!(decisions[0].small_text.as_deref(), Some("hello"));
+  }
+
+  #[test]
+  fn hybrid_plus_uses_medium_wording_on_small_timing() {
+    let (out, _) = HybridPlus { chant_aware: true }.merge("[00:01.00] helo there\n", "[00:01.10] hello there\n");
+    assert_eq!(parse_lrc(&out)[0].ms, 1_000);
+    assert_eq!(texts(&out), ["hello there"]);
+  }
+}
@@ -0,0 +1,331 @@
+use serde::{Deserialize, Serialize};
+
+pub use crate::encoding::OutputEncoding;
+pub use crate::textnorm::TextCleanup;
+
+use crate::validate;
+
+/// What is being transcribed. Drives cleaning, merging and the default output format.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+  /// Songs: drop music cues, chant-aware hybrid merge, LRC output.
+  #[default]
+  Lyrics,
+  /// Podcasts/interviews: keep cues like "(laughs)", sentence-length lines, SRT output.
+  Speech,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+  Lrc,
+  Srt,
+  Vtt,
+}
+
+impl OutputFormat {
+  pub const ALL: [OutputFormat; 3] = [OutputFormat::Lrc, OutputFormat::Srt, OutputFormat::Vtt];
+
+  pub fn extension(self) -> &'static str {
+    match self {
+      OutputFormat::Lrc => "lrc",
+      OutputFormat::Srt => "srt",
+      OutputFormat::Vtt => "vtt",
+    }
+  }
+}
+
+/// Romanization of CJK/Cyrillic output text.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RomanizeMode {
+  #[default]
+  Off,
+  /// Write romanized text instead of the original script.
+  Replace,
+  /// Keep the original and write a `.romanized.<ext>` copy next to it.
+  Sidecar,
+}
+
+/// What to do with files that have chapters (audiobooks, DJ mixes).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChapterMode {
+  /// One pass over the whole file, chapters ignored.
+  #[default]
+  Off,
+  /// One output per chapter, named after it.
+  Split,
+  /// One output with a marker line at each chapter start.
+  Markers,
+}
+
+/// Karaoke countdown lines in the seconds before the singing starts again.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CountdownMode {
+  #[default]
+  Off,
+  /// `3…`, `2…`, `1…`
+  Numbers,
+  /// `● ● ●`, `● ●`, `●`
+  Dots,
+}
+
+/// How hybrid mode combines its small and medium passes (see [`crate::merge`]).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMode {
+  /// Medium's transcript, small only where medium heard nothing.
+  PreferMedium,
+  /// Small's coverage with medium's wording, chant-aware.
+  #[default]
+  HybridPlus,
+  /// Every line either pass heard, timestamps averaged.
+  Ensemble,
+}
+
+/// Verse/chorus/bridge labels for navigating long songs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionMode {
+  #[default]
+  Off,
+  /// Write `<name>.sections.json`.
+  Sidecar,
+  /// Also put a `[#:Chorus 1]` comment line ahead of each section in LRC output.
+  Comments,
+}
+
+/// What whisper runs on.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+  /// The fastest backend already set up on this machine; never downloads one.
+  #[default]
+  Auto,
+  /// CPU only, even when the build could use the GPU.
+  Cpu,
+  /// Apple Silicon: the encoder on the Neural Engine via Core ML (about 3× faster),
+  /// downloading the Core ML build and encoders as needed.
+  CoreMl,
+  /// Intel CPUs and iGPUs: the encoder on OpenVINO, downloading the OpenVINO build and
+  /// encoders as needed.
+  OpenVino,
+}
+
+/// Batch handling of files that already have lyrics.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExistingLyrics {
+  /// Generate anyway, overwriting the previous output.
+  #[default]
+  Regenerate,
+  /// Skip files that already have a lyrics file (.lrc, .srt or .vtt) in any format.
+  Skip,
+  /// Only generate for files with no lyrics at all (no output file, no lyrics tag).
+  FillMissing,
+}
+
+/// Per-run options for the generate command.
+/// Every field has a default so the frontend can send only what it changes.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GenerateOptions {
+  /// Run whisper with Silero VAD so non-speech audio is never decoded.
+  pub vad: bool,
+  /// Text written at [00:00.00] when no vocals are found.
+  pub instrumental_marker: String,
+  /// Inputs longer than this are transcribed in chunks (0 = never chunk).
+  pub chunk_seconds: u64,
+  /// Audio shared between neighbouring chunks so no line is cut in half.
+  pub chunk_overlap_seconds: u64,
+  /// Draft passes: speed the audio up by this factor (up to 1.5) before transcribing and
+  /// scale the timestamps back; much faster, slightly less accurate. 1.0 = off.
+  pub speed_up: f32,
+  /// Insert a marker line when lyrics pause for longer than this (0 = off).
+  pub gap_marker_seconds: u64,
+  /// Marker text for gaps; empty writes a bare timestamp.
+  pub gap_marker_text: String,
+  /// LRC: count in the first line and lines after a long gap.
+  pub countdown: CountdownMode,
+  /// Gaps shorter than this get no countdown.
+  pub countdown_gap_seconds: u64,
+  /// Snap line starts to detected audio onsets.
+  pub refine_onsets: bool,
+  /// How far before a whisper timestamp to look for the onset.
+  pub onset_window_ms: u64,
+  /// Snap line starts to the detected beat grid.
+  pub snap_to_beats: bool,
+  /// How far a line may move to land on a beat.
+  pub beat_tolerance_ms: u64,
+  /// Synced lyrics (e.g. from LRCLIB) whose text is kept and re-timed to this audio.
+  pub reference_lrc: Option<String>,
+  /// Label lines by speaker (`v1:`/`v2:`) using a tinydiarize pass.
+  pub diarize: bool,
+  pub profile: Profile,
+  /// Output format; unset means the profile's default.
+  pub output_format: Option<OutputFormat>,
+  /// Spoken language passed to whisper (`en`, `ja`, `auto`, …).
+  pub language: Option<String>,
+  /// Speech profile: joined sentences stay under this many characters.
+  pub line_max_chars: usize,
+  /// Speech profile: lines closer than this are joined into one sentence.
+  pub line_join_pause_ms: i64,
+  /// Lines shown for less than this are merged into the next one (0 = off; 700 reads well).
+  pub min_line_ms: i64,
+  /// Lines still up after this long are cleared with a blank line in LRC and end early
+  /// in subtitles (0 = off).
+  pub max_line_ms: i64,
+  /// Split lines longer than this for narrow displays, e.g. car head units (0 = off).
+  pub wrap_width: usize,
+  pub romanize: RomanizeMode,
+  /// Follow each line with its English translation (extra whisper translate pass).
+  pub bilingual: bool,
+  /// Seed whisper's initial prompt with the file's artist/title/album tags.
+  pub prompt_from_tags: bool,
+  /// Extra prompt text (names, spellings) appended after the tags.
+  pub prompt_hint: Option<String>,
+  /// Advanced decoding; unset keeps whisper's defaults.
+  pub decoding: Decoding,
+  /// Passed verbatim to every whisper transcription pass (power users).
+  pub extra_whisper_args: Vec<String>,
+  /// Output options passed to ffmpeg when it converts the input (e.g. `-af highpass=f=100`).
+  pub extra_ffmpeg_args: Vec<String>,
+  /// Audio stream to transcribe in multi-stream files (position among audio streams).
+  pub audio_stream: Option<usize>,
+  /// Keep only the front-center channel of surround mixes (a cheap vocal isolate).
+  pub center_channel_only: bool,
+  /// Output name for each track of a .cue album: `{track}`, `{title}`, `{performer}`, `{album}`.
+  pub track_name_template: String,
+  /// Files with embedded chapters: one output per chapter, marker lines, or ignore them.
+  pub chapters: ChapterMode,
+  pub sections: SectionMode,
+  pub existing_lyrics: ExistingLyrics,
+  /// Reuse cleaned transcripts from earlier runs on the same audio and model.
+  pub cache: bool,
+  /// Keep converted WAVs across runs, up to this many MB (0 = off).
+  pub wav_cache_mb: u64,
+  /// Formats whisper can't read: pipe ffmpeg's decoded audio into whisper instead of
+  /// writing a temp WAV. Each pass decodes again; passes that need the WAV still write it.
+  pub pipe_conversion: bool,
+  /// Batch: transcribe duplicate audio once and copy the result to the others.
+  pub dedupe: bool,
+  /// What whisper runs on; every pass of a run, including compare and range regeneration.
+  pub backend: Backend,
+  /// NVIDIA card to transcribe on, by its `list_gpus` index (unset = CUDA's choice).
+  pub gpu_index: Option<u32>,
+  /// Kill whisper/ffmpeg if it prints nothing for this long (0 = wait forever).
+  pub stall_timeout_seconds: u64,
+  /// Model to retry with when whisper runs out of memory (unset = fail instead).
+  /// Hybrid keeps its small pass when medium doesn't fit.
+  pub oom_fallback_model: Option<String>,
+  /// Hybrid: how the small and medium passes are combined.
+  pub merge: MergeMode,
+  /// Hybrid: write `<name>.merge.json` listing which pass each line came from.
+  pub merge_log: bool,
+  /// Write `<name>.reading.json` listing lines faster than `max_chars_per_second`, for
+  /// karaoke hosts to split by hand.
+  pub reading_speed_report: bool,
+  /// Reading speed above which the report lists a line.
+  pub max_chars_per_second: f64,
+  /// Debug: copy whisper's untouched outputs to `diagnostics/<run>` in app data instead of
+  /// deleting them with the temp dir, so merge and cleaning bugs can be reported.
+  pub keep_raw_output: bool,
+  /// Keep lines hand-edited since the last run when regenerating an LRC.
+  pub preserve_edits: bool,
+  /// Detect the language of each 30 s window and re-transcribe stretches in another
+  /// language with it (K-pop, Latin pop).
+  pub multilingual: bool,
+  /// Restore sentence case and basic punctuation in all-lowercase transcripts.
+  pub restore_punctuation: bool,
+  /// Unicode and punctuation cleanup of the written text.
+  pub text_cleanup: TextCleanup,
+  /// Byte encoding of the written files.
+  pub encoding: OutputEncoding,
+}
+
+/// Whisper decoding parameters for users chasing maximum accuracy.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Decoding {
+  /// Beam search width (`-bs`); larger is slower but more accurate.
+  pub beam_size: Option<u32>,
+  /// Candidates sampled when falling back to temperature sampling (`-bo`).
+  pub best_of: Option<u32>,
+  /// Initial sampling temperature (`-tp`); 0 is greedy.
+  pub temperature: Option<f32>,
+  /// Entropy above which a segment is re-decoded at a higher temperature (`-et`).
+  pub entropy_threshold: Option<f32>,
+}
+
+impl Default for GenerateOptions {
+  fn default() -> Self {
+    Self {
+      vad: true,
+      instrumental_marker: "(Instrumental)".into(),
+      chunk_seconds: 20 * 60,
+      chunk_overlap_seconds: 15,
+      speed_up: 1.0,
+      gap_marker_seconds: 0,
+      gap_marker_text: "♪".into(),
+      countdown: CountdownMode::Off,
+      countdown_gap_seconds: 10,
+      refine_onsets: false,
+      onset_window_ms: 500,
+      snap_to_beats: false,
+      beat_tolerance_ms: 120,
+      reference_lrc: None,
+      diarize: false,
+      profile: Profile::Lyrics,
+      output_format: None,
+      language: None,
+      line_max_chars: 120,
+      line_join_pause_ms: 700,
+      min_line_ms: 0,
+      max_line_ms: 0,
+      wrap_width: 0,
+      romanize: RomanizeMode::Off,
+      bilingual: false,
+      prompt_from_tags: true,
+      prompt_hint: None,
+      decoding: Decoding::default(),
+      extra_whisper_args: Vec::new(),
+      extra_ffmpeg_args: Vec::new(),
+      audio_stream: None,
+      center_channel_only: false,
+      track_name_template: "{track} - {title}".into(),
+      chapters: ChapterMode::Off,
+      sections: SectionMode::Off,
+      existing_lyrics: ExistingLyrics::Regenerate,
+      cache: true,
+      wav_cache_mb: 2048,
+      pipe_conversion: false,
+      dedupe: true,
+      backend: Backend::Auto,
+      gpu_index: None,
+      stall_timeout_seconds: 300,
+      oom_fallback_model: Some("small".into()),
+      merge: MergeMode::HybridPlus,
+      merge_log: false,
+      reading_speed_report: false,
+      max_chars_per_second: validate::DEFAULT_MAX_CPS,
+      keep_raw_output: false,
+      preserve_edits: true,
+      multilingual: false,
+      restore_punctuation: false,
+      text_cleanup: TextCleanup::default(),
+      encoding: OutputEncoding::Utf8,
+    }
+  }
+}
+
+impl GenerateOptions {
+  pub fn output_format(&self) -> OutputFormat {
+    self.output_format.unwrap_or(match self.profile {
+      Profile::Lyrics => OutputFormat::Lrc,
+      Profile::Speech => OutputFormat::Srt,
+    })
+  }
+}
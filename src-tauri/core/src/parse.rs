@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use crate::lrc::LrcLine;
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+  pub start_ms: u64,
+  pub end_ms: u64,
+  pub text: String,
+  /// tinydiarize: a different speaker starts after this segment.
+  pub speaker_turn_next: bool,
+}
+
+pub fn read_whispercpp_json(path: &Path) -> Result<Vec<Segment>, String> {
+  let raw = std::fs::read_to_string(path).map_err(|e| format!("Read JSON failed: {e}"))?;
+  let v: serde_json::Value = serde_json::from_str(&raw).map_err(|e| format!("JSON parse failed: {e}"))?;
+
+  // Find the first array anywhere in the JSON that looks like "segments".
+  let segs = find_segments_array(&v).ok_or_else(|| {
+    let top_keys = v
+      .as_object()
+      .map(|o| o.keys().cloned().collect::<Vec<_>>().join(", "))
+      .unwrap_or_else(|| "(not an object)".to_string());
+
+    format!("Could not locate a segments-like array in JSON. Top-level keys: {top_keys}")
+  })?;
+
+  let mut out = Vec::with_capacity(segs.len());
+
+  for s in segs {
+    if !s.is_object() {
+      continue;
+    }
+
+    let text = s
+      .get("text")
+      .and_then(|t| t.as_str())
+      .unwrap_or("")
+      .trim()
+      .to_string();
+
+    if text.is_empty() {
+      continue;
+    }
+
+    let offsets = s.get("offsets");
+    let (start_ms, end_ms) = if let Some(o) = offsets.filter(|o| o.get("from").is_some() && o.get("to").is_some()) {
+      // milliseconds (whisper.cpp `-oj`: "offsets": { "from", "to" })
+      let from = o.get("from").and_then(|n| n.as_i64()).unwrap_or(0).max(0) as u64;
+      let to = o.get("to").and_then(|n| n.as_i64()).unwrap_or(0).max(0) as u64;
+      (from, to.max(from))
+    } else if s.get("t0").is_some() && s.get("t1").is_some() {
+      // centiseconds -> ms (common whisper.cpp format)
+      let t0 = s.get("t0").and_then(|n| n.as_i64()).unwrap_or(0).max(0) as u64;
+      let t1 = s.get("t1").and_then(|n| n.as_i64()).unwrap_or(0).max(0) as u64;
+      (t0 * 10, t1 * 10)
+    } else if s.get("start").is_some() && s.get("end").is_some() {
+      // seconds -> ms (common alternative)
+      let start = s.get("start").and_then(|n| n.as_f64()).unwrap_or(0.0).max(0.0);
+      let end = s.get("end").and_then(|n| n.as_f64()).unwrap_or(start).max(start);
+      ((start * 1000.0) as u64, (end * 1000.0) as u64)
+    } else {
+      // Unknown timing format for this entry
+      continue;
+    };
+
+    let speaker_turn_next = s
+      .get("speaker_turn_next")
+      .and_then(|b| b.as_bool())
+      .unwrap_or(false);
+
+    out.push(Segment { start_ms, end_ms, text, speaker_turn_next });
+  }
+
+  if out.is_empty() {
+    return Err("Found a segments-like array, but parsed zero usable segments (no timing/text).".into());
+  }
+
+  Ok(out)
+}
+
+/// Give whisper's LRC lines the ends of the JSON segments they came from, which the LRC
+/// leaves out. Matched by start, which the LRC rounds to centiseconds.
+pub fn attach_segment_ends(lines: &mut [LrcLine], segments: &[Segment]) {
+  for l in lines {
+    let i = segments.partition_point(|s| (s.start_ms as i64) < l.ms - 10);
+    if let Some(s) = segments.get(i).filter(|s| (s.start_ms as i64 - l.ms).abs() <= 10) {
+      l.end_ms = Some(s.end_ms as i64).filter(|&e| e > l.ms);
+    }
+  }
+}
+
+// Recursively search JSON for an array whose elements look like whisper segments.
+// A "segment-like" object has `text` and either `offsets`, (`t0`+`t1`) or (`start`+`end`).
+fn find_segments_array(v: &serde_json::Value) -> Option<&Vec<serde_json::Value>> {
+  match v {
+    serde_json::Value::Array(arr) => {
+      if looks_like_segments_array(arr) {
+        return Some(arr);
+      }
+      // Search inside elements
+      for item in arr {
+        if let Some(found) = find_segments_array(item) {
+          return Some(found);
+        }
+      }
+      None
+    }
+    serde_json::Value::Object(map) => {
+      // First: if there is a direct "segments" key, check it.
+      if let Some(serde_json::Value::Array(arr)) = map.get("segments") {
+        if looks_like_segments_array(arr) {
+          return Some(arr);
+        }
+      }
+
+      // Otherwise: search all values recursively.
+      for (_k, val) in map {
+        if let Some(found) = find_segments_array(val) {
+          return Some(found);
+        }
+      }
+      None
+    }
+    _ => None,
+  }
+}
+
+fn looks_like_segments_array(arr: &[serde_json::Value]) -> bool {
+  // Need at least one object that matches the segment pattern.
+  for v in arr.iter().take(10) {
+    if let serde_json::Value::Object(m) = v {
+      let has_text = m.get("text").and_then(|t| t.as_str()).map(|s| !s.trim().is_empty()).unwrap_or(false);
+
+      let has_t0t1 = m.get("t0").is_some() && m.get("t1").is_some();
+      let has_startend = m.get("start").is_some() && m.get("end").is_some();
+      let has_offsets = m.get("offsets").map(|o| o.is_object()).unwrap_or(false);
+
+      if has_text && (has_t0t1 || has_startend || has_offsets) {
+        return true;
+      }
+    }
+  }
+  false
+}
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Tests run in parallel threads, so each call gets its own file.
+  fn read(json: &str) -> Result<Vec<Segment>, String> {
+    static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("lyrictime-parse-{}-{n}.json", std::process::id()));
+    std::fs::write(&path, json).unwrap();
+    let out = read_whispercpp_json(&path);
+    let _ = std::fs::remove_file(&path);
+    out
+  }
+
+  #[test]
+  fn reads_whisper_cpp_offsets() {
+    let segs = read(
+      r#"{"result":{"language":"en"},"transcription":[
+        {"offsets":{"from":1000,"to":2500},"text":" hello","speaker_turn_next":true},
+        {"offsets":{"from":3000,"to":4000},"text":"  "}
+      ]}"#,
+    )
+    .unwrap();
+    assert_eq!(segs.len(), 1);
+    assert_eq!((segs[0].start_ms, segs[0].end_ms), (1000, 2500));
+    assert_eq!(segs[0].text, "hello");
+    assert!(segs[0].speaker_turn_next);
+  }
+
+  #[test]
+  fn reads_centisecond_and_second_timings() {
+    let segs = read(r#"{"segments":[{"t0":150,"t1":300,"text":"a"}]}"#).unwrap();
+    assert_eq!((segs[0].start_ms, segs[0].end_ms), (1500, 3000));
+    let segs = read(r#"[{"start":1.5,"end":2.25,"text":"b"}]"#).unwrap();
+    assert_eq!((segs[0].start_ms, segs[0].end_ms), (1500, 2250));
+  }
+
+  #[test]
+  fn rejects_json_without_segments() {
+    assert!(read(r#"{"foo":[1,2,3]}"#).unwrap_err().contains("Top-level keys: foo"));
+  }
+
+  #[test]
+  fn attaches_ends_by_start() {
+    let seg = |start_ms, end_ms| Segment { start_ms, end_ms, text: String::new(), speaker_turn_next: false };
+    let mut lines = vec![
+      LrcLine { ms: 1000, text: "a".into(), end_ms: None },
+      LrcLine { ms: 5000, text: "b".into(), end_ms: None },
+    ];
+    attach_segment_ends(&mut lines, &[seg(1004, 2500), seg(7000, 8000)]);
+    assert_eq!(lines[0].end_ms, Some(2500));
+    assert_eq!(lines[1].end_ms, None);
+  }
+}
@@ -0,0 +1,151 @@
+use crate::lrc::{parse_lrc, render, LrcLine};
+use crate::options::{GenerateOptions, OutputFormat, Profile, RomanizeMode, SectionMode};
+use crate::progress::{Progress, ProgressEvent};
+use crate::sections::Section;
+use crate::validate::FastLine;
+use crate::vocabulary::VocabularyWord;
+use crate::{align, diarize, duration, postprocess, punctuate, romanize, sections, textnorm, validate, vocabulary};
+
+/// Results of the caller's optional analysis passes, applied by [`finish`].
+#[derive(Default)]
+pub struct Extras {
+  pub vocabulary: Vec<VocabularyWord>,
+  /// Synced lyrics whose text is kept and re-timed to the transcription.
+  pub reference: Option<Vec<LrcLine>>,
+  /// Vocal onsets (ms) line starts are snapped to.
+  pub onsets: Option<Vec<i64>>,
+  /// Beat times (ms) line starts are snapped to.
+  pub beats: Option<Vec<i64>>,
+  /// Speaker changes as `(start_ms, speaker)`.
+  pub speaker_turns: Option<Vec<(i64, usize)>>,
+  /// English translation lines for bilingual output.
+  pub translation: Option<Vec<LrcLine>>,
+  /// Chapter starts as `(start_ms, title)`, written as marker lines.
+  pub chapters: Vec<(i64, String)>,
+}
+
+/// Rendered output for one run.
+pub struct Finished {
+  pub format: OutputFormat,
+  pub text: String,
+  /// Romanized copy when `RomanizeMode::Sidecar` is on.
+  pub romanized: Option<String>,
+  /// Lines too fast to sing or read when `reading_speed_report` is on.
+  pub fast_lines: Vec<FastLine>,
+  /// Verse/chorus/bridge labels unless `sections` is off.
+  pub sections: Vec<Section>,
+}
+
+/// Apply the output post-processing passes to a cleaned transcript and render it.
+pub fn finish(
+  lrc: &str,
+  options: &GenerateOptions,
+  extras: &Extras,
+  progress: &dyn Progress,
+) -> Result<Finished, String> {
+  let mut lines = vocabulary::correct_lines(parse_lrc(lrc), &extras.vocabulary);
+
+  // Reference lyrics: keep their text, but move them onto this audio's timeline.
+  if let Some(reference) = &extras.reference {
+    let map = align::estimate(reference, &lines)
+      .ok_or("Reference lyrics could not be aligned with the transcription")?;
+
+    progress.report(ProgressEvent::Stage {
+      stage: "Aligning".into(),
+      detail: Some(format!(
+        "Reference offset {:+.0} ms, stretch {:.4} ({} anchors)",
+        map.offset_ms, map.stretch, map.support
+      )),
+    });
+    lines = align::apply(reference, &map);
+  }
+
+  if let Some(onsets) = extras.onsets.as_deref().filter(|_| options.refine_onsets) {
+    progress.report(ProgressEvent::Stage {
+      stage: "Refining".into(),
+      detail: Some("Snapping line starts to vocal onsets".into()),
+    });
+    lines = postprocess::snap_to_onsets(lines, onsets, options.onset_window_ms as i64);
+  }
+
+  if let Some(beats) = extras.beats.as_deref().filter(|_| options.snap_to_beats) {
+    progress.report(ProgressEvent::Stage {
+      stage: "Refining".into(),
+      detail: Some(format!("Snapping line starts to the beat ({} beats)", beats.len())),
+    });
+    lines = postprocess::snap_to_beats(lines, beats, options.beat_tolerance_ms as i64);
+  }
+
+  if let Some(turns) = &extras.speaker_turns {
+    lines = diarize::label_lines(lines, turns);
+  }
+
+  // Speech: whisper splits mid-sentence; readers prefer whole sentences.
+  if options.profile == Profile::Speech {
+    lines = postprocess::join_sentences(lines, options.line_max_chars, options.line_join_pause_ms);
+  }
+
+  // Reference lyrics are the user's own text; leave their casing alone.
+  if options.restore_punctuation && extras.reference.is_none() {
+    lines = punctuate::restore(lines, options.language.as_deref(), options.profile == Profile::Speech);
+  }
+
+  lines = duration::merge_short_lines(lines, options.min_line_ms);
+  lines = postprocess::wrap_long_lines(lines, options.wrap_width);
+
+  // Labelled from the lyrics alone, before markers, countdowns and translations are mixed in.
+  let sections = if options.sections == SectionMode::Off {
+    Vec::new()
+  } else {
+    sections::label_sections(&lines)
+  };
+
+  let format = options.output_format();
+  // Gap markers are an LRC convention; subtitle cues already end on their own.
+  if options.gap_marker_seconds > 0 && format == OutputFormat::Lrc {
+    lines = postprocess::insert_gap_markers(
+      lines,
+      options.gap_marker_seconds as i64 * 1000,
+      &options.gap_marker_text,
+    );
+  }
+  if format == OutputFormat::Lrc {
+    lines = duration::cap_long_lines(lines, options.max_line_ms);
+    lines = postprocess::insert_countdowns(lines, options.countdown, options.countdown_gap_seconds as i64 * 1000);
+  }
+
+  let language = options.language.as_deref();
+  let mut romanized = None;
+  match options.romanize {
+    RomanizeMode::Off => {}
+    RomanizeMode::Replace => lines = romanize::romanize_lines(&lines, language),
+    RomanizeMode::Sidecar => {
+      let sidecar = textnorm::clean_lines(romanize::romanize_lines(&lines, language), &options.text_cleanup);
+      romanized = Some(render(format, &sidecar, options.max_line_ms));
+    }
+  }
+
+  if let Some(translation) = &extras.translation {
+    lines = postprocess::interleave_translation(lines, translation, format == OutputFormat::Lrc);
+  }
+
+  if !extras.chapters.is_empty() {
+    lines = postprocess::insert_chapter_markers(lines, &extras.chapters);
+  }
+
+  lines = textnorm::clean_lines(lines, &options.text_cleanup);
+
+  let fast_lines = if options.reading_speed_report {
+    validate::fast_lines(&lines, options.max_chars_per_second)
+  } else {
+    Vec::new()
+  };
+
+  Ok(Finished {
+    format,
+    text: render(format, &lines, options.max_line_ms),
+    romanized,
+    fast_lines,
+    sections,
+  })
+}
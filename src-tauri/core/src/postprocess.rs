@@ -0,0 +1,259 @@
+use crate::lrc::{word_count, LrcLine};
+use crate::options::CountdownMode;
+
+// The end of a sung line: whisper's when it's known, else estimated from its length
+// (LRC files only store line starts).
+pub fn estimated_end_ms(l: &LrcLine) -> i64 {
+  l.end_ms
+    .filter(|&e| e > l.ms)
+    .unwrap_or_else(|| l.ms + (word_count(&l.text) as i64 * 450).clamp(1500, 5000))
+}
+
+/// Insert `marker` lines where the gap to the next line exceeds `min_gap_ms`,
+/// so players stop highlighting the last lyric during a solo.
+pub fn insert_gap_markers(lines: Vec<LrcLine>, min_gap_ms: i64, marker: &str) -> Vec<LrcLine> {
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len());
+
+  for (i, l) in lines.iter().enumerate() {
+    out.push(l.clone());
+
+    let Some(next) = lines.get(i + 1) else {
+      continue;
+    };
+    if next.ms - l.ms <= min_gap_ms {
+      continue;
+    }
+
+    let at = estimated_end_ms(l);
+    if at < next.ms {
+      out.push(LrcLine {
+        ms: at,
+        text: marker.trim().to_string(),
+        end_ms: None,
+      });
+    }
+  }
+
+  out
+}
+
+/// Count in the first sung line and every line after a gap of at least `min_gap_ms`, one
+/// countdown line per second. Steps that would cut off the previous line are left out.
+pub fn insert_countdowns(lines: Vec<LrcLine>, mode: CountdownMode, min_gap_ms: i64) -> Vec<LrcLine> {
+  let steps: [&str; 3] = match mode {
+    CountdownMode::Off => return lines,
+    CountdownMode::Numbers => ["3\u{2026}", "2\u{2026}", "1\u{2026}"],
+    CountdownMode::Dots => ["\u{25CF} \u{25CF} \u{25CF}", "\u{25CF} \u{25CF}", "\u{25CF}"],
+  };
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len());
+  let mut prev_sung: Option<&LrcLine> = None;
+
+  for l in &lines {
+    // Gap markers and cues don't count as singing.
+    if l.text.chars().any(char::is_alphanumeric) {
+      let gap = prev_sung.is_none_or(|p| l.ms - p.ms >= min_gap_ms);
+      let free_from = prev_sung.map_or(0, estimated_end_ms).max(out.last().map_or(0, |o| o.ms + 1));
+      if gap {
+        for (i, text) in steps.iter().enumerate() {
+          let ms = l.ms - (steps.len() - i) as i64 * 1000;
+          if ms >= free_from {
+            out.push(LrcLine { ms, text: text.to_string(), end_ms: None });
+          }
+        }
+      }
+      prev_sung = Some(l);
+    }
+    out.push(l.clone());
+  }
+
+  out
+}
+
+/// Add a `== Title ==` line at each chapter start, ahead of any line at the same time.
+pub fn insert_chapter_markers(lines: Vec<LrcLine>, chapters: &[(i64, String)]) -> Vec<LrcLine> {
+  let marker = |(ms, title): &(i64, String)| LrcLine {
+    ms: *ms,
+    text: format!("== {} ==", title.trim()),
+    end_ms: None,
+  };
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len() + chapters.len());
+  let mut chapters = chapters.iter().peekable();
+
+  for l in lines {
+    while let Some(chapter) = chapters.next_if(|(ms, _)| *ms <= l.ms) {
+      out.push(marker(chapter));
+    }
+    out.push(l);
+  }
+  out.extend(chapters.map(marker));
+
+  out
+}
+
+/// Snap each line start to the nearest audio onset within the window.
+///
+/// Whisper tends to stamp lines late, so the search reaches `window_ms` back but only a
+/// quarter of that forward. A line never moves onto or before the previous line.
+pub fn snap_to_onsets(mut lines: Vec<LrcLine>, onsets: &[i64], window_ms: i64) -> Vec<LrcLine> {
+  if onsets.is_empty() {
+    return lines;
+  }
+
+  let mut prev_ms = i64::MIN;
+  for l in lines.iter_mut() {
+    let lo = (l.ms - window_ms).max(prev_ms + 1);
+    let hi = l.ms + window_ms / 4;
+
+    let best = onsets
+      .iter()
+      .copied()
+      .filter(|&o| o >= lo && o <= hi)
+      .min_by_key(|&o| (o - l.ms).abs());
+
+    if let Some(o) = best {
+      l.ms = o;
+    }
+    prev_ms = l.ms;
+  }
+
+  lines
+}
+
+/// Snap line starts to the nearest beat within `tolerance_ms`; lines that land on the beat
+/// feel right in karaoke apps. A line never moves onto or before the previous line.
+pub fn snap_to_beats(mut lines: Vec<LrcLine>, beats: &[i64], tolerance_ms: i64) -> Vec<LrcLine> {
+  let mut prev_ms = i64::MIN;
+  for l in lines.iter_mut() {
+    let i = beats.partition_point(|&b| b < l.ms);
+    let nearest = [i.checked_sub(1), Some(i)]
+      .into_iter()
+      .flatten()
+      .filter_map(|i| beats.get(i).copied())
+      .filter(|&b| b > prev_ms && (b - l.ms).abs() <= tolerance_ms)
+      .min_by_key(|&b| (b - l.ms).abs());
+
+    if let Some(b) = nearest {
+      l.ms = b;
+    }
+    prev_ms = l.ms;
+  }
+
+  lines
+}
+
+/// Join consecutive lines into sentences: a line that doesn't end in sentence
+/// punctuation absorbs the next one if it follows within `max_pause_ms` and the
+/// result stays under `max_chars`.
+pub fn join_sentences(lines: Vec<LrcLine>, max_chars: usize, max_pause_ms: i64) -> Vec<LrcLine> {
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len());
+
+  for l in lines {
+    if let Some(prev) = out.last_mut() {
+      let open = !prev.text.trim_end().ends_with(['.', '!', '?']);
+      let close = l.ms - estimated_end_ms(prev) <= max_pause_ms;
+      let fits = prev.text.chars().count() + 1 + l.text.chars().count() <= max_chars;
+      if open && close && fits {
+        prev.text = format!("{} {}", prev.text.trim_end(), l.text.trim());
+        prev.end_ms = l.end_ms;
+        continue;
+      }
+    }
+    out.push(l);
+  }
+
+  out
+}
+
+// Char offsets where `text` may be broken: after each space, or between any two
+// characters in scripts written without spaces.
+fn break_points(text: &str) -> Vec<usize> {
+  let chars: Vec<char> = text.chars().collect();
+  if chars.contains(&' ') {
+    (1..chars.len()).filter(|&i| chars[i - 1] == ' ').collect()
+  } else {
+    (1..chars.len()).collect()
+  }
+}
+
+/// Split lines longer than `max_chars` into balanced parts at word boundaries. Each
+/// part starts at a time interpolated by character count across the sung line, which
+/// ends at the next line or the estimated end, whichever is first.
+pub fn wrap_long_lines(lines: Vec<LrcLine>, max_chars: usize) -> Vec<LrcLine> {
+  if max_chars == 0 {
+    return lines;
+  }
+  let mut out: Vec<LrcLine> = Vec::with_capacity(lines.len());
+
+  for (i, l) in lines.iter().enumerate() {
+    let text = l.text.trim();
+    let total = text.chars().count();
+    if total <= max_chars {
+      out.push(l.clone());
+      continue;
+    }
+
+    let points = break_points(text);
+    let parts = total.div_ceil(max_chars);
+    let mut cuts: Vec<usize> = Vec::with_capacity(parts - 1);
+    for k in 1..parts {
+      let target = total * k / parts;
+      let after_last = |p: &&usize| cuts.last().is_none_or(|last| **p > *last);
+      if let Some(&p) = points.iter().filter(after_last).min_by_key(|p| p.abs_diff(target)) {
+        cuts.push(p);
+      }
+    }
+
+    let end = lines.get(i + 1).map_or(estimated_end_ms(l), |next| next.ms.min(estimated_end_ms(l)));
+    let duration = (end - l.ms).max(0);
+    let chars: Vec<char> = text.chars().collect();
+    let bounds: Vec<usize> = [0].into_iter().chain(cuts).chain([total]).collect();
+    let at = |i: usize| l.ms + duration * i as i64 / total as i64;
+    for pair in bounds.windows(2) {
+      let part: String = chars[pair[0]..pair[1]].iter().collect();
+      // Each part ends where the next starts; the last one where the line did.
+      let end_ms = if pair[1] == total { l.end_ms } else { Some(at(pair[1])) };
+      out.push(LrcLine {
+        ms: at(pair[0]),
+        text: part.trim().to_string(),
+        end_ms,
+      });
+    }
+  }
+
+  out
+}
+
+/// Attach translated text to the original lines.
+///
+/// The translate pass segments differently, so each translated line goes to the
+/// original line that is active when it starts. With `interleave` (LRC) the
+/// translation becomes its own line at the same timestamp, the convention lyric
+/// players use; otherwise (subtitles) it's a second row of the same cue.
+pub fn interleave_translation(lines: Vec<LrcLine>, translated: &[LrcLine], interleave: bool) -> Vec<LrcLine> {
+  let mut extra: Vec<Vec<&str>> = vec![Vec::new(); lines.len()];
+
+  for t in translated {
+    let owner = lines
+      .iter()
+      .rposition(|l| !l.text.trim().is_empty() && l.ms <= t.ms + 500)
+      .or_else(|| lines.iter().position(|l| !l.text.trim().is_empty()));
+    if let Some(i) = owner {
+      extra[i].push(t.text.trim());
+    }
+  }
+
+  let mut out = Vec::with_capacity(lines.len() * 2);
+  for (l, tr) in lines.into_iter().zip(extra) {
+    if tr.is_empty() {
+      out.push(l);
+    } else if interleave {
+      let (ms, end_ms) = (l.ms, l.end_ms);
+      out.push(l);
+      out.push(LrcLine { ms, text: tr.join(" "), end_ms });
+    } else {
+      let text = format!("{}\n{}", l.text.trim(), tr.join(" "));
+      out.push(LrcLine { ms: l.ms, text, end_ms: l.end_ms });
+    }
+  }
+  out
+}
@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+
+use crate::lrc::{normalize_text_key, LrcLine};
+
+/// A regenerated line this close to a kept edit is the machine version of it.
+const SAME_LINE_MS: i64 = 500;
+/// Further away, it still is if the words mostly match (the user re-timed it).
+const MOVED_LINE_MS: i64 = 3000;
+
+fn key(l: &LrcLine) -> (i64, String) {
+  (l.ms, l.text.trim().to_string())
+}
+
+/// Lines of `current` that aren't exactly (time and text) among the `generated` lines
+/// written last time: the user typed or re-timed them. Whole-file shifts and scaling
+/// must be applied to `generated` too, or every line counts as edited.
+pub fn edited_lines(current: &[LrcLine], generated: &[LrcLine]) -> Vec<LrcLine> {
+  let machine: HashSet<(i64, String)> = generated.iter().map(key).collect();
+  current.iter().filter(|l| !machine.contains(&key(l))).cloned().collect()
+}
+
+fn word_overlap(a: &str, b: &str) -> f64 {
+  let words = |s: &str| -> HashSet<String> { normalize_text_key(s).split_whitespace().map(str::to_string).collect() };
+  let (a, b) = (words(a), words(b));
+  if a.is_empty() || b.is_empty() {
+    return 0.0;
+  }
+  a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// Put `edits` into freshly generated `lines`, dropping the regenerated lines they stand in for.
+pub fn keep_edits(lines: Vec<LrcLine>, edits: &[LrcLine]) -> Vec<LrcLine> {
+  let mut out: Vec<LrcLine> = lines
+    .into_iter()
+    .filter(|l| {
+      !edits.iter().any(|e| {
+        let d = (l.ms - e.ms).abs();
+        d <= SAME_LINE_MS || (d <= MOVED_LINE_MS && word_overlap(&l.text, &e.text) >= 0.5)
+      })
+    })
+    .collect();
+  out.extend(edits.iter().cloned());
+  out.sort_by_key(|l| l.ms);
+  out
+}
@@ -0,0 +1,68 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind")]
+pub enum ProgressEvent {
+  #[serde(rename = "stage")]
+  Stage { stage: String, detail: Option<String> },
+
+  #[serde(rename = "log")]
+  Log { line: String },
+
+  #[serde(rename = "done", rename_all = "camelCase")]
+  Done {
+    output_path: String,
+    instrumental: bool,
+    /// Copies of whisper's untouched outputs when `keep_raw_output` is on.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    raw_outputs: Vec<String>,
+  },
+
+  /// Binaries and platform of a run (verbose diagnostics).
+  #[serde(rename = "diagnostics", rename_all = "camelCase")]
+  Diagnostics {
+    app_version: String,
+    platform: String,
+    whisper_path: String,
+    whisper_version: Option<String>,
+    ffmpeg_path: String,
+    ffmpeg_version: Option<String>,
+  },
+
+  /// The output couldn't be written where asked and went to `to` instead.
+  #[serde(rename = "redirected")]
+  Redirected { from: String, to: String, reason: String },
+
+  /// Structured failure the frontend can act on (e.g. offer a redownload).
+  #[serde(rename = "error")]
+  Error {
+    code: String,
+    message: String,
+    detail: Option<serde_json::Value>,
+  },
+}
+
+/// Receives progress from long-running work. Implemented for closures and for
+/// channel senders, so callers can either handle events inline or drain them elsewhere.
+pub trait Progress {
+  fn report(&self, event: ProgressEvent);
+}
+
+impl<F: Fn(ProgressEvent)> Progress for F {
+  fn report(&self, event: ProgressEvent) {
+    self(event)
+  }
+}
+
+impl Progress for std::sync::mpsc::Sender<ProgressEvent> {
+  fn report(&self, event: ProgressEvent) {
+    let _ = self.send(event);
+  }
+}
+
+/// Discards everything.
+pub struct Silent;
+
+impl Progress for Silent {
+  fn report(&self, _event: ProgressEvent) {}
+}
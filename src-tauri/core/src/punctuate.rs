@@ -0,0 +1,85 @@
+use crate::lrc::LrcLine;
+
+// Openers that make an English sentence a question.
+const QUESTION_WORDS: &[&str] = &[
+  "who", "what", "when", "where", "why", "how", "which", "whose", "do", "does", "did", "is", "are", "was", "were",
+  "can", "could", "would", "will", "should", "shall", "have", "has", "am", "isn't", "aren't", "don't", "doesn't",
+  "didn't", "won't", "can't", "couldn't", "wouldn't", "shouldn't",
+];
+
+fn is_english(language: Option<&str>) -> bool {
+  language.is_none_or(|l| l.eq_ignore_ascii_case("auto") || l.to_ascii_lowercase().starts_with("en"))
+}
+
+// `v1: ` speaker labels stay in front, untouched.
+fn split_label(text: &str) -> (&str, &str) {
+  let digits = text.strip_prefix('v').map(|r| r.len() - r.trim_start_matches(|c: char| c.is_ascii_digit()).len());
+  match digits {
+    Some(n) if n > 0 && text[1 + n..].starts_with(": ") => text.split_at(1 + n + 2),
+    _ => ("", text),
+  }
+}
+
+fn bare(word: &str) -> String {
+  word
+    .trim_matches(|c: char| !c.is_alphanumeric() && c != '\'' && c != '\u{2019}')
+    .to_lowercase()
+}
+
+// "i", "i'm", "i'll", ... with any surrounding punctuation kept.
+fn capital_i(word: &str) -> String {
+  let b = bare(word).replace('\u{2019}', "'");
+  if matches!(b.as_str(), "i" | "i'm" | "i'll" | "i've" | "i'd") {
+    word.replacen('i', "I", 1)
+  } else {
+    word.to_string()
+  }
+}
+
+fn capitalize_first(text: &str) -> String {
+  let mut chars = text.chars();
+  match chars.next() {
+    Some(first) if first.is_lowercase() => first.to_uppercase().chain(chars).collect(),
+    _ => text.to_string(),
+  }
+}
+
+fn restore_text(text: &str, english: bool, sentences: bool) -> String {
+  let (label, body) = split_label(text.trim());
+  // Cues, markers and empty gap lines.
+  if !body.starts_with(char::is_alphabetic) {
+    return text.to_string();
+  }
+
+  let mut words: Vec<String> = body.split_whitespace().map(str::to_string).collect();
+  if english {
+    for i in 0..words.len() {
+      words[i] = capital_i(&words[i]);
+      // "i tried but it broke" -> "I tried, but it broke"; short lines read fine without.
+      if i >= 3 && words[i] == "but" && !words[i - 1].ends_with(|c: char| c.is_ascii_punctuation()) {
+        words[i - 1].push(',');
+      }
+    }
+  }
+  let mut body = capitalize_first(&words.join(" "));
+
+  if sentences && !body.ends_with(['.', '!', '?', '\u{2026}', ',', ';', ':', '-', '"', ')']) {
+    let question = english && words.first().is_some_and(|w| QUESTION_WORDS.contains(&bare(w).as_str()));
+    body.push(if question { '?' } else { '.' });
+  }
+  format!("{label}{body}")
+}
+
+/// Restore sentence case, English "I" and a few commas in transcripts that come back all
+/// lowercase (small models). With `sentences` (speech), lines also get end punctuation;
+/// song lines conventionally have none.
+pub fn restore(lines: Vec<LrcLine>, language: Option<&str>, sentences: bool) -> Vec<LrcLine> {
+  let english = is_english(language);
+  lines
+    .into_iter()
+    .map(|mut l| {
+      l.text = restore_text(&l.text, english, sentences);
+      l
+    })
+    .collect()
+}
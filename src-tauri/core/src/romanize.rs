@@ -0,0 +1,163 @@
+use pinyin::ToPinyin;
+
+use crate::lrc::LrcLine;
+
+fn is_kana(c: char) -> bool {
+  matches!(c, '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' | '\u{FF66}'..='\u{FF9F}')
+}
+
+fn is_han(c: char) -> bool {
+  matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}' | '\u{F900}'..='\u{FAFF}')
+}
+
+fn is_hangul(c: char) -> bool {
+  matches!(c, '\u{AC00}'..='\u{D7A3}')
+}
+
+fn is_cyrillic(c: char) -> bool {
+  matches!(c, '\u{0400}'..='\u{04FF}')
+}
+
+/// True if the text has anything this module would rewrite.
+pub fn needs_romanization(text: &str) -> bool {
+  text
+    .chars()
+    .any(|c| is_kana(c) || is_han(c) || is_hangul(c) || is_cyrillic(c))
+}
+
+/// Romanize one line. Han characters are read as Japanese when the line also has kana
+/// or the run's language is `ja`, otherwise as Mandarin (toneless pinyin).
+pub fn romanize(text: &str, language: Option<&str>) -> String {
+  let japanese = language.map(|l| l.eq_ignore_ascii_case("ja")).unwrap_or(false)
+    || text.chars().any(is_kana);
+
+  let text = if japanese && text.chars().any(|c| is_kana(c) || is_han(c)) {
+    kakasi::convert(text).romaji
+  } else {
+    pinyin_words(text)
+  };
+
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    if is_hangul(c) {
+      push_spaced(&mut out, &hangul_syllable(c));
+    } else if let Some(latin) = cyrillic_char(c) {
+      out.push_str(&latin);
+    } else {
+      out.push(c);
+    }
+  }
+
+  out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Romanize every line's text, keeping timings.
+pub fn romanize_lines(lines: &[LrcLine], language: Option<&str>) -> Vec<LrcLine> {
+  lines
+    .iter()
+    .map(|l| LrcLine {
+      ms: l.ms,
+      text: if needs_romanization(&l.text) {
+        romanize(&l.text, language)
+      } else {
+        l.text.clone()
+      },
+      end_ms: l.end_ms,
+    })
+    .collect()
+}
+
+// Each Han character becomes its own space-separated syllable.
+fn pinyin_words(text: &str) -> String {
+  let mut out = String::with_capacity(text.len() * 2);
+  for c in text.chars() {
+    match c.to_pinyin() {
+      Some(p) => push_spaced(&mut out, p.plain()),
+      None => out.push(c),
+    }
+  }
+  out
+}
+
+fn push_spaced(out: &mut String, syllable: &str) {
+  if !out.is_empty() && !out.ends_with(' ') {
+    out.push(' ');
+  }
+  out.push_str(syllable);
+  out.push(' ');
+}
+
+// Revised Romanization, syllable by syllable (no cross-syllable assimilation rules).
+fn hangul_syllable(c: char) -> String {
+  const INITIAL: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p", "h",
+  ];
+  const MEDIAL: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we", "wi",
+    "yu", "eu", "ui", "i",
+  ];
+  const FINAL: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p", "t",
+    "t", "ng", "t", "t", "k", "t", "p", "t",
+  ];
+
+  let idx = c as u32 - 0xAC00;
+  let (l, v, t) = (idx / (21 * 28), (idx % (21 * 28)) / 28, idx % 28);
+  format!("{}{}{}", INITIAL[l as usize], MEDIAL[v as usize], FINAL[t as usize])
+}
+
+// Scholarly-lite transliteration covering Russian, Ukrainian and Belarusian letters.
+fn cyrillic_char(c: char) -> Option<String> {
+  let lower = c.to_lowercase().next()?;
+  let latin = match lower {
+    'а' => "a",
+    'б' => "b",
+    'в' => "v",
+    'г' => "g",
+    'ґ' => "g",
+    'д' => "d",
+    'е' => "e",
+    'ё' => "yo",
+    'є' => "ye",
+    'ж' => "zh",
+    'з' => "z",
+    'и' => "i",
+    'і' => "i",
+    'ї' => "yi",
+    'й' => "y",
+    'к' => "k",
+    'л' => "l",
+    'м' => "m",
+    'н' => "n",
+    'о' => "o",
+    'п' => "p",
+    'р' => "r",
+    'с' => "s",
+    'т' => "t",
+    'у' => "u",
+    'ў' => "w",
+    'ф' => "f",
+    'х' => "kh",
+    'ц' => "ts",
+    'ч' => "ch",
+    'ш' => "sh",
+    'щ' => "shch",
+    'ъ' => "",
+    'ы' => "y",
+    'ь' => "",
+    'э' => "e",
+    'ю' => "yu",
+    'я' => "ya",
+    _ => return None,
+  };
+
+  if c.is_uppercase() {
+    // "Ж" -> "Zh": only the first Latin letter is capitalized.
+    let mut chars = latin.chars();
+    return Some(match chars.next() {
+      Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+      None => String::new(),
+    });
+  }
+  Some(latin.to_string())
+}
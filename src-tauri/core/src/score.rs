@@ -0,0 +1,115 @@
+use serde::Serialize;
+
+use crate::align;
+use crate::lrc::{self, parse_lrc, LrcLine};
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Score {
+  pub reference_words: usize,
+  pub substitutions: usize,
+  pub deletions: usize,
+  pub insertions: usize,
+  /// (substitutions + deletions + insertions) / reference words.
+  pub word_error_rate: f64,
+  /// Unset when the reference is plain text.
+  pub timing: Option<TimingStats>,
+}
+
+/// Start-time error of generated lines against the reference lines they match.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingStats {
+  pub reference_lines: usize,
+  pub matched_lines: usize,
+  pub mean_abs_ms: f64,
+  pub median_abs_ms: f64,
+  pub p90_abs_ms: f64,
+  pub max_abs_ms: f64,
+  /// Positive = generated lines start late on average.
+  pub mean_offset_ms: f64,
+}
+
+fn words(lines: &[LrcLine]) -> Vec<String> {
+  lines.iter().flat_map(|l| lrc::words(&l.text)).collect()
+}
+
+// Word-level edit distance split into (substitutions, deletions, insertions). Two rows,
+// so whole albums or podcasts don't need a full matrix.
+fn edit_counts(reference: &[String], hypothesis: &[String]) -> (usize, usize, usize) {
+  type Cell = (usize, usize, usize, usize); // cost, subs, dels, ins
+  let mut prev: Vec<Cell> = (0..=hypothesis.len()).map(|j| (j, 0, 0, j)).collect();
+  for (i, r) in reference.iter().enumerate() {
+    let mut row: Vec<Cell> = Vec::with_capacity(hypothesis.len() + 1);
+    row.push((i + 1, 0, i + 1, 0));
+    for (j, h) in hypothesis.iter().enumerate() {
+      let diag = prev[j];
+      let up = prev[j + 1];
+      let left = row[j];
+      let candidates = [
+        if r == h { diag } else { (diag.0 + 1, diag.1 + 1, diag.2, diag.3) },
+        (up.0 + 1, up.1, up.2 + 1, up.3),
+        (left.0 + 1, left.1, left.2, left.3 + 1),
+      ];
+      row.push(candidates.into_iter().min_by_key(|c| c.0).unwrap_or(diag));
+    }
+    prev = row;
+  }
+  let (_, s, d, i) = prev[hypothesis.len()];
+  (s, d, i)
+}
+
+// Linear interpolation between closest ranks.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+  let pos = (sorted.len() - 1) as f64 * p;
+  let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+  sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+fn timing(reference: &[LrcLine], generated: &[LrcLine]) -> TimingStats {
+  let deviations: Vec<f64> = align::text_anchors(reference, generated).iter().map(|(r, g)| g - r).collect();
+  let mut abs: Vec<f64> = deviations.iter().map(|d| d.abs()).collect();
+  abs.sort_by(f64::total_cmp);
+
+  let n = deviations.len();
+  let mean = |v: &[f64]| if v.is_empty() { 0.0 } else { v.iter().sum::<f64>() / v.len() as f64 };
+  TimingStats {
+    reference_lines: reference.len(),
+    matched_lines: n,
+    mean_abs_ms: mean(&abs),
+    median_abs_ms: if n == 0 { 0.0 } else { percentile(&abs, 0.5) },
+    p90_abs_ms: if n == 0 { 0.0 } else { percentile(&abs, 0.9) },
+    max_abs_ms: abs.last().copied().unwrap_or(0.0),
+    mean_offset_ms: mean(&deviations),
+  }
+}
+
+/// Compare generated LRC text against a reference: LRC (words and timing) or plain
+/// lyrics, one line per line (words only).
+pub fn score(generated: &str, reference: &str) -> Score {
+  let generated = parse_lrc(generated);
+  let timed_reference = parse_lrc(reference);
+  let reference_lines = if timed_reference.is_empty() {
+    reference
+      .lines()
+      .map(str::trim)
+      .filter(|l| !l.is_empty() && !l.starts_with('['))
+      .map(|text| LrcLine { ms: 0, text: text.to_string(), end_ms: None })
+      .collect()
+  } else {
+    timed_reference.clone()
+  };
+
+  let ref_words = words(&reference_lines);
+  let (substitutions, deletions, insertions) = edit_counts(&ref_words, &words(&generated));
+  let errors = substitutions + deletions + insertions;
+
+  Score {
+    reference_words: ref_words.len(),
+    substitutions,
+    deletions,
+    insertions,
+    word_error_rate: if ref_words.is_empty() { 0.0 } else { errors as f64 / ref_words.len() as f64 },
+    timing: (!timed_reference.is_empty()).then(|| timing(&timed_reference, &generated)),
+  }
+}
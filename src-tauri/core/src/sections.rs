@@ -0,0 +1,189 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::lrc::{build_chant_set, normalize_text_key, parse_ts_to_ms, LrcLine};
+
+/// A pause this long between lines always starts a new section.
+const SECTION_PAUSE_MS: i64 = 6000;
+/// Blocks sharing this much of their text are the same section sung again.
+const SAME_SECTION: f64 = 0.5;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+  Verse,
+  Chorus,
+  Bridge,
+}
+
+impl SectionKind {
+  fn name(self) -> &'static str {
+    match self {
+      SectionKind::Verse => "Verse",
+      SectionKind::Chorus => "Chorus",
+      SectionKind::Bridge => "Bridge",
+    }
+  }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Section {
+  pub kind: SectionKind,
+  /// "Verse 2", "Chorus 3".
+  pub label: String,
+  pub start_ms: i64,
+  /// The next section's start, or the last line's for the final one.
+  pub end_ms: i64,
+  pub lines: usize,
+}
+
+struct Block {
+  start_ms: i64,
+  keys: Vec<String>,
+  repeated: bool,
+}
+
+// Gap markers, "♪" and cue lines separate sections rather than belong to one.
+fn is_break(text: &str) -> bool {
+  !text.chars().any(char::is_alphanumeric)
+}
+
+fn similarity(a: &[String], b: &[String]) -> f64 {
+  let a: HashSet<&String> = a.iter().collect();
+  let b: HashSet<&String> = b.iter().collect();
+  let union = a.union(&b).count();
+  if union == 0 {
+    return 0.0;
+  }
+  a.intersection(&b).count() as f64 / union as f64
+}
+
+// Split at pauses and breaks, and wherever the lyrics go from lines sung once to lines
+// sung again (or back): that is where a chorus starts or ends.
+fn blocks(lines: &[LrcLine]) -> Vec<Block> {
+  let mut counts: HashMap<String, usize> = HashMap::new();
+  for l in lines {
+    *counts.entry(normalize_text_key(&l.text)).or_insert(0) += 1;
+  }
+  let chant = build_chant_set(lines);
+  let repeated = |key: &String| counts.get(key).is_some_and(|&c| c >= 2) || chant.contains(key);
+
+  let mut out: Vec<Block> = Vec::new();
+  let mut prev_ms: Option<i64> = None;
+  let mut split = true;
+  for l in lines {
+    if is_break(&l.text) {
+      split = true;
+      continue;
+    }
+    let key = normalize_text_key(&l.text);
+    let line_repeated = repeated(&key);
+    let paused = prev_ms.is_some_and(|p| l.ms - p >= SECTION_PAUSE_MS);
+    match out.last_mut() {
+      Some(b) if !split && !paused && b.repeated == line_repeated => b.keys.push(key),
+      _ => out.push(Block {
+        start_ms: l.ms,
+        keys: vec![key],
+        repeated: line_repeated,
+      }),
+    }
+    prev_ms = Some(l.ms);
+    split = false;
+  }
+  out
+}
+
+/// Label verse, chorus and bridge sections from how the lyrics repeat.
+///
+/// The chorus is the most often repeated block; blocks sung once are verses, except the
+/// last one between two later choruses, which is the bridge. Songs without repetition
+/// are one long verse.
+pub fn label_sections(lines: &[LrcLine]) -> Vec<Section> {
+  let blocks = blocks(lines);
+  if blocks.is_empty() {
+    return Vec::new();
+  }
+
+  // Group repeated blocks that are the same section sung again.
+  let mut cluster: Vec<Option<usize>> = vec![None; blocks.len()];
+  let mut clusters: Vec<Vec<usize>> = Vec::new();
+  for (i, b) in blocks.iter().enumerate().filter(|(_, b)| b.repeated) {
+    let found = clusters
+      .iter()
+      .position(|members| similarity(&blocks[members[0]].keys, &b.keys) >= SAME_SECTION);
+    let c = found.unwrap_or_else(|| {
+      clusters.push(Vec::new());
+      clusters.len() - 1
+    });
+    clusters[c].push(i);
+    cluster[i] = Some(c);
+  }
+  let chorus = clusters
+    .iter()
+    .enumerate()
+    .filter(|(_, members)| members.len() >= 2)
+    .max_by_key(|(_, members)| (members.len(), members.iter().map(|&m| blocks[m].keys.len()).sum::<usize>()))
+    .map(|(c, _)| c);
+
+  let mut kinds: Vec<SectionKind> = cluster
+    .iter()
+    .map(|c| if chorus.is_some() && *c == chorus { SectionKind::Chorus } else { SectionKind::Verse })
+    .collect();
+  let choruses_before = |i: usize| kinds[..i].iter().filter(|k| **k == SectionKind::Chorus).count();
+  let bridge = (0..blocks.len()).rev().find(|&i| {
+    !blocks[i].repeated && choruses_before(i) >= 2 && kinds[i + 1..].contains(&SectionKind::Chorus)
+  });
+  if let Some(i) = bridge {
+    kinds[i] = SectionKind::Bridge;
+  }
+
+  let last_ms = lines.iter().map(|l| l.ms).max().unwrap_or(0);
+  let mut sections: Vec<Section> = Vec::new();
+  for (b, kind) in blocks.iter().zip(kinds) {
+    match sections.last_mut() {
+      // A verse split by a pause is still one verse.
+      Some(s) if s.kind == kind && kind != SectionKind::Chorus => s.lines += b.keys.len(),
+      _ => sections.push(Section {
+        kind,
+        label: String::new(),
+        start_ms: b.start_ms,
+        end_ms: last_ms,
+        lines: b.keys.len(),
+      }),
+    }
+  }
+
+  let mut numbers: HashMap<SectionKind, usize> = HashMap::new();
+  let next_starts: Vec<i64> = sections.iter().skip(1).map(|s| s.start_ms).collect();
+  for (i, s) in sections.iter_mut().enumerate() {
+    let n = numbers.entry(s.kind).or_insert(0);
+    *n += 1;
+    s.label = format!("{} {n}", s.kind.name());
+    if let Some(&next) = next_starts.get(i) {
+      s.end_ms = next;
+    }
+  }
+  sections
+}
+
+/// Put a `[#:Chorus 1]` comment line ahead of each section's first line. Players skip
+/// `#` comment tags.
+pub fn annotate_lrc(lrc: &str, sections: &[Section]) -> String {
+  let mut out = String::with_capacity(lrc.len() + sections.len() * 16);
+  let mut pending = sections.iter().peekable();
+  for line in lrc.lines() {
+    let ms = line
+      .trim()
+      .find(']')
+      .and_then(|end| parse_ts_to_ms(&line.trim()[..=end]));
+    if let Some(ms) = ms {
+      while let Some(s) = pending.next_if(|s| s.start_ms <= ms) {
+        out.push_str(&format!("[#:{}]\n", s.label));
+      }
+    }
+    out.push_str(line);
+    out.push('\n');
+  }
+  out
+}
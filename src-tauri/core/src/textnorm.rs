@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::lrc::LrcLine;
+
+/// Output text cleanup for players with limited fonts (car head units, old MP3 players)
+/// that draw boxes for characters they don't know.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct TextCleanup {
+  /// Compose accents into single characters (NFC), as most fonts expect.
+  pub nfc: bool,
+  /// Curly quotes, ellipses, dashes and odd spaces become their ASCII equivalents.
+  pub ascii_punctuation: bool,
+  /// Full-width Latin letters, digits and punctuation (`ＡＢＣ！`) become half-width.
+  pub halfwidth: bool,
+}
+
+impl Default for TextCleanup {
+  fn default() -> Self {
+    Self {
+      nfc: true,
+      ascii_punctuation: false,
+      halfwidth: false,
+    }
+  }
+}
+
+fn ascii_punctuation(c: char) -> Option<&'static str> {
+  Some(match c {
+    '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => "'",
+    '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => "\"",
+    '\u{2026}' => "...",
+    '\u{2010}'..='\u{2015}' | '\u{2212}' => "-",
+    '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' => " ",
+    '\u{200B}' | '\u{2060}' | '\u{FEFF}' => "",
+    _ => return None,
+  })
+}
+
+// U+FF01..U+FF5E mirror ASCII 0x21..0x7E; U+3000 is the ideographic space.
+fn halfwidth(c: char) -> char {
+  match c {
+    '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+    '\u{3000}' => ' ',
+    _ => c,
+  }
+}
+
+/// Apply `cleanup` to one line of text.
+pub fn clean_text(text: &str, cleanup: &TextCleanup) -> String {
+  let composed: String = if cleanup.nfc { text.nfc().collect() } else { text.to_string() };
+  let mut out = String::with_capacity(composed.len());
+  for c in composed.chars() {
+    let c = if cleanup.halfwidth { halfwidth(c) } else { c };
+    match ascii_punctuation(c).filter(|_| cleanup.ascii_punctuation) {
+      Some(replacement) => out.push_str(replacement),
+      None => out.push(c),
+    }
+  }
+  out
+}
+
+pub fn clean_lines(lines: Vec<LrcLine>, cleanup: &TextCleanup) -> Vec<LrcLine> {
+  lines
+    .into_iter()
+    .map(|mut l| {
+      l.text = clean_text(&l.text, cleanup);
+      l
+    })
+    .collect()
+}
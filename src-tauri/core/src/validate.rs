@@ -0,0 +1,256 @@
+use serde::Serialize;
+
+use crate::encoding;
+use crate::lrc::{offset_tag, parse_ts_to_ms, LrcLine};
+
+/// Two-line car/player displays fit about this many characters per line.
+pub const DEFAULT_MAX_CHARS: usize = 42;
+
+/// Karaoke singers and readers keep up with about this many characters per second.
+pub const DEFAULT_MAX_CPS: f64 = 20.0;
+
+/// Lines shown for less than this can't be read.
+const MIN_DISPLAY_MS: i64 = 300;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  /// Players will misread or skip something.
+  Error,
+  /// Plays, but looks wrong.
+  Warning,
+}
+
+/// One problem found in an LRC file. `line` is 1-based; 0 means the whole file.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+  pub line: usize,
+  pub severity: Severity,
+  /// Stable identifier for the UI: `encoding`, `malformedTimestamp`, `untimedLine`,
+  /// `outOfOrder`, `zeroGap`, `overlap`, `tooLong` or `readingSpeed`.
+  pub code: &'static str,
+  pub message: String,
+}
+
+fn finding(line: usize, severity: Severity, code: &'static str, message: String) -> Finding {
+  Finding { line, severity, code, message }
+}
+
+fn encoding_findings(bytes: &[u8]) -> Vec<Finding> {
+  if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+    return vec![finding(0, Severity::Warning, "encoding", "File is UTF-16; most players expect UTF-8".into())];
+  }
+  match std::str::from_utf8(bytes) {
+    Err(e) => {
+      let line = bytes[..e.valid_up_to()].iter().filter(|b| **b == b'\n').count() + 1;
+      let message = match encoding::legacy_charset(bytes) {
+        Some(charset) => format!("Not valid UTF-8 (looks like {charset}); most players will garble non-ASCII text"),
+        None => "Damaged UTF-8; the invalid bytes show as replacement characters (�)".into(),
+      };
+      vec![finding(line, Severity::Error, "encoding", message)]
+    }
+    Ok(_) => Vec::new(),
+  }
+}
+
+/// Leading `[mm:ss.xx]` stamps of a line, or the text of the first bracket that
+/// looks like a timestamp but doesn't parse.
+fn line_stamps(line: &str) -> Result<(Vec<i64>, &str), String> {
+  let mut rest = line;
+  let mut stamps = Vec::new();
+  while rest.starts_with('[') {
+    let Some(end) = rest.find(']') else {
+      return Err(format!("Unclosed bracket in \"{rest}\""));
+    };
+    let inner = &rest[1..end];
+    // ID tags ([ar:...]) start with a letter; anything numeric is meant as a time.
+    if !inner.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+      break;
+    }
+    match parse_ts_to_ms(&rest[..=end]) {
+      Some(ms) if ms >= 0 && valid_seconds(inner) => stamps.push(ms),
+      _ => return Err(format!("Malformed timestamp [{inner}]")),
+    }
+    rest = &rest[end + 1..];
+  }
+  Ok((stamps, rest))
+}
+
+fn valid_seconds(ts: &str) -> bool {
+  ts.split(':')
+    .nth(1)
+    .and_then(|s| s.split('.').next())
+    .and_then(|s| s.parse::<u32>().ok())
+    .is_some_and(|s| s < 60)
+}
+
+fn is_tag(line: &str) -> bool {
+  line
+    .strip_prefix('[')
+    .and_then(|l| l.strip_suffix(']'))
+    .and_then(|l| l.split_once(':'))
+    // `[#:...]` is a comment.
+    .is_some_and(|(k, _)| k == "#" || (!k.is_empty() && k.chars().all(|c| c.is_ascii_alphabetic())))
+}
+
+/// A line that goes by faster than it can be sung or read.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct FastLine {
+  pub ms: i64,
+  pub text: String,
+  pub chars_per_second: f64,
+}
+
+// Spaces aren't sung.
+fn chars_per_second(text: &str, shown_ms: i64) -> f64 {
+  text.chars().filter(|c| !c.is_whitespace()).count() as f64 * 1000.0 / shown_ms as f64
+}
+
+/// Lines faster than `max_cps`, each shown until the next later line starts.
+pub fn fast_lines(lines: &[LrcLine], max_cps: f64) -> Vec<FastLine> {
+  lines
+    .iter()
+    .enumerate()
+    .filter_map(|(i, l)| {
+      let next = lines[i + 1..].iter().find(|n| n.ms > l.ms)?;
+      let cps = chars_per_second(&l.text, next.ms - l.ms);
+      (cps > max_cps).then(|| FastLine {
+        ms: l.ms,
+        text: l.text.trim().to_string(),
+        chars_per_second: cps,
+      })
+    })
+    .collect()
+}
+
+/// Check an LRC file for things players trip over.
+pub fn validate_lrc(bytes: &[u8], max_chars: usize, max_cps: f64) -> Vec<Finding> {
+  let mut findings = encoding_findings(bytes);
+  // Invalid bytes are already reported; lossy decoding turns them into U+FFFD.
+  let decoded_cleanly = findings.iter().all(|f| f.severity != Severity::Error);
+  let text = encoding::decode(bytes);
+  // Times as players show them; the tag line itself stays so line numbers match the file.
+  let offset = offset_tag(&text);
+
+  let mut prev: Option<(usize, i64, &str)> = None;
+  for (i, raw) in text.lines().enumerate() {
+    let n = i + 1;
+    let line = raw.trim();
+    if line.is_empty() || is_tag(line) {
+      continue;
+    }
+    if decoded_cleanly && line.contains('\u{fffd}') {
+      findings.push(finding(n, Severity::Warning, "encoding", "Contains replacement characters (�)".into()));
+    }
+
+    let (stamps, lyric) = match line_stamps(line) {
+      Ok(parsed) => parsed,
+      Err(message) => {
+        findings.push(finding(n, Severity::Error, "malformedTimestamp", message));
+        continue;
+      }
+    };
+    if stamps.is_empty() {
+      findings.push(finding(n, Severity::Warning, "untimedLine", "Line has no timestamp and won't be shown".into()));
+      continue;
+    }
+
+    let len = lyric.trim().chars().count();
+    if len > max_chars {
+      findings.push(finding(
+        n,
+        Severity::Warning,
+        "tooLong",
+        format!("{len} characters; displays fit about {max_chars}"),
+      ));
+    }
+
+    // Repeated-chorus lines carry several stamps; order only matters line to line.
+    let ms = (stamps[0] - offset).max(0);
+    if stamps.len() == 1 {
+      if let Some((prev_line, prev_ms, prev_lyric)) = prev {
+        let gap = ms - prev_ms;
+        if gap < 0 {
+          findings.push(finding(
+            n,
+            Severity::Warning,
+            "outOfOrder",
+            format!("Starts before line {prev_line}; some players don't sort lines"),
+          ));
+        } else if gap == 0 {
+          findings.push(finding(
+            n,
+            Severity::Warning,
+            "zeroGap",
+            format!("Same timestamp as line {prev_line}, which is never shown"),
+          ));
+        } else if gap < MIN_DISPLAY_MS {
+          findings.push(finding(
+            n,
+            Severity::Warning,
+            "overlap",
+            format!("Line {prev_line} is shown for only {gap} ms"),
+          ));
+        } else if chars_per_second(prev_lyric, gap) > max_cps {
+          findings.push(finding(
+            prev_line,
+            Severity::Warning,
+            "readingSpeed",
+            format!(
+              "{:.0} characters per second; split the line or give it more time",
+              chars_per_second(prev_lyric, gap)
+            ),
+          ));
+        }
+      }
+      prev = Some((n, ms, lyric));
+    }
+  }
+  // Reading speed is only known once the next line is seen.
+  findings.sort_by_key(|f| f.line);
+  findings
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn codes(lrc: &str) -> Vec<(usize, &'static str)> {
+    validate_lrc(lrc.as_bytes(), DEFAULT_MAX_CHARS, DEFAULT_MAX_CPS).into_iter().map(|f| (f.line, f.code)).collect()
+  }
+
+  #[test]
+  fn clean_file_has_no_findings() {
+    assert!(codes("[ar:Someone]\n[00:01.00]hello there\n[00:04.00]general kenobi\n").is_empty());
+  }
+
+  #[test]
+  fn reports_timing_problems_by_line() {
+    let lrc = "[00:05.00]first\n[00:04.00]earlier\n[00:04.00]same time\n[00:04.10]too soon\nno stamp\n[0a:01.00]bad\n";
+    assert_eq!(
+      codes(lrc),
+      [(2, "outOfOrder"), (3, "zeroGap"), (4, "overlap"), (5, "untimedLine"), (6, "malformedTimestamp")]
+    );
+  }
+
+  #[test]
+  fn reports_long_and_fast_lines() {
+    let long = "a".repeat(DEFAULT_MAX_CHARS + 1);
+    assert_eq!(codes(&format!("[00:01.00]{long}\n[00:30.00]end\n")), [(1, "tooLong")]);
+    assert_eq!(codes("[00:01.00]supercalifragilistic\n[00:01.50]next\n"), [(1, "readingSpeed")]);
+  }
+
+  #[test]
+  fn offset_tag_moves_lines_before_zero_together() {
+    assert_eq!(codes("[offset:+2000]\n[00:00.50]a\n[00:01.00]b\n"), [(3, "zeroGap")]);
+  }
+
+  #[test]
+  fn legacy_encoding_is_an_error() {
+    let (latin, _, _) = encoding_rs::WINDOWS_1252.encode("[00:01.00]déjà vu, café crème\n");
+    let findings = validate_lrc(&latin, DEFAULT_MAX_CHARS, DEFAULT_MAX_CPS);
+    assert_eq!((findings[0].code, findings[0].severity), ("encoding", Severity::Error));
+  }
+}
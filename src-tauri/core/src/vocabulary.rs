@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::lrc::LrcLine;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct VocabularyWord {
+  /// Correct spelling, e.g. "Beyoncé".
+  pub word: String,
+  /// Known mishearings rewritten to `word`, e.g. "be on say".
+  #[serde(default)]
+  pub sounds_like: Vec<String>,
+}
+
+// Whisper only keeps the last ~224 prompt tokens; don't crowd out the tags.
+const MAX_PROMPT_WORDS: usize = 40;
+// Longest run of transcribed words tried against one vocabulary entry.
+const MAX_WINDOW: usize = 4;
+
+/// Comma-separated spellings for whisper's initial prompt.
+pub fn prompt_words(vocab: &[VocabularyWord]) -> Option<String> {
+  let words: Vec<&str> = vocab
+    .iter()
+    .map(|v| v.word.trim())
+    .filter(|w| !w.is_empty())
+    .take(MAX_PROMPT_WORDS)
+    .collect();
+  (!words.is_empty()).then(|| words.join(", "))
+}
+
+fn base_letter(c: char) -> char {
+  match c {
+    'à'..='å' => 'a',
+    'ç' => 'c',
+    'è'..='ë' => 'e',
+    'ì'..='ï' => 'i',
+    'ñ' => 'n',
+    'ò'..='ö' | 'ø' => 'o',
+    'ù'..='ü' => 'u',
+    'ý' | 'ÿ' => 'y',
+    _ => c,
+  }
+}
+
+/// Case-, accent-, space- and punctuation-insensitive key: "Be-yoncé" -> "beyonce".
+fn fold(s: &str) -> String {
+  s.chars()
+    .flat_map(char::to_lowercase)
+    .map(base_letter)
+    .filter(|c| c.is_alphanumeric())
+    .collect()
+}
+
+fn lookup(vocab: &[VocabularyWord]) -> HashMap<String, &str> {
+  let mut map = HashMap::new();
+  for v in vocab {
+    let word = v.word.trim();
+    if word.is_empty() {
+      continue;
+    }
+    for key in std::iter::once(word).chain(v.sounds_like.iter().map(String::as_str)) {
+      let key = fold(key);
+      if !key.is_empty() {
+        map.insert(key, word);
+      }
+    }
+  }
+  map
+}
+
+/// Rewrite runs of words that fold to a vocabulary entry (or one of its known
+/// mishearings), longest run first. Punctuation around the run is kept.
+fn correct_text(text: &str, map: &HashMap<String, &str>) -> String {
+  let tokens: Vec<&str> = text.split_whitespace().collect();
+  let mut out: Vec<String> = Vec::with_capacity(tokens.len());
+  let mut i = 0;
+
+  'outer: while i < tokens.len() {
+    for n in (1..=MAX_WINDOW.min(tokens.len() - i)).rev() {
+      let run = &tokens[i..i + n];
+      if let Some(word) = map.get(&fold(&run.concat())) {
+        let first = run[0];
+        let head = &first[..first.len() - first.trim_start_matches(|c: char| !c.is_alphanumeric()).len()];
+        let last = run[n - 1];
+        let tail = &last[last.trim_end_matches(|c: char| !c.is_alphanumeric()).len()..];
+        out.push(format!("{head}{word}{tail}"));
+        i += n;
+        continue 'outer;
+      }
+    }
+    out.push(tokens[i].to_string());
+    i += 1;
+  }
+
+  out.join(" ")
+}
+
+pub fn correct_lines(lines: Vec<LrcLine>, vocab: &[VocabularyWord]) -> Vec<LrcLine> {
+  let map = lookup(vocab);
+  if map.is_empty() {
+    return lines;
+  }
+  lines
+    .into_iter()
+    .map(|l| LrcLine { ms: l.ms, text: correct_text(&l.text, &map), end_ms: l.end_ms })
+    .collect()
+}
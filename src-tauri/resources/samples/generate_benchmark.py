@@ -0,0 +1,71 @@
+"""Synthesizes benchmark.wav, the 30 s sample the model benchmark transcribes.
+
+Plucked chords, bass, drums and a formant-filtered "sung" vowel melody: busy enough to
+keep whisper decoding the way a real song does. Deterministic; run it from anywhere
+to regenerate the file byte for byte. Pure standard library, takes about a minute.
+"""
+import math, os, random, struct, wave
+SR=16000; DUR=30; N=SR*DUR
+random.seed(7)
+out=[0.0]*N
+bpm=96; beat=60/bpm
+def midi(n): return 440*2**((n-69)/12)
+def add(buf, start, samples, gain):
+  i0=int(start*SR)
+  for k,v in enumerate(samples):
+    j=i0+k
+    if j>=N: break
+    buf[j]+=v*gain
+def pluck(freq, dur):
+  n=int(dur*SR); p=max(2,int(SR/freq))
+  ring=[random.uniform(-1,1) for _ in range(p)]
+  res=[]
+  for k in range(n):
+    v=ring[k%p]; nxt=ring[(k+1)%p]
+    ring[k%p]=0.498*(v+nxt)
+    res.append(v)
+  return res
+def kick():
+  n=int(0.25*SR); return [math.sin(2*math.pi*(50+90*math.exp(-k/400))*k/SR)*math.exp(-k/1800) for k in range(n)]
+def hat():
+  n=int(0.05*SR); return [random.uniform(-1,1)*math.exp(-k/150) for k in range(n)]
+# vowel formants (F1,F2,F3)
+VOW={'a':(730,1090,2440),'e':(530,1840,2480),'i':(270,2290,3010),'o':(570,840,2410),'u':(300,870,2240)}
+def resonate(x, f, bw):
+  r=math.exp(-math.pi*bw/SR); c=2*r*math.cos(2*math.pi*f/SR); y1=y2=0; out=[]
+  for v in x:
+    y=v+c*y1-r*r*y2; y2=y1; y1=y; out.append(y*(1-r))
+  return out
+def sing(freq, dur, vowel):
+  n=int(dur*SR); ph=0; src=[]
+  for k in range(n):
+    vib=1+0.012*math.sin(2*math.pi*5.5*k/SR)*min(1,k/(0.2*SR))
+    ph+=freq*vib/SR; ph-=int(ph)
+    src.append((2*ph-1)+random.uniform(-0.05,0.05))
+  y=[0.0]*n
+  for f,bw,g in zip(VOW[vowel],(80,90,120),(1.0,0.6,0.3)):
+    for k,v in enumerate(resonate(src,f,bw)): y[k]+=v*g
+  env=[min(1,k/(0.04*SR))*min(1,(n-k)/(0.08*SR)) for k in range(n)]
+  return [a*b for a,b in zip(y,env)]
+chords=[(60,64,67),(55,59,62),(57,60,64),(53,57,60)]  # C G Am F
+bar=4*beat; t=0; bi=0
+while t<DUR:
+  ch=chords[bi%4]
+  for b in range(4):
+    for n in ch: add(out, t+b*beat, pluck(midi(n), beat*1.5), 0.18)
+    add(out, t+b*beat, pluck(midi(ch[0]-24), beat), 0.35)
+    if b%2==0: add(out, t+b*beat, kick(), 0.5)
+    for h in range(2): add(out, t+b*beat+h*beat/2, hat(), 0.06)
+  t+=bar; bi+=1
+mel=[72,71,69,67, 69,71,72,74, 76,74,72,71, 69,67,69,72]
+vows='aoeiuaoeaiouaeoa'
+t=beat*4; i=0
+while t<DUR-beat*2:
+  if (i//8)%2==0 or i%8<6:
+    add(out, t, sing(midi(mel[i%len(mel)]-12), beat*0.9, vows[i%len(vows)]), 0.25)
+  t+=beat; i+=1
+peak=max(abs(v) for v in out)
+w=wave.open(os.path.join(os.path.dirname(os.path.abspath(__file__)),'benchmark.wav'),'wb')
+w.setnchannels(1); w.setsampwidth(2); w.setframerate(SR)
+w.writeframes(b''.join(struct.pack('<h',int(v/peak*0.8*32767)) for v in out))
+w.close()
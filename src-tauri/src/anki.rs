@@ -0,0 +1,73 @@
+use lyrictime_core::progress::{Progress, ProgressEvent};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::clips;
+use crate::host::Host;
+use crate::split::sanitize_file_name;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiExport {
+  /// Tab-separated notes for File > Import.
+  pub notes_path: String,
+  /// Clips to copy into Anki's `collection.media` folder.
+  pub media_dir: String,
+  pub notes: usize,
+}
+
+// Fields are HTML; tabs and newlines would split the note.
+fn field(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace(['\t', '\n', '\r'], " ")
+}
+
+/// Turn a song into a study deck: one Basic note per lyric line, the clip on the front,
+/// the line (and its translation, for bilingual LRCs) on the back. Written to
+/// `<name> (Anki)/` next to the LRC as an Anki text import plus a media folder.
+pub async fn export_anki(app: Host, audio: &Path, lrc: &Path) -> Result<AnkiExport, String> {
+  let stem = lrc
+    .file_stem()
+    .map(|s| s.to_string_lossy().into_owned())
+    .unwrap_or_else(|| "lyrics".into());
+  let dir = lrc
+    .parent()
+    .unwrap_or(Path::new("."))
+    .join(sanitize_file_name(&format!("{stem} (Anki)")));
+  let media_dir = dir.join("media");
+
+  // collection.media is flat and shared by all decks, so clip names carry the song.
+  let clips = clips::cut_clips(&app, audio, lrc, &media_dir, &format!("{stem} - ")).await?;
+
+  let mut notes = format!(
+    "#separator:tab\n#html:true\n#notetype:Basic\n#deck:LyricTime::{}\n#columns:Front\tBack\n",
+    field(&stem).replace("::", " ")
+  );
+  for clip in &clips {
+    let file = Path::new(&clip.path).file_name().map(|f| f.to_string_lossy()).unwrap_or_default();
+    let back = match &clip.translation {
+      Some(t) => format!("{}<br><i>{}</i>", field(&clip.text), field(t)),
+      None => field(&clip.text),
+    };
+    notes.push_str(&format!("[sound:{file}]\t{back}\n"));
+  }
+
+  let notes_path = dir.join(format!("{}.txt", sanitize_file_name(&stem)));
+  std::fs::write(&notes_path, notes).map_err(|e| format!("Failed writing {}: {e}", notes_path.display()))?;
+  app.report(ProgressEvent::Log {
+    line: format!(
+      "Copy the clips from {} into Anki's collection.media folder, then import {}",
+      media_dir.display(),
+      notes_path.display()
+    ),
+  });
+
+  Ok(AnkiExport {
+    notes_path: notes_path.display().to_string(),
+    media_dir: media_dir.display().to_string(),
+    notes: clips.len(),
+  })
+}
@@ -0,0 +1,89 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+enum Kind {
+  Zip,
+  TarXz,
+}
+
+fn kind(name: &str) -> Option<Kind> {
+  let name = name.split(['?', '#']).next().unwrap_or(name).to_ascii_lowercase();
+  if name.ends_with(".zip") {
+    Some(Kind::Zip)
+  } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+    Some(Kind::TarXz)
+  } else {
+    None
+  }
+}
+
+/// True if `url` points at an archive we know how to unpack (.zip, .tar.xz).
+pub fn is_archive(url: &str) -> bool {
+  kind(url).is_some()
+}
+
+/// Last path segment of a URL, without query string: used as the archive's file name.
+pub fn file_name_from_url(url: &str) -> String {
+  let path = url.split(['?', '#']).next().unwrap_or(url);
+  path.rsplit('/').next().unwrap_or(path).to_string()
+}
+
+/// Unpack a whole .zip into `dest_dir`, keeping its folders (Core ML models are
+/// directories, not single files).
+pub fn extract_tree(archive: &Path, dest_dir: &Path) -> Result<(), String> {
+  let name = archive.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+  let dest_dir = &crate::longpath::extended(dest_dir);
+  let file = File::open(archive).map_err(|e| format!("Failed opening {name}: {e}"))?;
+  let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Bad zip {name}: {e}"))?;
+  zip.extract(dest_dir).map_err(|e| format!("Failed extracting {name}: {e}"))
+}
+
+/// Extract the entries whose file name is in `wanted` (at any depth, e.g.
+/// `ffmpeg-7.1-amd64-static/ffmpeg`) flat into `dest_dir`. Returns what was written.
+pub fn extract_files(archive: &Path, wanted: &[&str], dest_dir: &Path) -> Result<Vec<PathBuf>, String> {
+  let name = archive.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+  let kind = kind(&name).ok_or_else(|| format!("Unsupported archive: {name}"))?;
+  let dest_dir = &crate::longpath::extended(dest_dir);
+  fs::create_dir_all(dest_dir).map_err(|e| e.to_string())?;
+
+  let file = File::open(archive).map_err(|e| format!("Failed opening {name}: {e}"))?;
+  let mut written = Vec::new();
+
+  match kind {
+    Kind::Zip => {
+      let mut zip = zip::ZipArchive::new(file).map_err(|e| format!("Bad zip {name}: {e}"))?;
+      for i in 0..zip.len() {
+        let mut entry = zip.by_index(i).map_err(|e| e.to_string())?;
+        let base = entry.name().rsplit('/').next().unwrap_or_default().to_string();
+        if !entry.is_file() || !wanted.contains(&base.as_str()) {
+          continue;
+        }
+        let out_path = dest_dir.join(&base);
+        let mut out = File::create(&out_path).map_err(|e| e.to_string())?;
+        io::copy(&mut entry, &mut out).map_err(|e| format!("Failed extracting {base}: {e}"))?;
+        written.push(out_path);
+      }
+    }
+    Kind::TarXz => {
+      let mut tar = tar::Archive::new(xz2::read::XzDecoder::new(file));
+      for entry in tar.entries().map_err(|e| format!("Bad archive {name}: {e}"))? {
+        let mut entry = entry.map_err(|e| e.to_string())?;
+        let base = entry
+          .path()
+          .ok()
+          .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+          .unwrap_or_default();
+        if !entry.header().entry_type().is_file() || !wanted.contains(&base.as_str()) {
+          continue;
+        }
+        // `unpack` keeps the archived permission bits.
+        let out_path = dest_dir.join(&base);
+        entry.unpack(&out_path).map_err(|e| format!("Failed extracting {base}: {e}"))?;
+        written.push(out_path);
+      }
+    }
+  }
+
+  Ok(written)
+}
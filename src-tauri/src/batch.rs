@@ -0,0 +1,584 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+use crate::whisper::{self, ChapterMode, ExistingLyrics, GenerateOptions, OutputFormat};
+use crate::{cache, chapters, cue, formats, library, playlist, tags, ytdlp};
+use lyrictime_core::encoding;
+use lyrictime_core::lrc::parse_lrc;
+use lyrictime_core::progress::{Progress, ProgressEvent};
+
+/// Inputs the pipeline accepts (ffmpeg reads more, but these are what people feed it).
+pub fn is_media_file(path: &Path) -> bool {
+  path.extension().and_then(|e| e.to_str()).is_some_and(formats::is_media_extension)
+}
+
+/// Frontend listens to: `batch://progress`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgressEvent {
+  /// 1-based position in the batch.
+  pub index: usize,
+  pub total: usize,
+  pub input: String,
+  pub status: String, // "running" | "done" | "planned" | "skipped" | "duplicate" | "rejected" | "failed" | "cancelled"
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemStatus {
+  Done,
+  /// Dry run: would be generated.
+  Planned,
+  Skipped,
+  /// Same audio as an earlier input; its output was copied.
+  Duplicate,
+  /// Not something the pipeline can read (missing file, unsupported format).
+  Rejected,
+  Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItem {
+  pub input: String,
+  pub status: ItemStatus,
+  /// What transcribed it, e.g. "small+medium" for hybrid; the batch's model when that
+  /// isn't known. Empty for inputs that weren't transcribed.
+  #[serde(default)]
+  pub model: String,
+  pub outputs: Vec<String>,
+  /// Failure message, or why the file was skipped.
+  pub error: Option<String>,
+  /// The input whose output was copied, for duplicates.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub duplicate_of: Option<String>,
+  #[serde(default)]
+  pub runtime_ms: u64,
+  /// Things worth a second look even though the file succeeded.
+  #[serde(default)]
+  pub warnings: Vec<String>,
+}
+
+impl BatchItem {
+  fn new(input: &str, status: ItemStatus) -> Self {
+    BatchItem {
+      input: input.to_string(),
+      status,
+      model: String::new(),
+      outputs: Vec::new(),
+      error: None,
+      duplicate_of: None,
+      runtime_ms: 0,
+      warnings: Vec::new(),
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BatchReport {
+  pub model: String,
+  /// Unix seconds.
+  pub started_at: u64,
+  pub finished_at: u64,
+  pub dry_run: bool,
+  pub items: Vec<BatchItem>,
+  pub succeeded: usize,
+  pub planned: usize,
+  pub skipped: usize,
+  pub duplicates: usize,
+  pub rejected: usize,
+  pub failed: usize,
+}
+
+impl BatchReport {
+  fn push(&mut self, item: BatchItem) {
+    match item.status {
+      ItemStatus::Done => self.succeeded += 1,
+      ItemStatus::Planned => self.planned += 1,
+      ItemStatus::Skipped => self.skipped += 1,
+      ItemStatus::Duplicate => self.duplicates += 1,
+      ItemStatus::Rejected => self.rejected += 1,
+      ItemStatus::Failed => self.failed += 1,
+    }
+    self.items.push(item);
+  }
+
+  /// One row per input: status, model, runtime, outputs, error and warnings.
+  pub fn to_csv(&self) -> String {
+    fn field(s: &str) -> String {
+      if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+      } else {
+        s.to_string()
+      }
+    }
+
+    let mut out = String::from("input,status,model,runtime_ms,outputs,error,duplicate_of,warnings\n");
+    for item in &self.items {
+      let status = serde_json::to_value(item.status).ok().and_then(|v| v.as_str().map(str::to_string));
+      let row = [
+        field(&item.input),
+        status.unwrap_or_default(),
+        field(&item.model),
+        item.runtime_ms.to_string(),
+        field(&item.outputs.join("; ")),
+        field(item.error.as_deref().unwrap_or_default()),
+        field(item.duplicate_of.as_deref().unwrap_or_default()),
+        field(&item.warnings.join("; ")),
+      ];
+      out.push_str(&row.join(","));
+      out.push('\n');
+    }
+    out
+  }
+}
+
+fn unix_now() -> u64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+fn reports_dir(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.app_data_dir()?.join("batch"))
+}
+
+/// Keep the latest report in app data, as both JSON and CSV.
+fn save_report(app: &Host, report: &BatchReport) -> Result<(), String> {
+  let dir = reports_dir(app)?;
+  std::fs::create_dir_all(&dir).map_err(|e| format!("Failed creating {}: {e}", dir.display()))?;
+  let json = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+  std::fs::write(dir.join("last_report.json"), json).map_err(|e| format!("Failed writing batch report: {e}"))?;
+  std::fs::write(dir.join("last_report.csv"), report.to_csv()).map_err(|e| format!("Failed writing batch report: {e}"))
+}
+
+pub fn last_report(app: &Host) -> Result<Option<BatchReport>, String> {
+  let path = reports_dir(app)?.join("last_report.json");
+  if !path.exists() {
+    return Ok(None);
+  }
+  let raw = std::fs::read_to_string(&path).map_err(|e| format!("Failed reading batch report: {e}"))?;
+  serde_json::from_str(&raw).map(Some).map_err(|e| format!("Invalid batch report: {e}"))
+}
+
+/// Write `report` to `path`: CSV for a `.csv` path, JSON otherwise.
+pub fn export_report(report: &BatchReport, path: &Path) -> Result<(), String> {
+  let csv = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+  let text = if csv {
+    report.to_csv()
+  } else {
+    serde_json::to_string_pretty(report).map_err(|e| e.to_string())?
+  };
+  std::fs::write(path, text).map_err(|e| format!("Failed writing {}: {e}", path.display()))
+}
+
+// Media files and cue sheets under `dir`, recursively, in name order. Audio a cue sheet
+// splits is left to the sheet, and symlinked folders aren't entered so loops can't recurse.
+fn walk_dir(dir: &Path, out: &mut Vec<String>) -> Result<(), String> {
+  let mut entries: Vec<_> = std::fs::read_dir(dir)
+    .map_err(|e| format!("Failed reading {}: {e}", dir.display()))?
+    .flatten()
+    .map(|e| e.path())
+    .collect();
+  entries.sort();
+
+  let split_by_cue: Vec<PathBuf> = entries
+    .iter()
+    .filter(|p| cue::is_cue(&p.to_string_lossy()))
+    .flat_map(|p| cue::referenced_files(p))
+    .collect();
+
+  for path in entries {
+    let is_link = std::fs::symlink_metadata(&path).is_ok_and(|m| m.file_type().is_symlink());
+    if path.is_dir() {
+      if !is_link {
+        walk_dir(&path, out)?;
+      }
+    } else if cue::is_cue(&path.to_string_lossy()) || (is_media_file(&path) && !split_by_cue.contains(&path)) {
+      out.push(path.to_string_lossy().into_owned());
+    }
+  }
+  Ok(())
+}
+
+/// Replace playlists and folders with the media files they contain; other inputs pass
+/// through.
+pub fn expand_inputs(inputs: &[String]) -> Result<Vec<String>, String> {
+  let mut out = Vec::new();
+  for input in inputs {
+    if playlist::is_playlist(input) {
+      out.extend(
+        playlist::read_playlist(Path::new(input))?
+          .into_iter()
+          .filter(|p| is_media_file(p))
+          .map(|p| p.to_string_lossy().into_owned()),
+      );
+    } else if Path::new(input).is_dir() {
+      walk_dir(Path::new(input), &mut out)?;
+    } else {
+      out.push(input.clone());
+    }
+  }
+  Ok(out)
+}
+
+fn reject_reason(input: &str) -> Option<String> {
+  let path = Path::new(input);
+  if ytdlp::is_url(input) {
+    None
+  } else if !path.exists() {
+    Some("File not found".into())
+  } else if !is_media_file(path) && !cue::is_cue(input) {
+    Some("Unsupported format".into())
+  } else {
+    None
+  }
+}
+
+/// Why `input` should be left alone under the `existing_lyrics` policy, if it should.
+fn skip_reason(app: &Host, input: &str, options: &GenerateOptions) -> Option<String> {
+  if options.existing_lyrics == ExistingLyrics::Regenerate || ytdlp::is_url(input) || cue::is_cue(input) {
+    return None;
+  }
+
+  let existing = OutputFormat::ALL
+    .iter()
+    .map(|f| library::output_path(app, Path::new(input), f.extension()))
+    .find(|p| p.exists());
+  if let Some(output) = existing {
+    return Some(format!("{} already exists", output.display()));
+  }
+  if options.existing_lyrics == ExistingLyrics::FillMissing {
+    let embedded = tags::read_audio_tags(Path::new(input)).is_ok_and(|t| t.has_embedded_lyrics);
+    if embedded {
+      return Some("File has embedded lyrics".into());
+    }
+  }
+  None
+}
+
+// Inputs that produce exactly one output next to themselves.
+fn is_plain_file(input: &str, options: &GenerateOptions) -> bool {
+  !ytdlp::is_url(input) && !cue::is_cue(input) && options.chapters != ChapterMode::Split
+}
+
+// Lowercased artist and title, duration in ms.
+type TagKey = (String, String, u64);
+// An input and the output written for it.
+type Transcribed = (String, String);
+
+/// What makes two files "the same song": identical bytes, or the same artist and title
+/// with durations within a second (re-encodes, copies in two albums).
+struct Signature {
+  hash: Option<String>,
+  tags: Option<TagKey>,
+}
+
+impl Signature {
+  fn of(path: &Path) -> Self {
+    let tags = tags::read_audio_tags(path).ok().and_then(|t| {
+      let artist = t.artist?.to_lowercase();
+      let title = t.title?.to_lowercase();
+      Some((artist, title, t.duration_ms))
+    });
+    Signature {
+      hash: cache::hash_file(path).ok(),
+      tags,
+    }
+  }
+}
+
+/// Plain files already transcribed in this batch, with their output.
+#[derive(Default)]
+struct Seen {
+  by_hash: HashMap<String, Transcribed>,
+  by_tags: Vec<(TagKey, Transcribed)>,
+}
+
+impl Seen {
+  /// The earlier input and its output; `true` when only the tags matched.
+  fn find(&self, sig: &Signature) -> Option<(&Transcribed, bool)> {
+    if let Some(seen) = sig.hash.as_ref().and_then(|h| self.by_hash.get(h)) {
+      return Some((seen, false));
+    }
+    let (artist, title, ms) = sig.tags.as_ref()?;
+    self
+      .by_tags
+      .iter()
+      .find(|((a, t, d), _)| a == artist && t == title && d.abs_diff(*ms) <= 1000)
+      .map(|(_, seen)| (seen, true))
+  }
+
+  fn insert(&mut self, sig: Signature, input: &str, output: &str) {
+    let seen = (input.to_string(), output.to_string());
+    if let Some(hash) = sig.hash {
+      self.by_hash.insert(hash, seen.clone());
+    }
+    if let Some(tags) = sig.tags {
+      self.by_tags.push((tags, seen));
+    }
+  }
+}
+
+// The original's output, copied next to the duplicate under its own name.
+fn copy_output(app: &Host, input: &str, output: &str) -> Result<String, String> {
+  let ext = Path::new(output).extension().and_then(|e| e.to_str()).unwrap_or("lrc");
+  let dest = library::output_path(app, Path::new(input), ext);
+  if let Some(dir) = dest.parent().filter(|d| !d.as_os_str().is_empty()) {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed creating {}: {e}", dir.display()))?;
+  }
+  std::fs::copy(output, &dest).map_err(|e| format!("Failed copying {output}: {e}"))?;
+  Ok(dest.display().to_string())
+}
+
+/// One input, routed by kind: URL, .cue sheet, per-chapter split or a plain file.
+async fn generate_one(
+  app: &Host,
+  input: &str,
+  out_dir: &Path,
+  model: &str,
+  options: &GenerateOptions,
+) -> Result<Vec<String>, String> {
+  if ytdlp::is_url(input) {
+    Ok(vec![ytdlp::generate_from_url(app.clone(), input, out_dir, model, options.clone()).await?])
+  } else if cue::is_cue(input) {
+    cue::generate_from_cue(app.clone(), input, model, options.clone()).await
+  } else if options.chapters == ChapterMode::Split {
+    chapters::generate_per_chapter(app.clone(), input, model, options.clone()).await
+  } else {
+    Ok(vec![whisper::generate_lrc_next_to_audio(app.clone(), input, model, options.clone()).await?])
+  }
+}
+
+// The models whose passes made the file just generated, from its saved job, which
+// records the English-only and out-of-memory substitutions; `requested` otherwise.
+fn model_used(app: &Host, requested: &str) -> String {
+  whisper::last_job(app)
+    .ok()
+    .filter(|job| job.stage == whisper::Stage::Done && !job.passes.is_empty())
+    .map(|job| job.passes.iter().map(|p| p.model.as_str()).collect::<Vec<_>>().join("+"))
+    .unwrap_or_else(|| requested.to_string())
+}
+
+// Outputs that contain nothing but the instrumental marker.
+fn output_warnings(outputs: &[String], options: &GenerateOptions) -> Vec<String> {
+  outputs
+    .iter()
+    .filter(|out| {
+      let text = std::fs::read(out).map(|b| encoding::decode(&b)).unwrap_or_default();
+      parse_lrc(&text).iter().all(|l| l.text.trim() == options.instrumental_marker.trim())
+    })
+    .map(|out| format!("No vocals detected in {out}"))
+    .collect()
+}
+
+/// Decide and carry out one input of the batch.
+#[allow(clippy::too_many_arguments)]
+async fn process(
+  app: &Host,
+  input: &str,
+  out_dir: &Path,
+  model: &str,
+  options: &GenerateOptions,
+  dry_run: bool,
+  seen: &mut Seen,
+  cancel: &whisper::CancelToken,
+  progress: &dyn Fn(&str, Option<String>),
+) -> BatchItem {
+  if let Some(reason) = reject_reason(input) {
+    progress("rejected", Some(reason.clone()));
+    return BatchItem { error: Some(reason), ..BatchItem::new(input, ItemStatus::Rejected) };
+  }
+  if let Some(reason) = skip_reason(app, input, options) {
+    progress("skipped", Some(reason.clone()));
+    return BatchItem { error: Some(reason), ..BatchItem::new(input, ItemStatus::Skipped) };
+  }
+  if dry_run {
+    progress("planned", None);
+    return BatchItem::new(input, ItemStatus::Planned);
+  }
+
+  let signature = (options.dedupe && is_plain_file(input, options)).then(|| Signature::of(Path::new(input)));
+  if let Some(((original, output), by_tags)) = signature.as_ref().and_then(|sig| seen.find(sig)) {
+    let mut item = BatchItem {
+      duplicate_of: Some(original.clone()),
+      model: model.to_string(),
+      ..BatchItem::new(input, ItemStatus::Duplicate)
+    };
+    match copy_output(app, input, output) {
+      Ok(copied) => {
+        progress("duplicate", Some(format!("Same audio as {original}")));
+        item.outputs = vec![copied];
+        if by_tags {
+          item.warnings.push("Matched by artist, title and duration; the audio differs".into());
+        }
+      }
+      Err(e) => {
+        progress("failed", Some(e.clone()));
+        item.status = ItemStatus::Failed;
+        item.error = Some(e);
+      }
+    }
+    return item;
+  }
+
+  progress("running", None);
+  match generate_one(app, input, out_dir, model, options).await {
+    Ok(outputs) => {
+      progress("done", None);
+      if let (Some(sig), [output]) = (signature, outputs.as_slice()) {
+        seen.insert(sig, input, output);
+      }
+      // Several outputs come from several generations (tracks, chapters).
+      let model = if outputs.len() == 1 { model_used(app, model) } else { model.to_string() };
+      BatchItem {
+        model,
+        warnings: output_warnings(&outputs, options),
+        outputs,
+        ..BatchItem::new(input, ItemStatus::Done)
+      }
+    }
+    Err(e) => {
+      // Cancelling isn't this file's failure; `drive` stops without recording it.
+      if !cancel.is_cancelled() {
+        progress("failed", Some(e.clone()));
+      }
+      BatchItem {
+        model: model.to_string(),
+        error: Some(e),
+        ..BatchItem::new(input, ItemStatus::Failed)
+      }
+    }
+  }
+}
+
+/// A batch in progress, written after every file so a crash or quit can resume it.
+/// Inputs past `report.items.len()` are still pending.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchState {
+  inputs: Vec<String>,
+  out_dir: String,
+  options: GenerateOptions,
+  report: BatchReport,
+}
+
+fn state_path(app: &Host) -> Result<PathBuf, String> {
+  Ok(reports_dir(app)?.join("unfinished.json"))
+}
+
+fn save_state(app: &Host, state: &BatchState) -> Result<(), String> {
+  let path = state_path(app)?;
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed creating {}: {e}", dir.display()))?;
+  }
+  let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+  // Written aside and renamed so a crash mid-write can't lose the previous state.
+  let tmp = path.with_extension("json.part");
+  std::fs::write(&tmp, json).map_err(|e| format!("Failed writing batch state: {e}"))?;
+  std::fs::rename(&tmp, &path).map_err(|e| format!("Failed writing batch state: {e}"))
+}
+
+fn load_state(app: &Host) -> Result<Option<BatchState>, String> {
+  let path = state_path(app)?;
+  if !path.exists() {
+    return Ok(None);
+  }
+  let raw = std::fs::read_to_string(&path).map_err(|e| format!("Failed reading batch state: {e}"))?;
+  serde_json::from_str(&raw).map(Some).map_err(|e| format!("Invalid batch state: {e}"))
+}
+
+/// Generate lyrics for every input in turn. Failures are recorded and the batch carries
+/// on. `out_dir` is only used for audio downloaded from URLs. A dry run only reports what
+/// would happen; nothing is spawned or written. The report is also kept in app data.
+pub async fn run(
+  app: Host,
+  inputs: &[String],
+  out_dir: &Path,
+  model: &str,
+  options: GenerateOptions,
+  dry_run: bool,
+) -> Result<BatchReport, String> {
+  let state = BatchState {
+    inputs: expand_inputs(inputs)?,
+    out_dir: out_dir.display().to_string(),
+    options,
+    report: BatchReport {
+      model: model.to_string(),
+      started_at: unix_now(),
+      dry_run,
+      ..Default::default()
+    },
+  };
+  drive(app, state).await
+}
+
+/// Continue the batch that was interrupted by a crash or by quitting the app.
+pub async fn resume_last(app: Host) -> Result<BatchReport, String> {
+  let state = load_state(&app)?.ok_or("No unfinished batch to resume")?;
+  drive(app, state).await
+}
+
+async fn drive(app: Host, mut state: BatchState) -> Result<BatchReport, String> {
+  let total = state.inputs.len();
+  let dry_run = state.report.dry_run;
+  let model = state.report.model.clone();
+  let out_dir = PathBuf::from(&state.out_dir);
+  // Duplicates are only found among files transcribed since the last (re)start.
+  let mut seen = Seen::default();
+  let cancel = whisper::CancelToken::new();
+
+  for i in state.report.items.len()..total {
+    let input = state.inputs[i].clone();
+    let progress = |status: &str, error: Option<String>| {
+      app.emit(
+        "batch://progress",
+        BatchProgressEvent {
+          index: i + 1,
+          total,
+          input: input.clone(),
+          status: status.into(),
+          error,
+        },
+      );
+    };
+
+    let started = std::time::Instant::now();
+    let mut item = process(&app, &input, &out_dir, &model, &state.options, dry_run, &mut seen, &cancel, &progress).await;
+    // Cancelled: this file and the rest stay pending in the saved state for `resume_last`.
+    if cancel.is_cancelled() {
+      progress("cancelled", None);
+      return Err("Batch cancelled; resume it to continue with this file".into());
+    }
+    item.runtime_ms = started.elapsed().as_millis() as u64;
+    state.report.push(item);
+
+    if !dry_run {
+      if let Err(e) = save_state(&app, &state) {
+        app.report(ProgressEvent::Log {
+          line: format!("Couldn't save batch progress: {e}"),
+        });
+      }
+    }
+  }
+
+  let mut report = state.report;
+  report.finished_at = unix_now();
+  if !dry_run {
+    if let Err(e) = save_report(&app, &report) {
+      app.report(ProgressEvent::Log {
+        line: format!("Couldn't save the batch report: {e}"),
+      });
+    }
+    if let Ok(path) = state_path(&app) {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+  Ok(report)
+}
@@ -0,0 +1,179 @@
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CacheUsage {
+  pub transcript_count: u64,
+  pub transcript_bytes: u64,
+  pub wav_count: u64,
+  pub wav_bytes: u64,
+}
+
+fn cache_dir(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.app_data_dir()?.join("cache"))
+}
+
+fn transcripts_dir(app: &Host) -> Result<PathBuf, String> {
+  Ok(cache_dir(app)?.join("transcripts"))
+}
+
+fn wav_dir(app: &Host) -> Result<PathBuf, String> {
+  Ok(cache_dir(app)?.join("wav"))
+}
+
+/// blake3 of the file's bytes, hex.
+pub fn hash_file(path: &Path) -> Result<String, String> {
+  let mut file = fs::File::open(path).map_err(|e| format!("Failed opening {}: {e}", path.display()))?;
+  let mut hasher = blake3::Hasher::new();
+  let mut buf = vec![0u8; 1 << 20];
+  loop {
+    let n = file.read(&mut buf).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+    if n == 0 {
+      break;
+    }
+    hasher.update(&buf[..n]);
+  }
+  Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Cleaned whisper transcripts of one input, one entry per model. `settings` is
+/// everything besides the audio and model that changes whisper's output (language,
+/// prompt, decoding, chunking, ffmpeg filters, …), so changing any of it misses.
+pub struct TranscriptCache {
+  dir: PathBuf,
+  base: String,
+}
+
+impl TranscriptCache {
+  pub fn new(app: &Host, audio_hash: &str, settings: &str) -> Result<Self, String> {
+    Ok(Self {
+      dir: transcripts_dir(app)?,
+      base: format!("{audio_hash}\n{settings}"),
+    })
+  }
+
+  // A redownloaded or replaced model has a different size, so it gets fresh entries.
+  fn entry(&self, model_path: &Path) -> PathBuf {
+    let model = model_path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let size = fs::metadata(model_path).map(|m| m.len()).unwrap_or(0);
+    let key = blake3::hash(format!("{}\n{model}\n{size}", self.base).as_bytes());
+    self.dir.join(format!("{}.lrc", key.to_hex()))
+  }
+
+  pub fn get(&self, model_path: &Path) -> Option<String> {
+    fs::read_to_string(self.entry(model_path)).ok()
+  }
+
+  /// Best-effort: a cache that can't be written just means the next run transcribes again.
+  pub fn put(&self, model_path: &Path, transcript: &str) {
+    if fs::create_dir_all(&self.dir).is_ok() {
+      let _ = fs::write(self.entry(model_path), transcript);
+    }
+  }
+}
+
+/// 16k mono WAV conversions, kept across runs up to a size cap. Least recently used
+/// entries (by mtime, refreshed on every hit) are evicted first.
+pub struct WavCache {
+  dir: PathBuf,
+  entry: PathBuf,
+  cap_bytes: u64,
+}
+
+impl WavCache {
+  pub fn new(app: &Host, audio_hash: &str, ffmpeg_args: &[String], cap_mb: u64) -> Result<Self, String> {
+    let dir = wav_dir(app)?;
+    let key = blake3::hash(format!("{audio_hash}\n{ffmpeg_args:?}").as_bytes());
+    Ok(Self {
+      entry: dir.join(format!("{}.wav", key.to_hex())),
+      dir,
+      cap_bytes: cap_mb * 1024 * 1024,
+    })
+  }
+
+  pub fn is_cached(&self) -> bool {
+    self.entry.exists()
+  }
+
+  /// The cached WAV, or `convert` writes it first.
+  pub async fn get_or_convert<F>(&self, convert: impl FnOnce(PathBuf) -> F) -> Result<(PathBuf, bool), String>
+  where
+    F: std::future::Future<Output = Result<(), String>>,
+  {
+    if self.entry.exists() {
+      let _ = fs::File::options()
+        .write(true)
+        .open(&self.entry)
+        .and_then(|f| f.set_modified(std::time::SystemTime::now()));
+      return Ok((self.entry.clone(), true));
+    }
+
+    fs::create_dir_all(&self.dir).map_err(|e| format!("Failed creating {}: {e}", self.dir.display()))?;
+    // Converted under a temp name so an interrupted ffmpeg never leaves a bad entry.
+    let part = self.entry.with_extension("part.wav");
+    convert(part.clone()).await?;
+    fs::rename(&part, &self.entry).map_err(|e| format!("Failed caching WAV: {e}"))?;
+    self.evict();
+    Ok((self.entry.clone(), false))
+  }
+
+  fn evict(&self) {
+    let Ok(entries) = fs::read_dir(&self.dir) else {
+      return;
+    };
+    let mut files: Vec<(PathBuf, u64, std::time::SystemTime)> = entries
+      .flatten()
+      .filter_map(|e| {
+        let meta = e.metadata().ok()?;
+        Some((e.path(), meta.len(), meta.modified().ok()?))
+      })
+      .filter(|(p, _, _)| p != &self.entry)
+      .collect();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let own = fs::metadata(&self.entry).map(|m| m.len()).unwrap_or(0);
+    let mut total = own + files.iter().map(|(_, len, _)| len).sum::<u64>();
+    for (path, len, _) in files {
+      if total <= self.cap_bytes {
+        break;
+      }
+      if fs::remove_file(&path).is_ok() {
+        total -= len;
+      }
+    }
+  }
+}
+
+fn dir_usage(dir: &Path) -> (u64, u64) {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return (0, 0);
+  };
+  entries
+    .flatten()
+    .filter_map(|e| e.metadata().ok())
+    .filter(|m| m.is_file())
+    .fold((0, 0), |(count, bytes), m| (count + 1, bytes + m.len()))
+}
+
+pub fn usage(app: &Host) -> Result<CacheUsage, String> {
+  let (transcript_count, transcript_bytes) = dir_usage(&transcripts_dir(app)?);
+  let (wav_count, wav_bytes) = dir_usage(&wav_dir(app)?);
+  Ok(CacheUsage {
+    transcript_count,
+    transcript_bytes,
+    wav_count,
+    wav_bytes,
+  })
+}
+
+pub fn clear(app: &Host) -> Result<(), String> {
+  let dir = cache_dir(app)?;
+  if dir.exists() {
+    fs::remove_dir_all(&dir).map_err(|e| format!("Failed clearing cache: {e}"))?;
+  }
+  Ok(())
+}
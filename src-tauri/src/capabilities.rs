@@ -0,0 +1,140 @@
+use lyrictime_core::options::Backend;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::Command;
+
+use crate::whisper;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuInfo {
+  pub name: String,
+  /// Dedicated memory; unset for unified memory (Apple Silicon) or when unknown.
+  pub vram_bytes: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemCapabilities {
+  pub total_ram_bytes: u64,
+  pub available_ram_bytes: u64,
+  pub cpu_cores: usize,
+  pub gpu: Option<GpuInfo>,
+  /// Accelerated backends this machine can use (the `backend` option), besides the default.
+  pub backends: Vec<Backend>,
+  /// Realtime factors from `run_benchmark`, by model.
+  pub benchmarks: HashMap<String, f64>,
+  /// What "auto" resolves to on this machine.
+  pub recommended_model: String,
+}
+
+/// One NVIDIA card, for pinning transcription with the `gpu_index` option.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuDevice {
+  pub index: u32,
+  pub name: String,
+  pub vram_bytes: Option<u64>,
+  pub used_vram_bytes: Option<u64>,
+  /// Current load, 0–100; the idle card is the one to pick.
+  pub utilization_percent: Option<u32>,
+}
+
+/// The NVIDIA cards `nvidia-smi` reports, in its (PCI bus) order; empty without a driver.
+pub fn list_gpus() -> Vec<GpuDevice> {
+  let Some(out) = Command::new("nvidia-smi")
+    .args([
+      "--query-gpu=index,name,memory.total,memory.used,utilization.gpu",
+      "--format=csv,noheader,nounits",
+    ])
+    .output()
+    .ok()
+    .filter(|o| o.status.success())
+  else {
+    return Vec::new();
+  };
+  let mib = |s: &str| s.trim().parse::<u64>().ok().map(|m| m * 1024 * 1024);
+  String::from_utf8_lossy(&out.stdout)
+    .lines()
+    .filter_map(|line| {
+      // Fields a card doesn't support read "[N/A]" and parse to None.
+      let fields: Vec<&str> = line.split(',').collect();
+      let [index, name, total, used, util] = fields.as_slice() else {
+        return None;
+      };
+      Some(GpuDevice {
+        index: index.trim().parse().ok()?,
+        name: name.trim().to_string(),
+        vram_bytes: mib(total),
+        used_vram_bytes: mib(used),
+        utilization_percent: util.trim().parse().ok(),
+      })
+    })
+    .collect()
+}
+
+// NVIDIA is the only discrete GPU whisper.cpp builds commonly use off macOS.
+fn nvidia_gpu() -> Option<GpuInfo> {
+  let gpu = list_gpus().into_iter().next()?;
+  Some(GpuInfo { name: gpu.name, vram_bytes: gpu.vram_bytes })
+}
+
+fn detect_gpu() -> Option<GpuInfo> {
+  if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+    return Some(GpuInfo {
+      name: "Apple Silicon (Metal)".into(),
+      vram_bytes: None,
+    });
+  }
+  nvidia_gpu()
+}
+
+/// Hybrid runs medium, which needs ~2 GB on top of everything else; on 8 GB machines
+/// that is where allocation failures start. A GPU with room for medium also qualifies.
+/// Once benchmarked, hybrid also has to finish within twice the song's length.
+pub fn recommend_model(caps: &SystemCapabilities) -> &'static str {
+  if let (Some(small), Some(medium)) = (caps.benchmarks.get("small"), caps.benchmarks.get("medium")) {
+    if small + medium > 2.0 {
+      return "small";
+    }
+  }
+  let gpu_fits_medium = caps
+    .gpu
+    .as_ref()
+    .is_some_and(|g| g.vram_bytes.map_or(caps.total_ram_bytes >= 16 * GIB, |v| v >= 4 * GIB));
+  if caps.total_ram_bytes >= 12 * GIB || gpu_fits_medium {
+    "hybrid"
+  } else {
+    "small"
+  }
+}
+
+fn detect_backends() -> Vec<Backend> {
+  let mut backends = Vec::new();
+  if whisper::coreml_supported() {
+    backends.push(Backend::CoreMl);
+  }
+  if whisper::openvino_supported() {
+    backends.push(Backend::OpenVino);
+  }
+  backends
+}
+
+pub fn detect(benchmarks: HashMap<String, f64>) -> SystemCapabilities {
+  let mut sys = sysinfo::System::new();
+  sys.refresh_memory();
+
+  let mut caps = SystemCapabilities {
+    total_ram_bytes: sys.total_memory(),
+    available_ram_bytes: sys.available_memory(),
+    cpu_cores: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    gpu: detect_gpu(),
+    backends: detect_backends(),
+    benchmarks,
+    recommended_model: String::new(),
+  };
+  caps.recommended_model = recommend_model(&caps).to_string();
+  caps
+}
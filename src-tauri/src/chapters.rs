@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+
+use crate::ffmpeg_downloader;
+use crate::host::Host;
+use crate::library;
+use crate::split::{self, Part};
+use crate::whisper::{self, ChapterMode, GenerateOptions};
+
+/// Transcribe each chapter of `audio_path` to its own output next to it
+/// (`<file> - 01 - <chapter>.lrc`). Files without chapters get the usual single pass.
+pub async fn generate_per_chapter(
+  app: Host,
+  audio_path: &str,
+  model: &str,
+  mut options: GenerateOptions,
+) -> Result<Vec<String>, String> {
+  let source = Path::new(audio_path);
+  if !source.exists() {
+    return Err("Audio file does not exist".into());
+  }
+  options.chapters = ChapterMode::Off;
+
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let ffprobe = PathBuf::from(ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await?.ffprobe_path);
+  let chapters = whisper::probe_chapters(&ffprobe, source)?;
+  if chapters.is_empty() {
+    return Ok(vec![whisper::generate_lrc_next_to_audio(app, audio_path, model, options).await?]);
+  }
+
+  let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("audio");
+  let parts: Vec<Part> = chapters
+    .iter()
+    .enumerate()
+    .map(|(i, c)| Part {
+      start_ms: c.start_ms,
+      end_ms: Some(c.end_ms),
+      name: format!("{stem} - {:02} - {}", i + 1, c.title),
+      metadata: vec![("title".into(), c.title.clone())],
+    })
+    .collect();
+
+  let out_dir = library::output_dir(&app, source.parent().unwrap_or(Path::new(".")));
+  split::generate_parts(app, source, &parts, &out_dir, model, options).await
+}
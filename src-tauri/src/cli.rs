@@ -0,0 +1,311 @@
+use crate::host::{CliHost, Host};
+use crate::whisper::{ChapterMode, ExistingLyrics, GenerateOptions, OutputFormat};
+use crate::batch::{self, BatchReport};
+use crate::{cache, download, models, presets, settings, whisper};
+
+// NOTE: release builds on Windows use the GUI subsystem, so run the CLI with output
+// redirected (`lyrictime generate song.mp3 > log.txt`) to see progress there.
+const USAGE: &str = "\
+Usage:
+  lyrictime generate [options] <audio file, folder, .cue sheet, playlist or URL>...
+  lyrictime resume [--json] [--verbose] [--report <file>]
+  lyrictime models
+  lyrictime cache [clear]
+  lyrictime benchmark <model>
+
+Generate options:
+  --model <name>       hybrid (default), auto, small, medium, small.en, medium.en or a custom model
+  --preset <name>      use a saved preset's model and options
+  --format <fmt>       lrc, srt or vtt
+  --language <code>    spoken language (en, ja, auto, ...)
+  --chapters <mode>    split (one output per chapter) or markers
+  --existing <policy>  regenerate (default), skip files with an output, or fill-missing
+  --dry-run            list what would be generated, skipped or rejected, then stop
+  --report <file>      also write the batch report to <file> (.csv or .json)
+  --options <json>     full GenerateOptions as JSON (or @file.json)
+  --out-dir <dir>      where audio downloaded from URLs is saved (default: current folder)
+  --json               print progress as JSON lines
+  --verbose            include whisper/ffmpeg output";
+
+/// True if the arguments ask for a CLI subcommand rather than the GUI.
+pub fn is_cli(args: &[String]) -> bool {
+  matches!(
+    args.first().map(String::as_str),
+    Some("generate" | "resume" | "models" | "cache" | "benchmark" | "help" | "--help" | "-h")
+  )
+}
+
+struct GenerateArgs {
+  model: String,
+  preset: Option<String>,
+  format: Option<OutputFormat>,
+  language: Option<String>,
+  chapters: Option<ChapterMode>,
+  existing: Option<ExistingLyrics>,
+  options: Option<GenerateOptions>,
+  out_dir: String,
+  dry_run: bool,
+  report: Option<String>,
+  files: Vec<String>,
+}
+
+fn parse_format(s: &str) -> Result<OutputFormat, String> {
+  match s.to_ascii_lowercase().as_str() {
+    "lrc" => Ok(OutputFormat::Lrc),
+    "srt" => Ok(OutputFormat::Srt),
+    "vtt" => Ok(OutputFormat::Vtt),
+    _ => Err(format!("Unknown format: {s}")),
+  }
+}
+
+fn parse_chapters(s: &str) -> Result<ChapterMode, String> {
+  match s.to_ascii_lowercase().as_str() {
+    "off" => Ok(ChapterMode::Off),
+    "split" => Ok(ChapterMode::Split),
+    "markers" => Ok(ChapterMode::Markers),
+    _ => Err(format!("Unknown chapter mode: {s}")),
+  }
+}
+
+fn parse_existing(s: &str) -> Result<ExistingLyrics, String> {
+  match s.to_ascii_lowercase().as_str() {
+    "regenerate" => Ok(ExistingLyrics::Regenerate),
+    "skip" => Ok(ExistingLyrics::Skip),
+    "fill-missing" => Ok(ExistingLyrics::FillMissing),
+    _ => Err(format!("Unknown existing-lyrics policy: {s}")),
+  }
+}
+
+fn parse_options(s: &str) -> Result<GenerateOptions, String> {
+  let raw = match s.strip_prefix('@') {
+    Some(path) => std::fs::read_to_string(path).map_err(|e| format!("Failed reading {path}: {e}"))?,
+    None => s.to_string(),
+  };
+  serde_json::from_str(&raw).map_err(|e| format!("Invalid --options: {e}"))
+}
+
+/// Split `generate` arguments into settings, host flags and input files.
+fn parse_generate(args: &[String]) -> Result<(GenerateArgs, CliHost), String> {
+  let mut out = GenerateArgs {
+    model: "hybrid".into(),
+    preset: None,
+    format: None,
+    language: None,
+    chapters: None,
+    existing: None,
+    options: None,
+    out_dir: ".".into(),
+    dry_run: false,
+    report: None,
+    files: Vec::new(),
+  };
+  let mut host = CliHost { json: false, verbose: false };
+
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    let mut value = || it.next().cloned().ok_or_else(|| format!("{arg} needs a value"));
+    match arg.as_str() {
+      "--model" | "-m" => out.model = value()?,
+      "--preset" => out.preset = Some(value()?),
+      "--format" | "-f" => out.format = Some(parse_format(&value()?)?),
+      "--language" | "-l" => out.language = Some(value()?),
+      "--chapters" => out.chapters = Some(parse_chapters(&value()?)?),
+      "--existing" => out.existing = Some(parse_existing(&value()?)?),
+      "--options" => out.options = Some(parse_options(&value()?)?),
+      "--out-dir" | "-o" => out.out_dir = value()?,
+      "--dry-run" => out.dry_run = true,
+      "--report" => out.report = Some(value()?),
+      "--json" => host.json = true,
+      "--verbose" | "-v" => host.verbose = true,
+      s if s.starts_with('-') => return Err(format!("Unknown option: {s}")),
+      _ => out.files.push(arg.clone()),
+    }
+  }
+
+  if out.files.is_empty() {
+    return Err("No audio files given".into());
+  }
+  Ok((out, host))
+}
+
+async fn generate(args: &[String]) -> i32 {
+  let (args, cli) = match parse_generate(args) {
+    Ok(parsed) => parsed,
+    Err(e) => {
+      eprintln!("{e}\n\n{USAGE}");
+      return 2;
+    }
+  };
+  let json = cli.json;
+  let host = Host::Cli(cli);
+
+  let (model, mut options) = match presets::resolve(&host, args.model, args.options, args.preset) {
+    Ok(resolved) => resolved,
+    Err(e) => {
+      eprintln!("error: {e}");
+      return 2;
+    }
+  };
+  if args.format.is_some() {
+    options.output_format = args.format;
+  }
+  if args.language.is_some() {
+    options.language = args.language;
+  }
+  if let Some(mode) = args.chapters {
+    options.chapters = mode;
+  }
+  if let Some(policy) = args.existing {
+    options.existing_lyrics = policy;
+  }
+
+  let out_dir = std::path::Path::new(&args.out_dir);
+  let result = batch::run(host, &args.files, out_dir, &model, options, args.dry_run).await;
+  finish_batch(result, json, args.report.as_deref(), args.dry_run)
+}
+
+/// Print (and optionally export) a batch report; returns the exit code.
+fn finish_batch(result: Result<BatchReport, String>, json: bool, export: Option<&str>, always_summarize: bool) -> i32 {
+  let report = match result {
+    Ok(report) => report,
+    Err(e) => {
+      eprintln!("error: {e}");
+      return 1;
+    }
+  };
+  if let Some(path) = export {
+    if let Err(e) = batch::export_report(&report, std::path::Path::new(path)) {
+      eprintln!("error: {e}");
+    }
+  }
+  if json {
+    println!("{}", serde_json::json!({ "event": "report", "payload": report }));
+  } else if report.items.len() > 1 || always_summarize {
+    println!(
+      "{} succeeded, {} planned, {} skipped, {} duplicates, {} rejected, {} failed",
+      report.succeeded, report.planned, report.skipped, report.duplicates, report.rejected, report.failed
+    );
+  }
+
+  if report.failed + report.rejected > 0 {
+    1
+  } else {
+    0
+  }
+}
+
+async fn resume(args: &[String]) -> i32 {
+  let mut cli = CliHost { json: false, verbose: false };
+  let mut export = None;
+  let mut it = args.iter();
+  while let Some(arg) = it.next() {
+    match arg.as_str() {
+      "--json" => cli.json = true,
+      "--verbose" | "-v" => cli.verbose = true,
+      "--report" => match it.next() {
+        Some(path) => export = Some(path.clone()),
+        None => {
+          eprintln!("--report needs a value\n\n{USAGE}");
+          return 2;
+        }
+      },
+      other => {
+        eprintln!("Unknown option: {other}\n\n{USAGE}");
+        return 2;
+      }
+    }
+  }
+
+  let json = cli.json;
+  let result = batch::resume_last(Host::Cli(cli)).await;
+  finish_batch(result, json, export.as_deref(), true)
+}
+
+fn list_models() -> i32 {
+  let host = Host::Cli(CliHost { json: false, verbose: false });
+  match models::list(&host) {
+    Ok(list) => {
+      for m in list {
+        println!("{:<16} {:>8.1} MB  {:<10} {}", m.name, m.size_bytes as f64 / 1e6, m.source, m.path);
+      }
+      0
+    }
+    Err(e) => {
+      eprintln!("error: {e}");
+      1
+    }
+  }
+}
+
+fn manage_cache(args: &[String]) -> i32 {
+  let host = Host::Cli(CliHost { json: false, verbose: false });
+  let result = match args.first().map(String::as_str) {
+    Some("clear") => cache::clear(&host).map(|()| println!("Cache cleared")),
+    None => cache::usage(&host).map(|u| {
+      println!("{} transcripts, {:.1} MB", u.transcript_count, u.transcript_bytes as f64 / 1e6);
+      println!("{} converted WAVs, {:.1} MB", u.wav_count, u.wav_bytes as f64 / 1e6);
+    }),
+    Some(other) => {
+      eprintln!("Unknown cache command: {other}\n\n{USAGE}");
+      return 2;
+    }
+  };
+
+  match result {
+    Ok(()) => 0,
+    Err(e) => {
+      eprintln!("error: {e}");
+      1
+    }
+  }
+}
+
+async fn benchmark(args: &[String]) -> i32 {
+  let [model] = args else {
+    eprintln!("benchmark needs exactly one model\n\n{USAGE}");
+    return 2;
+  };
+  let host = Host::Cli(CliHost { json: false, verbose: false });
+  match whisper::run_benchmark(host, model).await {
+    Ok(r) => {
+      println!(
+        "{}: {:.1} s for {:.0} s of audio (realtime factor {:.2})",
+        r.model,
+        r.elapsed_ms as f64 / 1000.0,
+        r.audio_ms as f64 / 1000.0,
+        r.realtime_factor
+      );
+      0
+    }
+    Err(e) => {
+      eprintln!("error: {e}");
+      1
+    }
+  }
+}
+
+/// Run a CLI subcommand and return the process exit code.
+pub fn run(args: &[String]) -> i32 {
+  let runtime = match tokio::runtime::Runtime::new() {
+    Ok(rt) => rt,
+    Err(e) => {
+      eprintln!("error: {e}");
+      return 1;
+    }
+  };
+
+  let host = Host::Cli(CliHost { json: false, verbose: false });
+  download::set_rate_limit(settings::load(&host).download_limit_kbps);
+
+  match args[0].as_str() {
+    "generate" => runtime.block_on(generate(&args[1..])),
+    "resume" => runtime.block_on(resume(&args[1..])),
+    "models" => list_models(),
+    "cache" => manage_cache(&args[1..]),
+    "benchmark" => runtime.block_on(benchmark(&args[1..])),
+    _ => {
+      println!("{USAGE}");
+      0
+    }
+  }
+}
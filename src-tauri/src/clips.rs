@@ -0,0 +1,107 @@
+use lyrictime_core::encoding;
+use lyrictime_core::lrc::{apply_offset_tag, parse_lrc_lines, LrcLine};
+use lyrictime_core::postprocess::estimated_end_ms;
+use lyrictime_core::progress::{Progress, ProgressEvent};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::ffmpeg_downloader;
+use crate::host::Host;
+use crate::split::sanitize_file_name;
+use crate::whisper;
+
+/// Longest lyric text used in a clip's file name.
+const NAME_CHARS: usize = 60;
+
+/// One exported clip and the line it holds.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LineClip {
+  pub path: String,
+  pub start_ms: u64,
+  pub end_ms: u64,
+  pub text: String,
+  /// Lines sharing the timestamp (bilingual output puts the translation there).
+  pub translation: Option<String>,
+}
+
+struct Span {
+  start_ms: u64,
+  end_ms: u64,
+  text: String,
+  translation: Option<String>,
+}
+
+/// Each lyric line with its span: up to the next line (or gap marker), and for the last
+/// line, about as long as it takes to sing.
+fn line_spans(lines: &[LrcLine]) -> Vec<Span> {
+  let mut spans = Vec::new();
+  let mut i = 0;
+  while i < lines.len() {
+    let l = &lines[i];
+    let same_time = lines[i..].iter().take_while(|o| o.ms == l.ms).count();
+    let next = lines.get(i + same_time);
+    i += same_time;
+    if l.text.trim().is_empty() {
+      continue;
+    }
+
+    let extra: Vec<&str> = lines[i - same_time + 1..i].iter().map(|o| o.text.trim()).filter(|t| !t.is_empty()).collect();
+    let end = next.map_or_else(|| estimated_end_ms(l), |n| n.ms);
+    spans.push(Span {
+      start_ms: l.ms.max(0) as u64,
+      end_ms: end.max(l.ms + 1).max(0) as u64,
+      text: l.text.trim().to_string(),
+      translation: (!extra.is_empty()).then(|| extra.join(" ")),
+    });
+  }
+  spans
+}
+
+/// Cut `audio` into one FLAC per line of `lrc` in `out_dir`, named
+/// `<prefix>NNN - <lyric>.flac`.
+pub(crate) async fn cut_clips(
+  app: &Host,
+  audio: &Path,
+  lrc: &Path,
+  out_dir: &Path,
+  prefix: &str,
+) -> Result<Vec<LineClip>, String> {
+  let bytes = std::fs::read(lrc).map_err(|e| format!("Failed reading {}: {e}", lrc.display()))?;
+  let spans = line_spans(&parse_lrc_lines(&apply_offset_tag(&encoding::decode(&bytes)), true));
+  if spans.is_empty() {
+    return Err(format!("No timed lines in {}", lrc.display()));
+  }
+  std::fs::create_dir_all(out_dir).map_err(|e| format!("Failed creating {}: {e}", out_dir.display()))?;
+
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let ffmpeg = PathBuf::from(ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await?.ffmpeg_path);
+
+  let run = whisper::RunContext::for_app(app);
+  let total = spans.len();
+  let mut clips = Vec::with_capacity(total);
+  for (i, span) in spans.into_iter().enumerate() {
+    app.report(ProgressEvent::Stage {
+      stage: format!("Clip {}/{total}", i + 1),
+      detail: Some(span.text.clone()),
+    });
+    let short: String = span.text.chars().take(NAME_CHARS).collect();
+    let name = sanitize_file_name(&format!("{prefix}{:03} - {short}", i + 1));
+    let path = out_dir.join(format!("{name}.flac"));
+    let metadata = [("title".to_string(), span.text.clone()), ("track".to_string(), (i + 1).to_string())];
+    whisper::run_ffmpeg_cut(app, &run, &ffmpeg, audio, span.start_ms, Some(span.end_ms), None, &metadata, &path).await?;
+    clips.push(LineClip {
+      path: path.display().to_string(),
+      start_ms: span.start_ms,
+      end_ms: span.end_ms,
+      text: span.text,
+      translation: span.translation,
+    });
+  }
+  Ok(clips)
+}
+
+/// Cut `audio` into one FLAC per line of `lrc`, named `NNN - <lyric>.flac`, in `out_dir`.
+pub async fn export_line_clips(app: Host, audio: &Path, lrc: &Path, out_dir: &Path) -> Result<Vec<LineClip>, String> {
+  cut_clips(&app, audio, lrc, out_dir, "").await
+}
@@ -0,0 +1,92 @@
+use lyrictime_core::cue::{self, CueSheet};
+use lyrictime_core::encoding;
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+use crate::library;
+use crate::split::{self, Part};
+use crate::whisper::GenerateOptions;
+
+pub fn is_cue(path: &str) -> bool {
+  Path::new(path)
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| e.eq_ignore_ascii_case("cue"))
+}
+
+// Rips are often re-encoded after the sheet was written ("Album.wav" next to "Album.flac").
+fn find_source(dir: &Path, name: &str) -> Option<PathBuf> {
+  let named = dir.join(name);
+  if named.exists() {
+    return Some(named);
+  }
+  ["flac", "wav", "ape", "wv", "m4a", "mp3", "ogg", "opus"]
+    .iter()
+    .map(|ext| named.with_extension(ext))
+    .find(|p| p.exists())
+}
+
+/// The audio files `cue_path` splits, as found on disk.
+pub fn referenced_files(cue_path: &Path) -> Vec<PathBuf> {
+  let Ok(bytes) = std::fs::read(cue_path) else {
+    return Vec::new();
+  };
+  let dir = cue_path.parent().unwrap_or(Path::new("."));
+  cue::parse_cue(&encoding::decode(&bytes))
+    .files
+    .iter()
+    .filter_map(|f| find_source(dir, &f.name))
+    .collect()
+}
+
+fn parts_for(sheet: &CueSheet, tracks: &[cue::CueTrack], template: &str) -> Vec<Part> {
+  tracks
+    .iter()
+    .enumerate()
+    .map(|(i, track)| {
+      let mut metadata = Vec::new();
+      let title = track.title.as_ref();
+      let artist = track.performer.as_ref().or(sheet.performer.as_ref());
+      for (key, value) in [("title", title), ("artist", artist), ("album", sheet.title.as_ref())] {
+        if let Some(value) = value {
+          metadata.push((key.to_string(), value.clone()));
+        }
+      }
+      Part {
+        start_ms: track.start_ms,
+        end_ms: tracks.get(i + 1).map(|next| next.start_ms),
+        name: cue::track_name(template, sheet, track),
+        metadata,
+      }
+    })
+    .collect()
+}
+
+/// Split the album rip(s) referenced by a .cue sheet into tracks and write one output
+/// per track, named by `options.track_name_template`, next to the sheet.
+pub async fn generate_from_cue(
+  app: Host,
+  cue_path: &str,
+  model: &str,
+  options: GenerateOptions,
+) -> Result<Vec<String>, String> {
+  let cue_path = Path::new(cue_path);
+  let bytes = std::fs::read(cue_path).map_err(|e| format!("Failed reading {}: {e}", cue_path.display()))?;
+  let sheet = cue::parse_cue(&encoding::decode(&bytes));
+  let dir = cue_path.parent().unwrap_or(Path::new("."));
+
+  if sheet.files.iter().all(|f| f.tracks.is_empty()) {
+    return Err(format!("No tracks found in {}", cue_path.display()));
+  }
+
+  let mut written = Vec::new();
+  for file in &sheet.files {
+    let source = find_source(dir, &file.name)
+      .ok_or_else(|| format!("Audio file from cue sheet not found: {}", dir.join(&file.name).display()))?;
+    let parts = parts_for(&sheet, &file.tracks, &options.track_name_template);
+    let out_dir = library::output_dir(&app, dir);
+    written.extend(split::generate_parts(app.clone(), &source, &parts, &out_dir, model, options.clone()).await?);
+  }
+
+  Ok(written)
+}
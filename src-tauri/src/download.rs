@@ -4,9 +4,16 @@ use std::{
   fs,
   io::Write,
   path::Path,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+  },
   time::{Duration, Instant},
 };
-use tauri::{AppHandle, Emitter};
+
+use crate::host::Host;
+use crate::settings;
+use lyrictime_core::progress::{Progress, ProgressEvent};
 
 /// Unified download progress event used by deps + models.
 /// Frontend listens to: `download://progress`
@@ -18,10 +25,103 @@ pub struct DownloadProgressEvent {
   pub total_bytes: Option<u64>,
   pub status: String,          // "downloading" | "done" | "error"
   pub error: Option<String>,
+  /// Average transfer rate so far; absent until bytes arrive.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bytes_per_second: Option<f64>,
+  /// Machine-readable failure kind, e.g. "binary_broken".
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub code: Option<String>,
 }
 
-fn emit(app: &AppHandle, evt: DownloadProgressEvent) {
-  let _ = app.emit("download://progress", evt);
+/// The latest event of every file downloaded this session, so a reloaded frontend
+/// can show download state without waiting for the next event.
+static DOWNLOADS: Mutex<Vec<DownloadProgressEvent>> = Mutex::new(Vec::new());
+
+fn emit(app: &Host, evt: DownloadProgressEvent) {
+  {
+    let mut downloads = DOWNLOADS.lock().unwrap_or_else(|e| e.into_inner());
+    match downloads.iter_mut().find(|d| d.group == evt.group && d.file == evt.file) {
+      Some(d) => *d = evt.clone(),
+      None => downloads.push(evt.clone()),
+    }
+  }
+  app.emit("download://progress", evt);
+}
+
+/// Download rate limit in KiB/s (0 = unlimited). Read for every chunk, so changing it
+/// slows or speeds up downloads already running.
+static RATE_LIMIT_KBPS: AtomicU64 = AtomicU64::new(0);
+
+pub fn set_rate_limit(kbps: u64) {
+  RATE_LIMIT_KBPS.store(kbps, Ordering::Relaxed);
+}
+
+/// Token bucket holding up to one second's worth of the current limit.
+struct Throttle {
+  tokens: f64,
+  refilled: Instant,
+}
+
+impl Throttle {
+  fn new() -> Self {
+    Self { tokens: 0.0, refilled: Instant::now() }
+  }
+
+  /// Take `bytes` from the bucket, first sleeping off any debt beyond what it holds.
+  async fn take(&mut self, bytes: usize) {
+    let kbps = RATE_LIMIT_KBPS.load(Ordering::Relaxed);
+    let now = Instant::now();
+    let elapsed = now.duration_since(self.refilled).as_secs_f64();
+    self.refilled = now;
+    if kbps == 0 {
+      self.tokens = 0.0;
+      return;
+    }
+    let rate = (kbps * 1024) as f64;
+    self.tokens = (self.tokens + elapsed * rate).min(rate) - bytes as f64;
+    if self.tokens < 0.0 {
+      tokio::time::sleep(Duration::from_secs_f64(-self.tokens / rate)).await;
+    }
+  }
+}
+
+/// Every download of this session with its latest state, in start order.
+pub fn status() -> Vec<DownloadProgressEvent> {
+  DOWNLOADS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Report a failure that happened after the transfer itself (e.g. a failed sanity check).
+pub fn report_error(app: &Host, group: &str, file: &str, code: &str, msg: &str) {
+  emit(
+    app,
+    DownloadProgressEvent {
+      group: group.to_string(),
+      file: file.to_string(),
+      downloaded_bytes: 0,
+      total_bytes: None,
+      status: "error".into(),
+      error: Some(msg.to_string()),
+      bytes_per_second: None,
+      code: Some(code.to_string()),
+    },
+  );
+}
+
+/// `code` of the errors returned instead of connecting while offline mode is on.
+pub const OFFLINE_CODE: &str = "offline_mode";
+
+/// Fail with an `offline_mode` error if offline mode is on; `what` needs the network.
+pub fn ensure_online(app: &Host, what: &str) -> Result<(), String> {
+  if !settings::load(app).offline {
+    return Ok(());
+  }
+  let message = format!("Offline mode is on, so {what} is unavailable; turn it off in Settings or install it manually");
+  app.report(ProgressEvent::Error {
+    code: OFFLINE_CODE.into(),
+    message: message.clone(),
+    detail: None,
+  });
+  Err(message)
 }
 
 /// Download a file with streamed progress.
@@ -30,18 +130,54 @@ fn emit(app: &AppHandle, evt: DownloadProgressEvent) {
 /// - Emits throttled progress events (default ~150ms)
 /// - Caller can set executable bit separately if needed
 pub async fn download_with_progress(
-  app: &AppHandle,
+  app: &Host,
+  group: &str,
+  url: &str,
+  dest: &Path,
+  display_name: &str,
+) -> Result<(), String> {
+  download(app, group, url, dest, display_name, false).await
+}
+
+/// `download_with_progress`, continuing from the `.part` left by an interrupted attempt.
+/// Only for URLs whose content never changes (e.g. pinned to a commit), since the
+/// partial file is trusted as is.
+pub async fn download_resumable(
+  app: &Host,
   group: &str,
   url: &str,
   dest: &Path,
   display_name: &str,
 ) -> Result<(), String> {
+  download(app, group, url, dest, display_name, true).await
+}
+
+async fn download(app: &Host, group: &str, url: &str, dest: &Path, display_name: &str, resume: bool) -> Result<(), String> {
+  if let Err(e) = ensure_online(app, &format!("downloading {display_name}")) {
+    report_error(app, group, display_name, OFFLINE_CODE, &e);
+    return Err(e);
+  }
+
+  // Models dirs set to a share or a deep folder would otherwise fail on Windows.
+  let dest = &crate::longpath::extended(dest);
   if let Some(parent) = dest.parent() {
     fs::create_dir_all(parent).map_err(|e| e.to_string())?;
   }
 
+  let tmp = dest.with_extension("part");
+  let mut offset = if resume { fs::metadata(&tmp).map(|m| m.len()).unwrap_or(0) } else { 0 };
+
   let client = reqwest::Client::new();
-  let res = client.get(url).send().await.map_err(|e| e.to_string())?;
+  let mut request = client.get(url);
+  if offset > 0 {
+    request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+  }
+  let mut res = request.send().await.map_err(|e| e.to_string())?;
+  if offset > 0 && res.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+    // The partial file is as long as the whole one or longer: start over.
+    offset = 0;
+    res = client.get(url).send().await.map_err(|e| e.to_string())?;
+  }
   if !res.status().is_success() {
     let msg = format!("Failed to download {display_name}: HTTP {}", res.status());
     emit(
@@ -53,40 +189,62 @@ pub async fn download_with_progress(
         total_bytes: None,
         status: "error".into(),
         error: Some(msg.clone()),
+        bytes_per_second: None,
+        code: None,
       },
     );
     return Err(msg);
   }
 
-  let total = res.content_length();
-  let tmp = dest.with_extension("part");
-  // Clear old partial if any
-  let _ = fs::remove_file(&tmp);
+  // A server that ignores the range sends the whole file again.
+  let resumed = offset > 0 && res.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+  let start = if resumed { offset } else { 0 };
+  let total = res.content_length().map(|len| len + start);
 
-  let mut f = fs::File::create(&tmp).map_err(|e| e.to_string())?;
+  let mut f = if resumed {
+    fs::File::options().append(true).open(&tmp)
+  } else {
+    // Clear old partial if any
+    fs::File::create(&tmp)
+  }
+  .map_err(|e| e.to_string())?;
 
-  let mut downloaded: u64 = 0;
+  let mut downloaded: u64 = start;
   let mut stream = res.bytes_stream();
 
+  let started = Instant::now();
+  let speed = |downloaded: u64| {
+    let secs = started.elapsed().as_secs_f64();
+    (downloaded > start && secs > 0.0).then(|| (downloaded - start) as f64 / secs)
+  };
   let mut last_emit = Instant::now();
   let min_interval = Duration::from_millis(150);
+  let mut throttle = Throttle::new();
 
   emit(
     app,
     DownloadProgressEvent {
       group: group.to_string(),
       file: display_name.to_string(),
-      downloaded_bytes: 0,
+      downloaded_bytes: start,
       total_bytes: total,
       status: "downloading".into(),
       error: None,
+      bytes_per_second: None,
+      code: None,
     },
   );
 
+  // A dropped connection or full disk still ends the file's entry in the registry.
+  let failed = |e: String| {
+    report_error(app, group, display_name, "transfer_failed", &e);
+    e
+  };
   while let Some(chunk) = stream.next().await {
-    let chunk = chunk.map_err(|e| e.to_string())?;
-    f.write_all(&chunk).map_err(|e| e.to_string())?;
+    let chunk = chunk.map_err(|e| failed(e.to_string()))?;
+    f.write_all(&chunk).map_err(|e| failed(e.to_string()))?;
     downloaded += chunk.len() as u64;
+    throttle.take(chunk.len()).await;
 
     if last_emit.elapsed() >= min_interval {
       emit(
@@ -98,6 +256,8 @@ pub async fn download_with_progress(
           total_bytes: total,
           status: "downloading".into(),
           error: None,
+          bytes_per_second: speed(downloaded),
+          code: None,
         },
       );
       last_emit = Instant::now();
@@ -107,7 +267,7 @@ pub async fn download_with_progress(
   // Close file before rename (important on Windows)
   drop(f);
 
-  fs::rename(&tmp, dest).map_err(|e| e.to_string())?;
+  fs::rename(&tmp, dest).map_err(|e| failed(format!("Failed moving {} into place: {e}", dest.display())))?;
 
   emit(
     app,
@@ -118,6 +278,8 @@ pub async fn download_with_progress(
       total_bytes: total,
       status: "done".into(),
       error: None,
+      bytes_per_second: speed(downloaded),
+      code: None,
     },
   );
 
@@ -0,0 +1,203 @@
+use lyrictime_core::encoding::{self, OutputEncoding};
+use lyrictime_core::lrc::{self, LrcLine};
+use lyrictime_core::options::OutputFormat;
+use lyrictime_core::score::{self, Score};
+use lyrictime_core::validate::{self, Finding};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::edits;
+use crate::host::Host;
+use crate::longpath;
+
+/// An LRC ID tag such as `ar` (artist) or `offset`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LrcTag {
+  pub key: String,
+  pub value: String,
+}
+
+/// An .lrc file as the sync editor sees it: header tags plus timed lines. Empty text
+/// marks a gap (a bare timestamp that clears the previous line).
+#[derive(Serialize, Clone, Debug)]
+pub struct LrcFile {
+  pub tags: Vec<LrcTag>,
+  pub lines: Vec<LrcLine>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WriteOptions {
+  /// Defaults to the path's extension, or LRC.
+  pub format: Option<OutputFormat>,
+  /// Written above the lines; LRC only.
+  pub tags: Vec<LrcTag>,
+  /// Defaults to the encoding of the file being replaced, or UTF-8.
+  pub encoding: Option<OutputEncoding>,
+}
+
+/// An `[offset:]` tag is applied to the lines rather than listed with the tags.
+pub fn read_lrc_file(path: &Path) -> Result<LrcFile, String> {
+  let bytes = std::fs::read(path).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  let text = lrc::apply_offset_tag(&encoding::decode(&bytes));
+  Ok(LrcFile {
+    tags: lrc::parse_lrc_tags(&text)
+      .into_iter()
+      .map(|(key, value)| LrcTag { key, value })
+      .collect(),
+    lines: lrc::parse_lrc_lines(&text, true),
+  })
+}
+
+fn format_for(path: &Path) -> OutputFormat {
+  match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+    Some("srt") => OutputFormat::Srt,
+    Some("vtt") => OutputFormat::Vtt,
+    _ => OutputFormat::Lrc,
+  }
+}
+
+pub fn write_lrc_file(path: &Path, mut lines: Vec<LrcLine>, options: WriteOptions) -> Result<(), String> {
+  lines.sort_by_key(|l| l.ms);
+  let format = options.format.unwrap_or_else(|| format_for(path));
+  let text = match format {
+    OutputFormat::Lrc => {
+      let tags: Vec<(String, String)> = options.tags.into_iter().map(|t| (t.key, t.value)).collect();
+      lrc::lines_to_lrc_with_tags(&tags, &lines)
+    }
+    // Subtitles have no gap markers; a cue simply ends.
+    _ => {
+      lines.retain(|l| !l.text.trim().is_empty());
+      lrc::render(format, &lines, 0)
+    }
+  };
+  let encoding = options
+    .encoding
+    .unwrap_or_else(|| std::fs::read(longpath::extended(path)).map(|b| encoding::detect(&b)).unwrap_or_default());
+  std::fs::write(longpath::extended(path), encoding::encode(&text, encoding))
+    .map_err(|e| format!("Failed writing {}: {e}", path.display()))
+}
+
+fn rewrite_file(path: &Path, f: impl FnOnce(&str) -> String) -> Result<(), String> {
+  let bytes = std::fs::read(longpath::extended(path)).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  let rewritten = f(&encoding::decode(&bytes));
+  std::fs::write(longpath::extended(path), encoding::encode(&rewritten, encoding::detect(&bytes)))
+    .map_err(|e| format!("Failed writing {}: {e}", path.display()))
+}
+
+// The generated-lines record moves along, so regenerating still tells edits apart.
+fn retime_file(app: &Host, path: &Path, f: impl Fn(i64) -> i64 + Copy) -> Result<(), String> {
+  rewrite_file(path, |text| lrc::retime_lrc(text, f))?;
+  edits::retime(app, path, f)
+}
+
+/// Move every timestamp by `offset_ms` (negative = earlier, clamped at 0). With `as_tag`
+/// the lines stay as they are and the `[offset:]` tag is adjusted instead.
+pub fn shift_lrc(app: &Host, path: &Path, offset_ms: i64, as_tag: bool) -> Result<(), String> {
+  let shift = |ms: i64| (ms + offset_ms).max(0);
+  if as_tag {
+    // A positive tag shows lyrics earlier, so shifting later lowers it.
+    rewrite_file(path, |text| lrc::set_offset_tag(text, lrc::offset_tag(text) - offset_ms))?;
+    return edits::retime(app, path, shift);
+  }
+  retime_file(app, path, shift)
+}
+
+/// Multiply every timestamp by `factor`, e.g. 44100/48000 for a resampled master.
+pub fn scale_lrc(app: &Host, path: &Path, factor: f64) -> Result<(), String> {
+  if !factor.is_finite() || factor <= 0.0 {
+    return Err(format!("Scale factor must be positive, got {factor}"));
+  }
+  retime_file(app, path, |ms| (ms as f64 * factor).round() as i64)
+}
+
+/// Lint an LRC file; `max_chars` defaults to what two-line displays fit and `max_cps` to
+/// what a karaoke singer keeps up with.
+pub fn validate_lrc(path: &Path, max_chars: Option<usize>, max_cps: Option<f64>) -> Result<Vec<Finding>, String> {
+  let bytes = std::fs::read(path).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  Ok(validate::validate_lrc(
+    &bytes,
+    max_chars.unwrap_or(validate::DEFAULT_MAX_CHARS),
+    max_cps.unwrap_or(validate::DEFAULT_MAX_CPS),
+  ))
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NormalizeOptions {
+  /// Lines closer than this are pushed apart (out-of-order lines are sorted first).
+  pub min_gap_ms: i64,
+  /// Keep cue lines such as "(guitar solo)" instead of dropping them.
+  pub keep_cues: bool,
+  /// Keep ID tags ([ar:], [ti:], ...).
+  pub keep_tags: bool,
+  /// Defaults to `<name>.clean.lrc` next to the input.
+  pub out_path: Option<String>,
+}
+
+impl Default for NormalizeOptions {
+  fn default() -> Self {
+    Self {
+      min_gap_ms: 250,
+      keep_cues: false,
+      keep_tags: true,
+      out_path: None,
+    }
+  }
+}
+
+/// Run a third-party LRC through the same cleanup generated output gets and write the
+/// result as a copy. Returns the written path.
+pub fn normalize_lrc_file(path: &Path, options: NormalizeOptions) -> Result<String, String> {
+  let bytes = std::fs::read(longpath::extended(path)).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  let text = lrc::apply_offset_tag(&encoding::decode(&bytes));
+  let text = text.as_str();
+
+  let normalized = lrc::normalize_lrc_timestamps(&lrc::clean_lrc(text, options.keep_cues), options.min_gap_ms);
+  if normalized.is_empty() {
+    return Err(format!("No timed lines in {}", path.display()));
+  }
+  let tags = if options.keep_tags { lrc::parse_lrc_tags(text) } else { Vec::new() };
+  let out: String = tags.iter().map(|(k, v)| format!("[{k}:{v}]\n")).chain([normalized]).collect();
+
+  let out_path = match options.out_path {
+    Some(p) => PathBuf::from(p),
+    None => path.with_extension("clean.lrc"),
+  };
+  if out_path == path {
+    return Err("Refusing to overwrite the input; choose a different output path".into());
+  }
+  std::fs::write(longpath::extended(&out_path), encoding::encode(&out, encoding::detect(&bytes)))
+    .map_err(|e| format!("Failed writing {}: {e}", out_path.display()))?;
+  Ok(out_path.display().to_string())
+}
+
+/// Word error rate and timing error of `generated` against a reference LRC or plain lyrics.
+pub fn score_lrc(generated: &Path, reference: &Path) -> Result<Score, String> {
+  let read = |p: &Path| {
+    std::fs::read(p)
+      .map(|b| lrc::apply_offset_tag(&encoding::decode(&b)))
+      .map_err(|e| format!("Failed reading {}: {e}", p.display()))
+  };
+  Ok(score::score(&read(generated)?, &read(reference)?))
+}
+
+/// Last file `lyric_at` parsed; displays poll many times a second on the same song.
+static LYRIC_AT_CACHE: Mutex<Option<(PathBuf, SystemTime, Vec<LrcLine>)>> = Mutex::new(None);
+
+/// Active line and time until the next one at `position_ms` of `path`'s song.
+pub fn lyric_at(path: &Path, position_ms: i64) -> Result<lrc::LyricPosition, String> {
+  let modified = std::fs::metadata(path)
+    .and_then(|m| m.modified())
+    .map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+
+  let mut cache = LYRIC_AT_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+  let fresh = matches!(&*cache, Some((p, m, _)) if p == path && *m == modified);
+  if !fresh {
+    *cache = Some((path.to_path_buf(), modified, read_lrc_file(path)?.lines));
+  }
+  let lines = cache.as_ref().map(|(_, _, lines)| lines.as_slice()).unwrap_or_default();
+  Ok(lrc::lyric_at(lines, position_ms))
+}
@@ -0,0 +1,84 @@
+use lyrictime_core::encoding;
+use lyrictime_core::lrc::{self, LrcLine};
+use lyrictime_core::preserve;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+
+/// The lines LyricTime last wrote to an output; anything else in the file is a user edit.
+#[derive(Serialize, Deserialize)]
+struct Generated {
+  path: String,
+  lines: Vec<LrcLine>,
+}
+
+// Kept in app data rather than next to the lyrics, one file per output path.
+fn record_path(app: &Host, output: &Path) -> Result<PathBuf, String> {
+  let key = output.canonicalize().unwrap_or_else(|_| output.to_path_buf());
+  let hash = blake3::hash(key.to_string_lossy().as_bytes()).to_hex();
+  Ok(app.app_data_dir()?.join("generated").join(format!("{hash}.json")))
+}
+
+fn load(app: &Host, output: &Path) -> Option<Vec<LrcLine>> {
+  let bytes = fs::read(record_path(app, output).ok()?).ok()?;
+  serde_json::from_slice::<Generated>(&bytes).ok().map(|g| g.lines)
+}
+
+/// Carry hand-edited lines of the existing `output` over into `generated` (LRC text).
+/// Returns the text to write and how many edits were kept.
+pub fn preserve(app: &Host, output: &Path, generated: &str) -> (String, usize) {
+  let (Some(previous), Ok(current)) = (load(app, output), fs::read(output)) else {
+    return (generated.to_string(), 0);
+  };
+  let current = lrc::apply_offset_tag(&encoding::decode(&current));
+  let edits = preserve::edited_lines(&lrc::parse_lrc_lines(&current, true), &previous);
+  if edits.is_empty() {
+    return (generated.to_string(), 0);
+  }
+  let lines = preserve::keep_edits(lrc::parse_lrc_lines(generated, true), &edits);
+  (lrc::lines_to_lrc_with_tags(&lrc::parse_lrc_tags(generated), &lines), edits.len())
+}
+
+fn save(app: &Host, output: &Path, lines: Vec<LrcLine>) -> Result<(), String> {
+  let path = record_path(app, output)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("Failed creating {}: {e}", parent.display()))?;
+  }
+  let record = Generated {
+    path: output.display().to_string(),
+    lines,
+  };
+  let json = serde_json::to_vec(&record).map_err(|e| e.to_string())?;
+  fs::write(&path, json).map_err(|e| format!("Failed writing {}: {e}", path.display()))
+}
+
+/// Remember the machine-generated lines of `output` for the next regeneration.
+pub fn remember(app: &Host, output: &Path, generated: &str) -> Result<(), String> {
+  save(app, output, lrc::parse_lrc_lines(generated, true))
+}
+
+/// After the editor shifts or scales `output`: move the record the same way, so the
+/// re-timed lines aren't all taken for hand edits.
+pub fn retime(app: &Host, output: &Path, f: impl Fn(i64) -> i64) -> Result<(), String> {
+  let Some(mut lines) = load(app, output) else {
+    return Ok(());
+  };
+  for l in &mut lines {
+    l.ms = f(l.ms);
+  }
+  save(app, output, lines)
+}
+
+/// After `regenerate_range`: the new lines of `start_ms..end_ms` are machine lines too.
+pub fn remember_range(app: &Host, output: &Path, start_ms: i64, end_ms: i64, lines: &[LrcLine]) -> Result<(), String> {
+  let mut record: Vec<LrcLine> = load(app, output)
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|l| !(start_ms..end_ms).contains(&l.ms))
+    .collect();
+  record.extend(lines.iter().cloned());
+  record.sort_by_key(|l| l.ms);
+  save(app, output, record)
+}
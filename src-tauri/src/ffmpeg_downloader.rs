@@ -1,7 +1,8 @@
 use std::path::{Path, PathBuf};
-use tauri::{AppHandle, Emitter, Manager};
 
-use crate::download;
+use crate::host::Host;
+use crate::{archive, download, settings};
+use crate::whisper::{find_system_executable, responds};
 
 
 #[derive(serde::Serialize)]
@@ -10,8 +11,8 @@ pub struct FfmpegPaths {
   pub ffprobe_path: String,
 }
 
-fn bin_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
-  Ok(app.path().app_data_dir()?.join("bin"))
+pub fn bin_dir(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.app_data_dir()?.join("bin"))
 }
 
 
@@ -28,10 +29,100 @@ fn ensure_executable(path: &Path) -> Result<(), String> {
   Ok(())
 }
 
+/// Fetch `url` into `path`. Archives (.zip/.tar.xz, as upstream ffmpeg builds ship) are
+/// downloaded next to it, every executable in `extract` is unpacked into the same
+/// directory, and the archive is removed.
+async fn fetch(app: &Host, url: &str, path: &Path, name: &str, extract: &[&str]) -> Result<(), String> {
+  if !archive::is_archive(url) {
+    return download::download_with_progress(app, "deps", url, path, name).await;
+  }
+
+  let dir = path.parent().ok_or("Invalid binary path")?;
+  let archive_name = archive::file_name_from_url(url);
+  let archive_path = dir.join(&archive_name);
+
+  download::download_with_progress(app, "deps", url, &archive_path, &archive_name).await?;
+  let extracted = archive::extract_files(&archive_path, extract, dir);
+  let _ = std::fs::remove_file(&archive_path);
+
+  for p in extracted? {
+    ensure_executable(&p)?;
+  }
+  if !path.exists() {
+    return Err(format!("{archive_name} doesn't contain {name}"));
+  }
+  Ok(())
+}
+
+/// Download `path` if missing, then make sure it actually runs (`probe_arg` exits 0).
+/// A copy that doesn't (truncated, wrong architecture) is deleted and fetched once more
+/// before giving up.
+pub async fn ensure_binary(
+  app: &Host,
+  url: &str,
+  path: &Path,
+  name: &str,
+  probe_arg: &str,
+  extract: &[&str],
+) -> Result<(), String> {
+  if path.exists() && responds(path, probe_arg) {
+    return Ok(());
+  }
+
+  for _ in 0..2 {
+    let _ = std::fs::remove_file(path);
+    fetch(app, url, path, name, extract).await?;
+    ensure_executable(path)?;
+    if responds(path, probe_arg) {
+      return Ok(());
+    }
+  }
+
+  let _ = std::fs::remove_file(path);
+  let msg = format!("Downloaded {name} doesn't run on this system (corrupt download or wrong architecture)");
+  download::report_error(app, "deps", name, "binary_broken", &msg);
+  Err(msg)
+}
+
+/// Release asset URLs for ffmpeg and ffprobe on this platform.
+// NOTE: GitHub Releases are flat files (no folders). Upload these 4 files as assets
+// under tag `deps`: ffmpeg.exe, ffprobe.exe, ffmpeg, ffprobe
+pub fn default_urls() -> (String, String) {
+  #[cfg(windows)]
+  let (ffmpeg_url, ffprobe_url) = (
+    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffmpeg.exe".to_string(),
+    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffprobe.exe".to_string(),
+  );
+
+  #[cfg(not(windows))]
+  let (ffmpeg_url, ffprobe_url) = (
+    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffmpeg".to_string(),
+    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffprobe".to_string(),
+  );
+
+  (ffmpeg_url, ffprobe_url)
+}
+
+/// Whether ffmpeg and ffprobe are available without downloading them: configured,
+/// downloaded before or on the system PATH.
+pub fn installed(app: &Host) -> bool {
+  #[cfg(windows)]
+  let (ffmpeg_name, ffprobe_name) = ("ffmpeg.exe", "ffprobe.exe");
+  #[cfg(not(windows))]
+  let (ffmpeg_name, ffprobe_name) = ("ffmpeg", "ffprobe");
+
+  if let Some(ffmpeg) = settings::ffmpeg_override(app) {
+    return ffmpeg.is_file() && ffmpeg.with_file_name(ffprobe_name).is_file();
+  }
+  let downloaded = bin_dir(app).is_ok_and(|dir| dir.join(ffmpeg_name).exists() && dir.join(ffprobe_name).exists());
+  downloaded
+    || (find_system_executable("ffmpeg", "-version").is_some() && find_system_executable("ffprobe", "-version").is_some())
+}
+
 /// Downloads ffmpeg + ffprobe into app data dir if missing.
 /// You should host the binaries as direct-download URLs (recommended: GitHub Release assets).
 pub async fn ensure_ffmpeg(
-  app: AppHandle,
+  app: Host,
   ffmpeg_url: String,
   ffprobe_url: String,
 ) -> Result<FfmpegPaths, String> {
@@ -42,18 +133,41 @@ pub async fn ensure_ffmpeg(
   #[cfg(not(windows))]
   let (ffmpeg_name, ffprobe_name) = ("ffmpeg", "ffprobe");
 
+  if let Some(ffmpeg) = settings::ffmpeg_override(&app) {
+    let ffprobe = ffmpeg.with_file_name(ffprobe_name);
+    for p in [&ffmpeg, &ffprobe] {
+      if !p.is_file() {
+        return Err(format!("Configured ffmpeg install is incomplete, missing: {}", p.display()));
+      }
+    }
+    return Ok(FfmpegPaths {
+      ffmpeg_path: ffmpeg.to_string_lossy().to_string(),
+      ffprobe_path: ffprobe.to_string_lossy().to_string(),
+    });
+  }
+
   let ffmpeg_path = dir.join(ffmpeg_name);
   let ffprobe_path = dir.join(ffprobe_name);
 
-  if !ffmpeg_path.exists() {
-    download::download_with_progress(&app, "deps", &ffmpeg_url, &ffmpeg_path, ffmpeg_name).await?;
-    ensure_executable(&ffmpeg_path)?;
-  }
-  if !ffprobe_path.exists() {
-    download::download_with_progress(&app, "deps", &ffprobe_url, &ffprobe_path, ffprobe_name).await?;
-    ensure_executable(&ffprobe_path)?;
+  // A working system ffmpeg saves a ~100 MB download; both tools must come from there
+  // so their versions match.
+  if !ffmpeg_path.exists() || !ffprobe_path.exists() {
+    if let (Some(ffmpeg), Some(ffprobe)) = (
+      find_system_executable("ffmpeg", "-version"),
+      find_system_executable("ffprobe", "-version"),
+    ) {
+      return Ok(FfmpegPaths {
+        ffmpeg_path: ffmpeg.to_string_lossy().to_string(),
+        ffprobe_path: ffprobe.to_string_lossy().to_string(),
+      });
+    }
   }
 
+  // Both URLs may name the same archive; whichever is fetched first unpacks both tools.
+  let tools = [ffmpeg_name, ffprobe_name];
+  ensure_binary(&app, &ffmpeg_url, &ffmpeg_path, ffmpeg_name, "-version", &tools).await?;
+  ensure_binary(&app, &ffprobe_url, &ffprobe_path, ffprobe_name, "-version", &tools).await?;
+
   Ok(FfmpegPaths {
     ffmpeg_path: ffmpeg_path.to_string_lossy().to_string(),
     ffprobe_path: ffprobe_path.to_string_lossy().to_string(),
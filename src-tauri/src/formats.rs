@@ -0,0 +1,136 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+/// An input format the pipeline handles, for the UI's supported-format list.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaFormat {
+  pub name: &'static str,
+  pub extensions: &'static [&'static str],
+  /// ffprobe `format_name` entries it's detected as.
+  pub containers: &'static [&'static str],
+  /// Codec name prefixes ("pcm_" for every PCM variant); empty for any codec.
+  pub codecs: &'static [&'static str],
+  /// Whisper reads it itself; the rest go through ffmpeg.
+  pub direct: bool,
+  /// Only the audio track is used.
+  pub video: bool,
+  /// MP4 `ftyp` major brands that set it apart from others with the same container and
+  /// codec; empty for any.
+  #[serde(skip)]
+  pub brands: &'static [&'static str],
+}
+
+const fn audio(
+  name: &'static str,
+  extensions: &'static [&'static str],
+  containers: &'static [&'static str],
+  codecs: &'static [&'static str],
+  direct: bool,
+) -> MediaFormat {
+  MediaFormat { name, extensions, containers, codecs, direct, video: false, brands: &[] }
+}
+
+const fn video(name: &'static str, extensions: &'static [&'static str], containers: &'static [&'static str]) -> MediaFormat {
+  MediaFormat { name, extensions, containers, codecs: &[], direct: false, video: true, brands: &[] }
+}
+
+const fn branded(format: MediaFormat, brands: &'static [&'static str]) -> MediaFormat {
+  MediaFormat { brands, ..format }
+}
+
+/// Most specific first: detection takes the first container, codec and brand match.
+pub const FORMATS: &[MediaFormat] = &[
+  audio("WAV", &["wav"], &["wav"], &["pcm_"], true),
+  audio("MP3", &["mp3"], &["mp3"], &["mp3"], true),
+  audio("FLAC", &["flac"], &["flac"], &["flac"], true),
+  audio("Ogg Vorbis", &["ogg", "oga"], &["ogg"], &["vorbis"], true),
+  audio("Opus", &["opus", "ogg"], &["ogg"], &["opus"], false),
+  branded(audio("M4B audiobook", &["m4b"], &["mp4"], &["aac"], false), &["M4B "]),
+  branded(video("MP4 / MOV video", &["mp4", "m4v", "mov"], &["mp4", "mov"]), VIDEO_BRANDS),
+  audio("M4A / AAC", &["m4a", "aac"], &["mp4", "aac"], &["aac", "alac"], false),
+  audio("WMA", &["wma"], &["asf"], &["wma"], false),
+  audio("AIFF", &["aiff", "aif"], &["aiff"], &["pcm_"], false),
+  audio("Monkey's Audio (APE)", &["ape"], &["ape"], &["ape"], false),
+  audio("WavPack", &["wv"], &["wv"], &["wavpack"], false),
+  audio("Matroska audio", &["mka"], &["matroska"], &[], false),
+  video("Matroska / WebM video", &["mkv", "webm"], &["matroska", "webm"]),
+  video("AVI video", &["avi"], &["avi"]),
+];
+
+// What cameras, phones and encoders stamp on video files; plain audio says "M4A ".
+const VIDEO_BRANDS: &[&str] = &["isom", "iso2", "iso4", "iso5", "iso6", "mp41", "mp42", "avc1", "M4V ", "qt  "];
+
+/// The major brand in an MP4/MOV file's leading `ftyp` box ("M4A ", "M4B ", "isom", ...).
+pub fn mp4_brand(path: &Path) -> Option<String> {
+  let mut head = [0u8; 12];
+  std::fs::File::open(path).ok()?.read_exact(&mut head).ok()?;
+  (&head[4..8] == b"ftyp").then(|| String::from_utf8_lossy(&head[8..12]).into_owned())
+}
+
+/// The format ffprobe's `container` (e.g. "mov,mp4,m4a,3gp,3g2,mj2") and first audio
+/// `codec` amount to, with the MP4 brand of the file at `path` telling audiobooks and
+/// videos from songs; `None` for ones ffmpeg may still read but nobody tested.
+pub fn detect(container: &str, codec: &str, path: &Path) -> Option<&'static MediaFormat> {
+  let brand = mp4_brand(path);
+  FORMATS.iter().find(|f| {
+    container.split(',').any(|c| f.containers.contains(&c))
+      && (f.codecs.is_empty() || f.codecs.iter().any(|p| codec.starts_with(p)))
+      && (f.brands.is_empty() || brand.as_deref().is_some_and(|b| f.brands.contains(&b)))
+  })
+}
+
+/// Extensions batch and folder scans pick up: every format's, plus cue sheets.
+pub fn is_media_extension(ext: &str) -> bool {
+  ext.eq_ignore_ascii_case("cue") || FORMATS.iter().flat_map(|f| f.extensions).any(|e| ext.eq_ignore_ascii_case(e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Headers of real files: the `ftyp` box for the MP4 family, a complete tiny WAV.
+  fn fixture(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("formats").join(name)
+  }
+
+  fn detected(container: &str, codec: &str, file: &str) -> Option<&'static str> {
+    detect(container, codec, &fixture(file)).map(|f| f.name)
+  }
+
+  const MP4: &str = "mov,mp4,m4a,3gp,3g2,mj2";
+
+  #[test]
+  fn detect_matches_container_and_codec() {
+    assert_eq!(detected("wav", "pcm_s16le", "silence.wav"), Some("WAV"));
+    assert_eq!(detected("ogg", "vorbis", "missing.ogg"), Some("Ogg Vorbis"));
+    assert_eq!(detected("ogg", "opus", "missing.opus"), Some("Opus"));
+    assert_eq!(detected("matroska,webm", "opus", "missing.mka"), Some("Matroska audio"));
+    assert!(detected("mp3", "pcm_s16le", "silence.wav").is_none());
+    assert!(detected("dsf", "dsd_lsbf", "missing.dsf").is_none());
+  }
+
+  #[test]
+  fn detect_tells_the_mp4_family_apart_by_brand() {
+    assert_eq!(mp4_brand(&fixture("audiobook.m4b")).as_deref(), Some("M4B "));
+    assert_eq!(mp4_brand(&fixture("silence.wav")), None);
+    assert_eq!(detected(MP4, "aac", "audiobook.m4b"), Some("M4B audiobook"));
+    assert_eq!(detected(MP4, "aac", "song.m4a"), Some("M4A / AAC"));
+    assert_eq!(detected(MP4, "alac", "song.m4a"), Some("M4A / AAC"));
+    assert_eq!(detected(MP4, "aac", "clip.mp4"), Some("MP4 / MOV video"));
+    assert_eq!(detected(MP4, "aac", "clip.mov"), Some("MP4 / MOV video"));
+    // Unreadable: judged by container and codec alone.
+    assert_eq!(detected(MP4, "aac", "missing.m4a"), Some("M4A / AAC"));
+  }
+
+  #[test]
+  fn media_extensions_ignore_case() {
+    for ext in ["mp3", "FLAC", "Mkv", "cue", "CUE"] {
+      assert!(is_media_extension(ext), "{ext}");
+    }
+    for ext in ["lrc", "txt", "m3u", ""] {
+      assert!(!is_media_extension(ext), "{ext}");
+    }
+  }
+}
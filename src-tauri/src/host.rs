@@ -0,0 +1,113 @@
+use lyrictime_core::progress::{Progress, ProgressEvent};
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Bundle identifier from tauri.conf.json; the CLI shares the app's data directory.
+const IDENTIFIER: &str = "com.seanengland.lyrictime";
+
+/// Where the pipeline runs: inside the Tauri app (events go to the webview) or
+/// headless from the command line (events are printed to stdout).
+#[derive(Clone)]
+pub enum Host {
+  App(AppHandle),
+  Cli(CliHost),
+}
+
+#[derive(Clone)]
+pub struct CliHost {
+  /// One JSON object per event instead of human-readable lines.
+  pub json: bool,
+  /// Print whisper/ffmpeg output too, not just stages.
+  pub verbose: bool,
+}
+
+impl From<AppHandle> for Host {
+  fn from(app: AppHandle) -> Self {
+    Host::App(app)
+  }
+}
+
+impl Host {
+  pub fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) {
+    match self {
+      Host::App(app) => {
+        let _ = app.emit(event, payload);
+      }
+      Host::Cli(cli) => cli.print(event, &payload),
+    }
+  }
+
+  /// The CLI's `--verbose`.
+  pub fn verbose(&self) -> bool {
+    matches!(self, Host::Cli(cli) if cli.verbose)
+  }
+
+  /// Same directory the app uses (settings, downloaded models and binaries).
+  pub fn app_data_dir(&self) -> Result<PathBuf, String> {
+    match self {
+      Host::App(app) => app.path().app_data_dir().map_err(|e| e.to_string()),
+      Host::Cli(_) => dirs::data_dir()
+        .map(|d| d.join(IDENTIFIER))
+        .ok_or_else(|| "No data directory on this system".into()),
+    }
+  }
+
+  /// Bundled resources: next to the executable, or `../Resources` inside a macOS bundle.
+  pub fn resource_dir(&self) -> Result<PathBuf, String> {
+    match self {
+      Host::App(app) => app.path().resource_dir().map_err(|e| e.to_string()),
+      Host::Cli(_) => {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let dir = exe.parent().ok_or("Executable has no parent directory")?;
+        let bundle = dir.join("..").join("Resources");
+        Ok(if bundle.is_dir() { bundle } else { dir.to_path_buf() })
+      }
+    }
+  }
+}
+
+impl Progress for Host {
+  fn report(&self, event: ProgressEvent) {
+    self.emit("lyric_progress", event);
+  }
+}
+
+impl CliHost {
+  fn print<S: Serialize>(&self, event: &str, payload: &S) {
+    let value = serde_json::to_value(payload).unwrap_or_default();
+
+    if self.json {
+      println!("{}", serde_json::json!({ "event": event, "payload": value }));
+      // The per-file event scripts used before batches existed; still sent alongside.
+      if event == "batch://progress" && value["status"] == "failed" {
+        let payload = serde_json::json!({ "file": value["input"], "error": value["error"] });
+        println!("{}", serde_json::json!({ "event": "failed", "payload": payload }));
+      }
+      return;
+    }
+
+    let field = |k: &str| value[k].as_str().unwrap_or_default().to_string();
+    match (event, value["kind"].as_str(), value["status"].as_str()) {
+      (_, Some("stage"), _) => match value["detail"].as_str() {
+        Some(detail) => println!("{}: {detail}", field("stage")),
+        None => println!("{}", field("stage")),
+      },
+      (_, Some("log"), _) if self.verbose => println!("  {}", field("line")),
+      (_, Some("done"), _) => println!("Wrote {}", field("outputPath")),
+      (_, Some("redirected"), _) => {
+        eprintln!("warning: {} ({}); wrote {} instead", field("from"), field("reason"), field("to"))
+      }
+      (_, Some("error"), _) => eprintln!("error: {}", field("message")),
+      ("batch://progress", _, Some("running")) => println!("[{}/{}] {}", value["index"], value["total"], field("input")),
+      ("batch://progress", _, Some("planned")) => println!("Would generate {}", field("input")),
+      ("batch://progress", _, Some("rejected")) => eprintln!("Rejected {}: {}", field("input"), field("error")),
+      ("batch://progress", _, Some("skipped")) => println!("Skipped {}: {}", field("input"), field("error")),
+      ("batch://progress", _, Some("duplicate")) => println!("Copied {}: {}", field("input"), field("error")),
+      ("batch://progress", _, Some("failed")) => eprintln!("error: {}: {}", field("input"), field("error")),
+      ("download://progress", _, Some("done")) => println!("Downloaded {}", field("file")),
+      ("download://progress", _, Some("error")) => eprintln!("error: {}", field("error")),
+      _ => {}
+    }
+  }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use lyrictime_core::progress::{Progress, ProgressEvent};
+
+use crate::host::Host;
+use crate::settings;
+
+/// Keep lyrics in a separate tree that mirrors the music library
+/// (`Music/Artist/Album/Track.flac` -> `Lyrics/Artist/Album/Track.lrc`), for music
+/// folders that are read-only or shouldn't be touched.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LyricsLibrary {
+  pub music_root: String,
+  pub lyrics_root: String,
+}
+
+impl LyricsLibrary {
+  fn mirror(&self, dir: &Path) -> Option<PathBuf> {
+    let music_root = Path::new(self.music_root.trim());
+    let lyrics_root = Path::new(self.lyrics_root.trim());
+    if music_root.as_os_str().is_empty() || lyrics_root.as_os_str().is_empty() {
+      return None;
+    }
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    // Compare resolved paths so relative inputs, symlinks and drive-letter case still match.
+    let resolve = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    let relative = resolve(dir).strip_prefix(resolve(music_root)).ok()?.to_path_buf();
+    Some(lyrics_root.join(relative))
+  }
+}
+
+/// Where outputs for files in `dir` go: its mirror in the lyrics library when one is
+/// configured and `dir` is inside the music root, otherwise `dir` itself.
+pub fn output_dir(app: &Host, dir: &Path) -> PathBuf {
+  settings::load(app)
+    .lyrics_library
+    .and_then(|library| library.mirror(dir))
+    .unwrap_or_else(|| dir.to_path_buf())
+}
+
+/// The `ext` file that belongs to `audio` (`song.flac` -> `song.lrc`), in the library or
+/// next to it.
+pub fn output_path(app: &Host, audio: &Path, ext: &str) -> PathBuf {
+  let dir = audio.parent().unwrap_or(Path::new(""));
+  let name = audio.file_name().map(PathBuf::from).unwrap_or_default();
+  output_dir(app, dir).join(name).with_extension(ext)
+}
+
+fn fallback_dir(app: &Host) -> Option<PathBuf> {
+  settings::load(app)
+    .fallback_output_dir
+    .filter(|d| !d.trim().is_empty())
+    .map(|d| PathBuf::from(d.trim()))
+}
+
+// Actually creating a file is the only reliable test: read-only shares often report
+// writable permissions, and Windows ACLs aren't reflected in them at all.
+fn check_writable(dir: &Path) -> Result<(), String> {
+  std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+  let probe = dir.join(format!(".lyrictime-write-test-{}", std::process::id()));
+  std::fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .open(&probe)
+    .map_err(|e| e.to_string())?;
+  let _ = std::fs::remove_file(&probe);
+  Ok(())
+}
+
+fn redirect(app: &Host, path: &Path, reason: String) -> Result<PathBuf, String> {
+  let Some(dir) = fallback_dir(app) else {
+    return Err(format!(
+      "Can't write {}: {reason}. Set a fallback output folder to write elsewhere",
+      path.display()
+    ));
+  };
+  let to = dir.join(path.file_name().unwrap_or_default());
+  app.report(ProgressEvent::Redirected {
+    from: path.display().to_string(),
+    to: to.display().to_string(),
+    reason: format!("{} is not writable: {reason}", path.parent().unwrap_or(path).display()),
+  });
+  Ok(to)
+}
+
+/// Checked before any work starts, so an unwritable destination doesn't fail a run at the
+/// very end: `path` if its folder is writable, else the same name in the fallback folder.
+pub fn writable_destination(app: &Host, path: &Path) -> Result<PathBuf, String> {
+  let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+  match check_writable(dir) {
+    Ok(()) => Ok(path.to_path_buf()),
+    Err(reason) => {
+      let to = redirect(app, path, reason)?;
+      let fallback = to.parent().unwrap_or(Path::new("."));
+      check_writable(fallback).map_err(|e| format!("Fallback output folder {} is not writable: {e}", fallback.display()))?;
+      Ok(to)
+    }
+  }
+}
+
+/// Write `contents` to `path`, or to the fallback folder if that fails (the share went
+/// read-only mid-run). Returns where the file ended up.
+pub fn write_with_fallback(app: &Host, path: &Path, contents: &[u8]) -> Result<PathBuf, String> {
+  let Err(e) = std::fs::write(path, contents) else { return Ok(path.to_path_buf()) };
+  let to = redirect(app, path, e.to_string())?;
+  if let Some(dir) = to.parent() {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed creating {}: {e}", dir.display()))?;
+  }
+  std::fs::write(&to, contents).map_err(|e| format!("Failed writing {}: {e}", to.display()))?;
+  Ok(to)
+}
@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+
+// Below MAX_PATH (260) with room for the extensions and temp suffixes added to outputs.
+#[cfg(target_os = "windows")]
+const SHORT_ENOUGH: usize = 240;
+
+/// `path` in Windows' extended-length form (`\\?\C:\...`, `\\?\UNC\server\share\...`) when
+/// it is long or on a network share, so file APIs and spawned tools accept it. Other
+/// paths, and every path elsewhere, come back unchanged.
+#[cfg(target_os = "windows")]
+pub fn extended(path: &Path) -> PathBuf {
+  let Some(raw) = path.to_str() else { return path.to_path_buf() };
+  if raw.starts_with(r"\\?\") || raw.starts_with(r"\\.\") {
+    return path.to_path_buf();
+  }
+  // The prefix turns off Win32 normalization, so resolve `..`, `/` and relative parts first.
+  let Some(absolute) = std::path::absolute(path).ok().and_then(|p| p.to_str().map(str::to_string)) else {
+    return path.to_path_buf();
+  };
+  if let Some(share) = absolute.strip_prefix(r"\\") {
+    return PathBuf::from(format!(r"\\?\UNC\{share}"));
+  }
+  if absolute.encode_utf16().count() > SHORT_ENOUGH {
+    return PathBuf::from(format!(r"\\?\{absolute}"));
+  }
+  path.to_path_buf()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn extended(path: &Path) -> PathBuf {
+  path.to_path_buf()
+}
+
+/// `path` as the user would write it, for messages and returned output paths.
+pub fn display(path: &Path) -> String {
+  let raw = path.display().to_string();
+  if let Some(share) = raw.strip_prefix(r"\\?\UNC\") {
+    format!(r"\\{share}")
+  } else if let Some(local) = raw.strip_prefix(r"\\?\") {
+    local.to_string()
+  } else {
+    raw
+  }
+}
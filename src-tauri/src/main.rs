@@ -4,56 +4,596 @@ mod whisper;
 mod model_downloader;
 mod ffmpeg_downloader;
 mod download;
+mod archive;
+mod models;
+mod presets;
+mod settings;
+mod host;
+mod cli;
+mod ytdlp;
+mod split;
+mod cue;
+mod chapters;
+mod playlist;
+mod batch;
+mod tags;
+mod cache;
+mod capabilities;
+mod notify;
+mod reveal;
+mod editor;
+mod subtitles;
+mod edits;
+mod waveform;
+mod clips;
+mod anki;
+mod nowplaying;
+mod library;
+mod longpath;
+mod formats;
+mod recents;
+mod update;
+mod setup;
+
+use host::Host;
 
 #[tauri::command]
 async fn generate_lrc_next_to_audio(
   app: tauri::AppHandle,
   audio_path: String,
   model: String,
+  options: Option<whisper::GenerateOptions>,
+  preset: Option<String>,
+) -> Result<String, String> {
+  let app = Host::from(app);
+  let (model, options) = presets::resolve(&app, model, options, preset)?;
+  let result = if options.chapters == whisper::ChapterMode::Split {
+    chapters::generate_per_chapter(app.clone(), &audio_path, &model, options)
+      .await
+      .map(|outputs| outputs.join("\n"))
+  } else {
+    whisper::generate_lrc_next_to_audio(app.clone(), &audio_path, &model, options).await
+  };
+  notify::outputs(&app, &result);
+  if let Ok(output) = &result {
+    // Best-effort: the list is only a shortcut.
+    let _ = recents::record(&app, &audio_path, &model, output.lines().next());
+  }
+  result
+}
+
+#[tauri::command]
+async fn generate_lrc_from_url(
+  app: tauri::AppHandle,
+  url: String,
+  out_dir: String,
+  model: String,
+  options: Option<whisper::GenerateOptions>,
+  preset: Option<String>,
+) -> Result<String, String> {
+  let app = Host::from(app);
+  let (model, options) = presets::resolve(&app, model, options, preset)?;
+  let result = ytdlp::generate_from_url(app.clone(), &url, std::path::Path::new(&out_dir), &model, options).await;
+  notify::outputs(&app, &result);
+  result
+}
+
+#[tauri::command]
+async fn generate_lrc_from_cue(
+  app: tauri::AppHandle,
+  cue_path: String,
+  model: String,
+  options: Option<whisper::GenerateOptions>,
+  preset: Option<String>,
+) -> Result<Vec<String>, String> {
+  let app = Host::from(app);
+  let (model, options) = presets::resolve(&app, model, options, preset)?;
+  let result = cue::generate_from_cue(app.clone(), &cue_path, &model, options).await;
+  notify::outputs(&app, &result.as_ref().map(|outputs| outputs.join("\n")).map_err(Clone::clone));
+  result
+}
+
+#[tauri::command]
+async fn generate_batch(
+  app: tauri::AppHandle,
+  inputs: Vec<String>,
+  out_dir: String,
+  model: String,
+  options: Option<whisper::GenerateOptions>,
+  preset: Option<String>,
+  dry_run: Option<bool>,
+) -> Result<batch::BatchReport, String> {
+  let app = Host::from(app);
+  let (model, options) = presets::resolve(&app, model, options, preset)?;
+  let out_dir = std::path::Path::new(&out_dir);
+  let result = batch::run(app.clone(), &inputs, out_dir, &model, options, dry_run.unwrap_or(false)).await;
+  notify::batch(&app, &result);
+  result
+}
+
+#[tauri::command]
+async fn resume_last_batch(app: tauri::AppHandle) -> Result<batch::BatchReport, String> {
+  let app = Host::from(app);
+  let result = batch::resume_last(app.clone()).await;
+  notify::batch(&app, &result);
+  result
+}
+
+#[tauri::command]
+fn get_last_batch_report(app: tauri::AppHandle) -> Result<Option<batch::BatchReport>, String> {
+  let app = Host::from(app);
+  batch::last_report(&app)
+}
+
+#[tauri::command]
+fn export_last_batch_report(app: tauri::AppHandle, path: String) -> Result<(), String> {
+  let app = Host::from(app);
+  let report = batch::last_report(&app)?.ok_or("No batch has finished yet")?;
+  batch::export_report(&report, std::path::Path::new(&path))
+}
+
+#[tauri::command]
+async fn list_audio_streams(app: tauri::AppHandle, path: String) -> Result<Vec<whisper::AudioStream>, String> {
+  let app = Host::from(app);
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let paths = ffmpeg_downloader::ensure_ffmpeg(app, ffmpeg_url, ffprobe_url).await?;
+  whisper::probe_audio_streams(std::path::Path::new(&paths.ffprobe_path), std::path::Path::new(&path))
+}
+
+#[tauri::command]
+async fn list_chapters(app: tauri::AppHandle, path: String) -> Result<Vec<whisper::Chapter>, String> {
+  let app = Host::from(app);
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let paths = ffmpeg_downloader::ensure_ffmpeg(app, ffmpeg_url, ffprobe_url).await?;
+  whisper::probe_chapters(std::path::Path::new(&paths.ffprobe_path), std::path::Path::new(&path))
+}
+
+#[tauri::command]
+fn parse_lrc_file(path: String) -> Result<editor::LrcFile, String> {
+  editor::read_lrc_file(std::path::Path::new(&path))
+}
+
+#[tauri::command]
+fn write_lrc_file(
+  path: String,
+  lines: Vec<lyrictime_core::lrc::LrcLine>,
+  options: Option<editor::WriteOptions>,
+) -> Result<(), String> {
+  editor::write_lrc_file(std::path::Path::new(&path), lines, options.unwrap_or_default())
+}
+
+#[tauri::command]
+async fn get_now_playing(app: tauri::AppHandle) -> Result<Option<nowplaying::NowPlaying>, String> {
+  let app = Host::from(app);
+  tokio::task::spawn_blocking(move || nowplaying::current(&app))
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn lyric_at(path: String, position_ms: i64) -> Result<lyrictime_core::lrc::LyricPosition, String> {
+  editor::lyric_at(std::path::Path::new(&path), position_ms)
+}
+
+#[tauri::command]
+fn shift_lrc(app: tauri::AppHandle, path: String, offset_ms: i64, as_tag: Option<bool>) -> Result<(), String> {
+  editor::shift_lrc(&Host::from(app), std::path::Path::new(&path), offset_ms, as_tag.unwrap_or(false))
+}
+
+#[tauri::command]
+fn scale_lrc(app: tauri::AppHandle, path: String, factor: f64) -> Result<(), String> {
+  editor::scale_lrc(&Host::from(app), std::path::Path::new(&path), factor)
+}
+
+#[tauri::command]
+fn validate_lrc(
+  path: String,
+  max_chars: Option<usize>,
+  max_cps: Option<f64>,
+) -> Result<Vec<lyrictime_core::validate::Finding>, String> {
+  editor::validate_lrc(std::path::Path::new(&path), max_chars, max_cps)
+}
+
+#[tauri::command]
+fn normalize_lrc_file(path: String, options: Option<editor::NormalizeOptions>) -> Result<String, String> {
+  editor::normalize_lrc_file(std::path::Path::new(&path), options.unwrap_or_default())
+}
+
+#[tauri::command]
+fn import_subtitles(app: tauri::AppHandle, path: String) -> Result<String, String> {
+  subtitles::import_subtitles(&Host::from(app), std::path::Path::new(&path))
+}
+
+#[tauri::command]
+fn score_lrc(generated: String, reference: String) -> Result<lyrictime_core::score::Score, String> {
+  editor::score_lrc(std::path::Path::new(&generated), std::path::Path::new(&reference))
+}
+
+#[tauri::command]
+async fn compare_models(
+  app: tauri::AppHandle,
+  audio_path: String,
+  model_a: String,
+  model_b: String,
+  options: Option<whisper::GenerateOptions>,
+) -> Result<whisper::ModelComparison, String> {
+  let app = Host::from(app);
+  let options = options.unwrap_or_else(|| settings::load(&app).generate);
+  whisper::compare_models(app, std::path::Path::new(&audio_path), &model_a, &model_b, options).await
+}
+
+#[tauri::command]
+async fn regenerate_range(
+  app: tauri::AppHandle,
+  audio_path: String,
+  start_ms: u64,
+  end_ms: u64,
+  model: String,
+  options: Option<whisper::GenerateOptions>,
 ) -> Result<String, String> {
-  whisper::generate_lrc_next_to_audio(app, &audio_path, &model).await
+  let app = Host::from(app);
+  let (model, options) = presets::resolve(&app, model, options, None)?;
+  whisper::regenerate_range(app, std::path::Path::new(&audio_path), start_ms, end_ms, &model, options).await
+}
+
+/// Every download of this session with its latest progress, for a frontend that
+/// missed the events (e.g. after a reload).
+#[tauri::command]
+fn get_download_status() -> Vec<download::DownloadProgressEvent> {
+  download::status()
+}
+
+/// Cancel the runs in progress: their whisper/ffmpeg are stopped and a batch stops before the next file.
+#[tauri::command]
+fn cancel_generation() {
+  whisper::cancel();
+}
+
+/// Saved state of the last generation, to offer retrying from a stage.
+#[tauri::command]
+fn get_last_job(app: tauri::AppHandle) -> Result<whisper::Job, String> {
+  whisper::last_job(&Host::from(app))
+}
+
+#[tauri::command]
+async fn retry_generation(
+  app: tauri::AppHandle,
+  from: whisper::Stage,
+  options: Option<whisper::GenerateOptions>,
+) -> Result<String, String> {
+  let app = Host::from(app);
+  let result = whisper::retry_generation(app.clone(), from, options).await;
+  notify::outputs(&app, &result);
+  result
+}
+
+#[tauri::command]
+async fn resync_lrc(
+  app: tauri::AppHandle,
+  lrc_path: String,
+  audio_path: String,
+  out_path: Option<String>,
+) -> Result<whisper::ResyncResult, String> {
+  let out_path = out_path.as_deref().map(std::path::Path::new);
+  whisper::resync_lrc(Host::from(app), std::path::Path::new(&lrc_path), std::path::Path::new(&audio_path), out_path).await
+}
+
+#[tauri::command]
+fn open_output_location(path: String) -> Result<(), String> {
+  reveal::open_output_location(std::path::Path::new(&path))
+}
+
+#[tauri::command]
+async fn get_waveform_peaks(app: tauri::AppHandle, path: String, resolution: u32) -> Result<waveform::WaveformPeaks, String> {
+  waveform::get_waveform_peaks(Host::from(app), std::path::Path::new(&path), resolution).await
+}
+
+#[tauri::command]
+async fn export_line_clips(
+  app: tauri::AppHandle,
+  audio: String,
+  lrc: String,
+  out_dir: String,
+) -> Result<Vec<clips::LineClip>, String> {
+  let (audio, lrc, out_dir) = (std::path::Path::new(&audio), std::path::Path::new(&lrc), std::path::Path::new(&out_dir));
+  clips::export_line_clips(Host::from(app), audio, lrc, out_dir).await
+}
+
+#[tauri::command]
+async fn export_anki(app: tauri::AppHandle, audio: String, lrc: String) -> Result<anki::AnkiExport, String> {
+  anki::export_anki(Host::from(app), std::path::Path::new(&audio), std::path::Path::new(&lrc)).await
+}
+
+#[tauri::command]
+fn get_supported_formats() -> Vec<formats::MediaFormat> {
+  formats::FORMATS.to_vec()
+}
+
+#[tauri::command]
+fn read_audio_tags(path: String) -> Result<tags::AudioTags, String> {
+  tags::read_audio_tags(std::path::Path::new(&path))
+}
+
+#[tauri::command]
+fn detect_system_capabilities(app: tauri::AppHandle) -> capabilities::SystemCapabilities {
+  let app = Host::from(app);
+  capabilities::detect(settings::load(&app).benchmarks)
+}
+
+#[tauri::command]
+fn list_gpus() -> Vec<capabilities::GpuDevice> {
+  capabilities::list_gpus()
+}
+
+#[tauri::command]
+async fn run_benchmark(app: tauri::AppHandle, model: String) -> Result<whisper::BenchmarkResult, String> {
+  whisper::run_benchmark(Host::from(app), &model).await
+}
+
+#[tauri::command]
+fn get_settings(app: tauri::AppHandle) -> settings::Settings {
+  let app = Host::from(app);
+  settings::load(&app)
+}
+
+#[tauri::command]
+fn save_settings(app: tauri::AppHandle, settings: settings::Settings) -> Result<(), String> {
+  let app = Host::from(app);
+  download::set_rate_limit(settings.download_limit_kbps);
+  settings::save(&app, &settings)
+}
+
+/// Change the download rate limit (KiB/s, 0 = unlimited), including for downloads
+/// already running, and remember it.
+#[tauri::command]
+fn set_download_limit(app: tauri::AppHandle, kbps: u64) -> Result<(), String> {
+  let app = Host::from(app);
+  download::set_rate_limit(kbps);
+  let mut s = settings::load(&app);
+  s.download_limit_kbps = kbps;
+  settings::save(&app, &s)
+}
+
+#[tauri::command]
+fn register_model(app: tauri::AppHandle, path: String, name: String) -> Result<(), String> {
+  let app = Host::from(app);
+  model_downloader::register_custom_model(&app, &path, &name)
+}
+
+#[tauri::command]
+fn list_models(app: tauri::AppHandle) -> Result<Vec<models::InstalledModel>, String> {
+  let app = Host::from(app);
+  models::list(&app)
+}
+
+#[tauri::command]
+fn delete_model(app: tauri::AppHandle, name: String) -> Result<(), String> {
+  let app = Host::from(app);
+  models::delete(&app, &name)
+}
+
+/// Make a quantized copy (`target` "q5_0", "q5_1" or "q8_0") of an installed model.
+#[tauri::command]
+async fn quantize_model(app: tauri::AppHandle, name: String, target: String) -> Result<String, String> {
+  whisper::quantize_model(Host::from(app), &name, &target).await
+}
+
+/// Downloadable models (full and quantized) with size, RAM and quality notes.
+#[tauri::command]
+fn list_available_models(app: tauri::AppHandle) -> Result<Vec<models::AvailableModel>, String> {
+  models::available(&Host::from(app))
+}
+
+#[tauri::command]
+fn get_models_disk_usage(app: tauri::AppHandle) -> Result<models::ModelsDiskUsage, String> {
+  let app = Host::from(app);
+  models::disk_usage(&app)
+}
+
+#[tauri::command]
+fn verify_models(app: tauri::AppHandle) -> Result<Vec<models::ModelIssue>, String> {
+  let app = Host::from(app);
+  models::verify_all(&app)
+}
+
+#[tauri::command]
+async fn redownload_model(app: tauri::AppHandle, name: String) -> Result<String, String> {
+  let app = Host::from(app);
+  models::redownload(&app, &name).await
+}
+
+#[tauri::command]
+fn get_cache_usage(app: tauri::AppHandle) -> Result<cache::CacheUsage, String> {
+  let app = Host::from(app);
+  cache::usage(&app)
+}
+
+#[tauri::command]
+fn clear_cache(app: tauri::AppHandle) -> Result<(), String> {
+  let app = Host::from(app);
+  cache::clear(&app)
+}
+
+#[tauri::command]
+fn list_presets(app: tauri::AppHandle) -> Vec<presets::Preset> {
+  let app = Host::from(app);
+  presets::list(&app)
+}
+
+#[tauri::command]
+fn save_preset(app: tauri::AppHandle, preset: presets::Preset) -> Result<(), String> {
+  let app = Host::from(app);
+  presets::save(&app, preset)
+}
+
+#[tauri::command]
+fn delete_preset(app: tauri::AppHandle, name: String) -> Result<(), String> {
+  let app = Host::from(app);
+  presets::delete(&app, &name)
+}
+
+#[tauri::command]
+async fn check_app_update(app: tauri::AppHandle) -> Result<update::AppUpdate, String> {
+  update::check(&Host::from(app)).await
+}
+
+#[tauri::command]
+fn list_recent_files(app: tauri::AppHandle) -> Vec<recents::RecentFile> {
+  recents::list(&Host::from(app))
+}
+
+#[tauri::command]
+fn record_recent_file(app: tauri::AppHandle, path: String, model: String, output: Option<String>) -> Result<(), String> {
+  recents::record(&Host::from(app), &path, &model, output.as_deref())
+}
+
+#[tauri::command]
+fn clear_recent_files(app: tauri::AppHandle) -> Result<(), String> {
+  recents::clear(&Host::from(app))
+}
+
+#[tauri::command]
+fn list_favorites(app: tauri::AppHandle) -> Vec<recents::Favorite> {
+  recents::favorites(&Host::from(app))
+}
+
+#[tauri::command]
+fn add_favorite(app: tauri::AppHandle, path: String) -> Result<(), String> {
+  recents::add_favorite(&Host::from(app), &path)
+}
+
+#[tauri::command]
+fn remove_favorite(app: tauri::AppHandle, path: String) -> Result<(), String> {
+  recents::remove_favorite(&Host::from(app), &path)
 }
 
 #[tauri::command]
 async fn ensure_models_downloaded(
   app: tauri::AppHandle,
 ) -> Result<model_downloader::ModelPaths, String> {
-  // NOTE: GitHub Releases are flat files (no folders). Upload these as assets
-  // under tag `models`: ggml-small.bin and ggml-medium.bin
-  let small = "https://github.com/evilduck1/LyricTime/releases/download/models/ggml-small.bin".to_string();
-  let medium = "https://github.com/evilduck1/LyricTime/releases/download/models/ggml-medium.bin".to_string();
-  model_downloader::ensure_models(app, small, medium).await
+  model_downloader::ensure_models(Host::from(app)).await
+}
+
+/// What the first-run wizard still has to offer: tools, models, disk space, RAM class.
+#[tauri::command]
+fn get_setup_status(app: tauri::AppHandle) -> Result<setup::SetupStatus, String> {
+  setup::status(&Host::from(app))
+}
+
+/// Download the components picked in the wizard; progress on `setup://progress`.
+#[tauri::command]
+async fn run_setup(app: tauri::AppHandle, selections: setup::SetupSelections) -> Result<(), String> {
+  setup::run(&Host::from(app), selections).await
+}
+
+/// ggml models in the Hugging Face repo at the configured revision.
+#[tauri::command]
+async fn get_model_catalog(app: tauri::AppHandle) -> Result<Vec<model_downloader::CatalogModel>, String> {
+  model_downloader::hf_catalog(&Host::from(app)).await
+}
+
+/// Pin model downloads to Hugging Face's current commit; returns it.
+#[tauri::command]
+async fn pin_model_revision(app: tauri::AppHandle) -> Result<String, String> {
+  model_downloader::pin_revision(&Host::from(app)).await
 }
 
 #[tauri::command]
 async fn ensure_ffmpeg_downloaded(
   app: tauri::AppHandle,
 ) -> Result<ffmpeg_downloader::FfmpegPaths, String> {
-  // NOTE: GitHub Releases are flat files (no folders). Upload these 4 files as assets
-  // under tag `deps`: ffmpeg.exe, ffprobe.exe, ffmpeg, ffprobe
-  #[cfg(windows)]
-  let (ffmpeg_url, ffprobe_url) = (
-    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffmpeg.exe".to_string(),
-    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffprobe.exe".to_string(),
-  );
-
-  #[cfg(not(windows))]
-  let (ffmpeg_url, ffprobe_url) = (
-    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffmpeg".to_string(),
-    "https://github.com/evilduck1/LyricTime/releases/download/deps/ffprobe".to_string(),
-  );
-
+  let app = Host::from(app);
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
   ffmpeg_downloader::ensure_ffmpeg(app, ffmpeg_url, ffprobe_url).await
 }
 
 fn main() {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  if cli::is_cli(&args) {
+    std::process::exit(cli::run(&args));
+  }
+
   tauri::Builder::default()
     .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_notification::init())
+    .setup(|app| {
+      let host = Host::from(app.handle().clone());
+      download::set_rate_limit(settings::load(&host).download_limit_kbps);
+      nowplaying::watch(host);
+      Ok(())
+    })
     .invoke_handler(tauri::generate_handler![
       generate_lrc_next_to_audio,
+      generate_lrc_from_url,
+      generate_lrc_from_cue,
+      generate_batch,
+      resume_last_batch,
+      get_last_batch_report,
+      export_last_batch_report,
+      list_audio_streams,
+      list_chapters,
+      read_audio_tags,
+      get_waveform_peaks,
+      export_line_clips,
+      export_anki,
+      open_output_location,
+      parse_lrc_file,
+      write_lrc_file,
+      lyric_at,
+      get_now_playing,
+      shift_lrc,
+      scale_lrc,
+      resync_lrc,
+      validate_lrc,
+      normalize_lrc_file,
+      import_subtitles,
+      score_lrc,
+      compare_models,
+      regenerate_range,
+      cancel_generation,
+      get_download_status,
+      set_download_limit,
+      get_last_job,
+      retry_generation,
+      get_supported_formats,
+      detect_system_capabilities,
+      list_gpus,
+      run_benchmark,
       ensure_models_downloaded,
-      ensure_ffmpeg_downloaded
+      get_setup_status,
+      run_setup,
+      get_model_catalog,
+      pin_model_revision,
+      ensure_ffmpeg_downloaded,
+      get_settings,
+      save_settings,
+      list_presets,
+      save_preset,
+      delete_preset,
+      check_app_update,
+      list_recent_files,
+      record_recent_file,
+      clear_recent_files,
+      list_favorites,
+      add_favorite,
+      remove_favorite,
+      register_model,
+      list_models,
+      delete_model,
+      list_available_models,
+      quantize_model,
+      get_models_disk_usage,
+      verify_models,
+      redownload_model,
+      get_cache_usage,
+      clear_cache
     ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while running tauri application")
+    .run(|_app, event| {
+      // Quitting mid-generation would otherwise leave whisper running in the background.
+      if let tauri::RunEvent::Exit = event {
+        whisper::shutdown();
+      }
+    });
 }
@@ -1,8 +1,171 @@
-use std::path::PathBuf;
-use tauri::{AppHandle, Emitter, Manager};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
-use crate::download;
+use crate::host::Host;
+use crate::{download, settings};
 
+/// Hugging Face repo whisper.cpp publishes its ggml models in.
+pub const HF_REPO: &str = "ggerganov/whisper.cpp";
+/// Revision used until one is pinned in settings.
+const DEFAULT_REVISION: &str = "main";
+const GITHUB_MODELS_URL: &str = "https://github.com/evilduck1/LyricTime/releases/download/models";
+
+/// Where whisper models are downloaded from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelSource {
+  /// whisper.cpp's own Hugging Face repo, at the pinned revision.
+  #[default]
+  HuggingFace,
+  /// The LyricTime GitHub release (the four default models only).
+  GitHub,
+}
+
+// The commit pinned in settings, if any.
+fn pinned_revision(app: &Host) -> Option<String> {
+  settings::load(app).model_revision.filter(|r| !r.trim().is_empty())
+}
+
+/// The Hugging Face revision models are downloaded at: the pinned commit, else `main`.
+pub fn revision(app: &Host) -> String {
+  pinned_revision(app).unwrap_or_else(|| DEFAULT_REVISION.to_string())
+}
+
+/// Download a model file from the configured source into `path`. From Hugging Face it's
+/// fetched at a fixed commit, so an interrupted download continues where it stopped,
+/// and checked against the SHA-256 the repo lists for it.
+async fn fetch(app: &Host, file: &str, path: &Path) -> Result<(), String> {
+  match settings::load(app).model_source {
+    ModelSource::HuggingFace => {
+      download::ensure_online(app, &format!("downloading {file}"))?;
+      let commit = commit(app).await?;
+      let sha256 = catalog_at(&commit).await?.into_iter().find(|m| m.file == file).and_then(|m| m.sha256);
+      let url = format!("https://huggingface.co/{HF_REPO}/resolve/{commit}/{file}");
+      download::download_resumable(app, "models", &url, path, file).await?;
+      match sha256 {
+        Some(expected) => verify_sha256(path, &expected).await,
+        None => Ok(()),
+      }
+    }
+    ModelSource::GitHub => {
+      let url = format!("{GITHUB_MODELS_URL}/{file}");
+      download::download_with_progress(app, "models", &url, path, file).await
+    }
+  }
+}
+
+/// A model file in the Hugging Face repo.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogModel {
+  /// Model option name, e.g. "medium-q5_0".
+  pub name: String,
+  pub file: String,
+  pub size_bytes: u64,
+  /// SHA-256 of the file, as Hugging Face stores it.
+  pub sha256: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TreeEntry {
+  #[serde(rename = "type")]
+  kind: String,
+  path: String,
+  #[serde(default)]
+  size: u64,
+  lfs: Option<LfsInfo>,
+}
+
+#[derive(Deserialize)]
+struct LfsInfo {
+  oid: String,
+  size: u64,
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, String> {
+  let res = reqwest::Client::new().get(url).send().await.map_err(|e| e.to_string())?;
+  if !res.status().is_success() {
+    return Err(format!("Hugging Face answered HTTP {} for {url}", res.status()));
+  }
+  let body = res.bytes().await.map_err(|e| e.to_string())?;
+  serde_json::from_slice(&body).map_err(|e| format!("Unexpected answer from Hugging Face: {e}"))
+}
+
+/// Every ggml model in the Hugging Face repo at the configured revision: all sizes,
+/// English-only variants and quantizations.
+pub async fn hf_catalog(app: &Host) -> Result<Vec<CatalogModel>, String> {
+  download::ensure_online(app, "listing Hugging Face models")?;
+  catalog_at(&revision(app)).await
+}
+
+async fn catalog_at(revision: &str) -> Result<Vec<CatalogModel>, String> {
+  let url = format!("https://huggingface.co/api/models/{HF_REPO}/tree/{revision}");
+  let entries: Vec<TreeEntry> = get_json(&url).await?;
+
+  let mut models: Vec<CatalogModel> = entries
+    .into_iter()
+    .filter(|e| e.kind == "file" && e.path.starts_with("ggml-") && e.path.ends_with(".bin"))
+    .map(|e| CatalogModel {
+      name: crate::models::model_name(&e.path),
+      size_bytes: e.lfs.as_ref().map_or(e.size, |l| l.size),
+      sha256: e.lfs.map(|l| l.oid),
+      file: e.path,
+    })
+    .collect();
+  models.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(models)
+}
+
+#[derive(Deserialize)]
+struct RevisionInfo {
+  sha: String,
+}
+
+async fn main_commit() -> Result<String, String> {
+  let url = format!("https://huggingface.co/api/models/{HF_REPO}/revision/{DEFAULT_REVISION}");
+  Ok(get_json::<RevisionInfo>(&url).await?.sha)
+}
+
+// The pinned commit, else the one `main` points at right now.
+async fn commit(app: &Host) -> Result<String, String> {
+  match pinned_revision(app) {
+    Some(pinned) => Ok(pinned),
+    None => main_commit().await,
+  }
+}
+
+// Hashed off the async runtime; a medium model is 1.5 GB. A mismatch deletes the file.
+async fn verify_sha256(path: &Path, expected: &str) -> Result<(), String> {
+  use sha2::{Digest, Sha256};
+  let file = path.to_path_buf();
+  let actual = tokio::task::spawn_blocking(move || {
+    let mut hasher = Sha256::new();
+    let mut f = std::fs::File::open(&file).map_err(|e| format!("Failed reading {}: {e}", file.display()))?;
+    std::io::copy(&mut f, &mut hasher).map_err(|e| format!("Failed reading {}: {e}", file.display()))?;
+    Ok::<_, String>(hex::encode(hasher.finalize()))
+  })
+  .await
+  .map_err(|e| e.to_string())??;
+  if !actual.eq_ignore_ascii_case(expected) {
+    let _ = std::fs::remove_file(path);
+    return Err(format!(
+      "{} is corrupt (SHA-256 {actual}, expected {expected}); download it again",
+      path.display()
+    ));
+  }
+  Ok(())
+}
+
+/// Pin model downloads to the commit `main` currently points at, so every later
+/// download (and every machine with these settings) gets the same files.
+pub async fn pin_revision(app: &Host) -> Result<String, String> {
+  download::ensure_online(app, "pinning the model revision")?;
+  let sha = main_commit().await?;
+  let mut s = settings::load(app);
+  s.model_revision = Some(sha.clone());
+  settings::save(app, &s)?;
+  Ok(sha)
+}
 
 #[derive(serde::Serialize)]
 pub struct ModelPaths {
@@ -10,25 +173,22 @@ pub struct ModelPaths {
   pub medium_path: String,
 }
 
-fn models_dir(app: &AppHandle) -> tauri::Result<PathBuf> {
-  Ok(app.path().app_data_dir()?.join("models"))
+fn models_dir(app: &Host) -> Result<PathBuf, String> {
+  settings::models_dir(app)
 }
 
 
-pub async fn ensure_models(
-  app: AppHandle,
-  small_url: String,
-  medium_url: String,
-) -> Result<ModelPaths, String> {
+/// Download the default small and medium models if missing.
+pub async fn ensure_models(app: Host) -> Result<ModelPaths, String> {
   let dir = models_dir(&app).map_err(|e| e.to_string())?;
   let small = dir.join("ggml-small.bin");
   let medium = dir.join("ggml-medium.bin");
 
   if !small.exists() {
-    download::download_with_progress(&app, "models", &small_url, &small, "ggml-small.bin").await?;
+    fetch(&app, "ggml-small.bin", &small).await?;
   }
   if !medium.exists() {
-    download::download_with_progress(&app, "models", &medium_url, &medium, "ggml-medium.bin").await?;
+    fetch(&app, "ggml-medium.bin", &medium).await?;
   }
 
   Ok(ModelPaths {
@@ -36,3 +196,135 @@ pub async fn ensure_models(
     medium_path: medium.to_string_lossy().to_string(),
   })
 }
+
+/// A downloadable whisper model.
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVariant {
+  /// Model option name, e.g. "medium-q5_0".
+  pub name: &'static str,
+  pub file: &'static str,
+  /// "q5_0", "q5_1" or "q8_0"; unset for full precision.
+  pub quantization: Option<&'static str>,
+  pub download_bytes: u64,
+  /// Roughly what whisper needs in memory to run it.
+  pub ram_bytes: u64,
+  pub quality: &'static str,
+}
+
+const MB: u64 = 1_000_000;
+const FULL: &str = "Reference accuracy";
+const Q8: &str = "Practically the same accuracy as full precision, about half the size";
+const Q5: &str = "Slightly less accurate on fast or mumbled vocals; about a third of the size";
+
+const fn variant(
+  name: &'static str,
+  file: &'static str,
+  quantization: Option<&'static str>,
+  download_mb: u64,
+  ram_mb: u64,
+  quality: &'static str,
+) -> ModelVariant {
+  ModelVariant {
+    name,
+    file,
+    quantization,
+    download_bytes: download_mb * MB,
+    ram_bytes: ram_mb * MB,
+    quality,
+  }
+}
+
+/// Downloadable whisper models. Quantized files are on Hugging Face only.
+pub const MODEL_VARIANTS: [ModelVariant; 12] = [
+  variant("small", "ggml-small.bin", None, 488, 852, FULL),
+  variant("small-q8_0", "ggml-small-q8_0.bin", Some("q8_0"), 264, 650, Q8),
+  variant("small-q5_1", "ggml-small-q5_1.bin", Some("q5_1"), 190, 580, Q5),
+  variant("small.en", "ggml-small.en.bin", None, 488, 852, FULL),
+  variant("small.en-q8_0", "ggml-small.en-q8_0.bin", Some("q8_0"), 264, 650, Q8),
+  variant("small.en-q5_1", "ggml-small.en-q5_1.bin", Some("q5_1"), 190, 580, Q5),
+  variant("medium", "ggml-medium.bin", None, 1533, 2100, FULL),
+  variant("medium-q8_0", "ggml-medium-q8_0.bin", Some("q8_0"), 823, 1420, Q8),
+  variant("medium-q5_0", "ggml-medium-q5_0.bin", Some("q5_0"), 539, 1140, Q5),
+  variant("medium.en", "ggml-medium.en.bin", None, 1533, 2100, FULL),
+  variant("medium.en-q8_0", "ggml-medium.en-q8_0.bin", Some("q8_0"), 823, 1420, Q8),
+  variant("medium.en-q5_0", "ggml-medium.en-q5_0.bin", Some("q5_0"), 539, 1140, Q5),
+];
+
+/// The downloadable model called `name`, if there is one.
+pub fn find_variant(name: &str) -> Option<&'static ModelVariant> {
+  MODEL_VARIANTS.iter().find(|v| v.name == name)
+}
+
+/// Downloads one model by option name (e.g. "small.en", "medium-q5_0") if missing.
+pub async fn ensure_model(app: &Host, model: &str) -> Result<PathBuf, String> {
+  let v = find_variant(model).ok_or_else(|| format!("Unknown model: {model}"))?;
+  if v.quantization.is_some() && settings::load(app).model_source == ModelSource::GitHub {
+    return Err(format!("{model} is only on Hugging Face; switch the model source to download it"));
+  }
+  let file = v.file;
+  let path = models_dir(app)?.join(file);
+  if !path.exists() {
+    fetch(app, file, &path).await?;
+  }
+  Ok(path)
+}
+
+/// Register an existing ggml/gguf file under `name` so it can be picked as a model.
+pub fn register_custom_model(app: &Host, path: &str, name: &str) -> Result<(), String> {
+  let name = name.trim();
+  if name.is_empty() {
+    return Err("Model name is empty".into());
+  }
+  if name.eq_ignore_ascii_case("hybrid") || MODEL_VARIANTS.iter().any(|v| v.name.eq_ignore_ascii_case(name)) {
+    return Err(format!("'{name}' is a built-in model name"));
+  }
+
+  let file = Path::new(path);
+  if !file.is_file() {
+    return Err(format!("Model file not found: {path}"));
+  }
+  let ext = file
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_ascii_lowercase())
+    .unwrap_or_default();
+  if !matches!(ext.as_str(), "bin" | "gguf") {
+    return Err("Model file must be a ggml .bin or .gguf file".into());
+  }
+
+  let mut s = settings::load(app);
+  s.custom_models.retain(|m| !m.name.eq_ignore_ascii_case(name));
+  s.custom_models.push(settings::CustomModel {
+    name: name.to_string(),
+    path: path.to_string(),
+  });
+  settings::save(app, &s)
+}
+
+/// Silero VAD weights used by whisper's `--vad` flag (~1 MB).
+pub const VAD_MODEL_FILE: &str = "ggml-silero-v5.1.2.bin";
+
+/// tinydiarize model: small.en fine-tuned to predict speaker turns (`-tdrz`).
+pub const TDRZ_MODEL_FILE: &str = "ggml-small.en-tdrz.bin";
+
+/// Downloads the tinydiarize model from the configured source if missing.
+pub async fn ensure_tdrz_model(app: &Host) -> Result<PathBuf, String> {
+  let path = models_dir(app)?.join(TDRZ_MODEL_FILE);
+  if !path.exists() {
+    fetch(app, TDRZ_MODEL_FILE, &path).await?;
+  }
+  Ok(path)
+}
+
+/// Downloads a single auxiliary model file into app data /models if missing.
+pub async fn ensure_model_file(app: &Host, file_name: &str, url: String) -> Result<PathBuf, String> {
+  let dir = models_dir(app).map_err(|e| e.to_string())?;
+  let path = dir.join(file_name);
+
+  if !path.exists() {
+    download::download_with_progress(app, "models", &url, &path, file_name).await?;
+  }
+
+  Ok(path)
+}
@@ -0,0 +1,241 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+use crate::{model_downloader, settings};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct InstalledModel {
+  pub name: String,
+  pub path: String,
+  pub size_bytes: u64,
+  pub source: String, // "downloaded" | "bundled" | "custom"
+}
+
+/// A model file that failed verification.
+#[derive(Serialize, Clone, Debug)]
+pub struct ModelIssue {
+  pub name: String,
+  pub path: String,
+  pub reason: String,
+  /// True if the app can fetch a fresh copy (`redownload_model`).
+  pub redownloadable: bool,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ModelsDiskUsage {
+  pub downloaded_bytes: u64,
+  pub bundled_bytes: u64,
+  pub custom_bytes: u64,
+  pub total_bytes: u64,
+}
+
+fn downloaded_dir(app: &Host) -> Result<PathBuf, String> {
+  settings::models_dir(app)
+}
+
+fn bundled_dir(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.resource_dir().map_err(|e| e.to_string())?.join("models"))
+}
+
+/// "ggml-small.en-q5_1.bin" -> "small.en-q5_1"
+pub fn model_name(file_name: &str) -> String {
+  let stem = file_name
+    .strip_suffix(".bin")
+    .or_else(|| file_name.strip_suffix(".gguf"))
+    .unwrap_or(file_name);
+  stem
+    .strip_prefix("ggml-model-whisper-")
+    .or_else(|| stem.strip_prefix("ggml-"))
+    .unwrap_or(stem)
+    .to_string()
+}
+
+fn scan_dir(dir: &Path, source: &str) -> Vec<InstalledModel> {
+  let Ok(entries) = std::fs::read_dir(dir) else {
+    return Vec::new();
+  };
+
+  let mut out: Vec<InstalledModel> = entries
+    .filter_map(|e| e.ok())
+    .filter_map(|e| {
+      let file_name = e.file_name().to_string_lossy().into_owned();
+      if !(file_name.ends_with(".bin") || file_name.ends_with(".gguf")) {
+        return None;
+      }
+      // Helper weights (VAD, tinydiarize) live alongside but aren't transcription models.
+      if [model_downloader::VAD_MODEL_FILE, model_downloader::TDRZ_MODEL_FILE].contains(&file_name.as_str()) {
+        return None;
+      }
+      let meta = e.metadata().ok().filter(|m| m.is_file())?;
+      Some(InstalledModel {
+        name: model_name(&file_name),
+        path: e.path().to_string_lossy().to_string(),
+        size_bytes: meta.len(),
+        source: source.to_string(),
+      })
+    })
+    .collect();
+
+  out.sort_by(|a, b| a.name.cmp(&b.name));
+  out
+}
+
+/// Every model file the app can see: downloaded, bundled and user-registered.
+pub fn list(app: &Host) -> Result<Vec<InstalledModel>, String> {
+  let mut out = scan_dir(&downloaded_dir(app)?, "downloaded");
+  out.extend(scan_dir(&bundled_dir(app)?, "bundled"));
+
+  for m in settings::load(app).custom_models {
+    let size_bytes = std::fs::metadata(&m.path).map(|md| md.len()).unwrap_or(0);
+    out.push(InstalledModel {
+      name: m.name,
+      path: m.path,
+      size_bytes,
+      source: "custom".into(),
+    });
+  }
+
+  Ok(out)
+}
+
+/// Delete a downloaded model, or unregister a custom one (the user's file is left alone).
+/// Bundled models ship with the app and can't be removed.
+pub fn delete(app: &Host, name: &str) -> Result<(), String> {
+  let mut s = settings::load(app);
+  let before = s.custom_models.len();
+  s.custom_models.retain(|m| !m.name.eq_ignore_ascii_case(name));
+  if s.custom_models.len() != before {
+    return settings::save(app, &s);
+  }
+
+  let all = list(app)?;
+  let matches: Vec<&InstalledModel> = all.iter().filter(|m| m.name.eq_ignore_ascii_case(name)).collect();
+
+  let downloaded = matches.iter().find(|m| m.source == "downloaded");
+  match downloaded {
+    Some(m) => std::fs::remove_file(&m.path).map_err(|e| format!("Failed deleting {}: {e}", m.path)),
+    None if !matches.is_empty() => Err(format!("'{name}' is bundled with the app and can't be deleted")),
+    None => Err(format!("Model not installed: {name}")),
+  }
+}
+
+// Smallest plausible size of the known model files; anything under this is a truncated
+// download. Custom files only get the generic floor.
+fn min_size_bytes(name: &str) -> u64 {
+  match name {
+    "small" | "small.en" => 480_000_000,
+    "medium" | "medium.en" => 1_500_000_000,
+    _ => model_downloader::find_variant(name).map_or(100 * 1024, |v| v.download_bytes * 9 / 10),
+  }
+}
+
+/// Check the GGML/GGUF magic and size before handing a model to whisper, which
+/// otherwise fails on a truncated file with an opaque error.
+pub fn verify_model_file(path: &Path) -> Result<(), String> {
+  let len = std::fs::metadata(path).map_err(|e| format!("unreadable: {e}"))?.len();
+
+  let mut magic = [0u8; 4];
+  std::fs::File::open(path)
+    .and_then(|mut f| f.read_exact(&mut magic))
+    .map_err(|_| "file is empty or unreadable".to_string())?;
+
+  // ggml: uint32 0x67676d6c written little-endian; gguf: ASCII "GGUF".
+  let ggml = u32::from_le_bytes(magic) == 0x6767_6d6c;
+  let gguf = &magic == b"GGUF";
+  if !ggml && !gguf {
+    return Err("not a ggml/gguf model (bad header)".into());
+  }
+
+  let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+  let min = min_size_bytes(&model_name(&file_name));
+  if len < min {
+    return Err(format!("truncated ({len} bytes, expected at least {min})"));
+  }
+
+  Ok(())
+}
+
+fn is_redownloadable(name: &str) -> bool {
+  model_downloader::find_variant(name).is_some()
+}
+
+pub fn issue_for(name: &str, path: &Path, reason: String) -> ModelIssue {
+  ModelIssue {
+    name: name.to_string(),
+    path: path.to_string_lossy().to_string(),
+    reason,
+    redownloadable: is_redownloadable(name),
+  }
+}
+
+/// Verify every installed model; used by the frontend at startup.
+pub fn verify_all(app: &Host) -> Result<Vec<ModelIssue>, String> {
+  Ok(
+    list(app)?
+      .into_iter()
+      .filter_map(|m| {
+        let path = PathBuf::from(&m.path);
+        verify_model_file(&path).err().map(|reason| issue_for(&m.name, &path, reason))
+      })
+      .collect(),
+  )
+}
+
+/// Replace a downloaded model with a fresh copy.
+pub async fn redownload(app: &Host, name: &str) -> Result<String, String> {
+  if !is_redownloadable(name) {
+    return Err(format!("'{name}' can't be redownloaded"));
+  }
+  for m in scan_dir(&downloaded_dir(app)?, "downloaded") {
+    if m.name == name {
+      std::fs::remove_file(&m.path).map_err(|e| format!("Failed deleting {}: {e}", m.path))?;
+    }
+  }
+  let path = model_downloader::ensure_model(app, name).await?;
+  Ok(path.to_string_lossy().to_string())
+}
+
+/// A downloadable model with whether it's installed and suits this machine.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailableModel {
+  #[serde(flatten)]
+  pub variant: model_downloader::ModelVariant,
+  pub installed: bool,
+  /// Needs at most a quarter of the RAM, leaving room for everything else.
+  pub recommended: bool,
+}
+
+/// Every downloadable model, full and quantized, with size, memory and quality notes.
+pub fn available(app: &Host) -> Result<Vec<AvailableModel>, String> {
+  let installed = list(app)?;
+  let mut sys = sysinfo::System::new();
+  sys.refresh_memory();
+  let total_ram = sys.total_memory();
+
+  Ok(
+    model_downloader::MODEL_VARIANTS
+      .iter()
+      .map(|v| AvailableModel {
+        variant: *v,
+        installed: installed.iter().any(|m| m.name == v.name),
+        recommended: total_ram == 0 || v.ram_bytes <= total_ram / 4,
+      })
+      .collect(),
+  )
+}
+
+pub fn disk_usage(app: &Host) -> Result<ModelsDiskUsage, String> {
+  let mut usage = ModelsDiskUsage::default();
+  for m in list(app)? {
+    match m.source.as_str() {
+      "downloaded" => usage.downloaded_bytes += m.size_bytes,
+      "bundled" => usage.bundled_bytes += m.size_bytes,
+      _ => usage.custom_bytes += m.size_bytes,
+    }
+    usage.total_bytes += m.size_bytes;
+  }
+  Ok(usage)
+}
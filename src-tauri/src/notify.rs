@@ -0,0 +1,49 @@
+use tauri_plugin_notification::NotificationExt;
+
+use crate::batch::BatchReport;
+use crate::host::Host;
+use crate::settings;
+
+/// Desktop notification, unless disabled in settings. The CLI already prints the outcome.
+pub fn show(app: &Host, title: &str, body: &str) {
+  let Host::App(handle) = app else { return };
+  if settings::load(app).disable_notifications {
+    return;
+  }
+  let _ = handle.notification().builder().title(title).body(body).show();
+}
+
+fn file_name(path: &str) -> &str {
+  path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+fn failed(app: &Host, err: &str) {
+  show(app, "Lyrics generation failed", err.lines().next().unwrap_or(err));
+}
+
+/// Notify about a single run that wrote one or more files (newline-separated).
+pub fn outputs(app: &Host, result: &Result<String, String>) {
+  match result {
+    Ok(paths) => {
+      let names: Vec<&str> = paths.lines().map(file_name).collect();
+      let body = match names.as_slice() {
+        [one] => one.to_string(),
+        many => format!("{} files written", many.len()),
+      };
+      show(app, "Lyrics ready", &body);
+    }
+    Err(e) => failed(app, e),
+  }
+}
+
+pub fn batch(app: &Host, result: &Result<BatchReport, String>) {
+  match result {
+    Ok(r) if r.dry_run => {}
+    Ok(r) => show(
+      app,
+      "Batch finished",
+      &format!("{} succeeded, {} skipped, {} failed", r.succeeded, r.skipped + r.duplicates, r.failed + r.rejected),
+    ),
+    Err(e) => failed(app, e),
+  }
+}
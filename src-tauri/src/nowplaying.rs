@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::host::Host;
+use crate::settings;
+use crate::tags;
+use crate::{library, notify, presets, whisper};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// What to do when the system media player starts a local file. Linux only (MPRIS
+/// players); elsewhere the setting has no effect.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NowPlayingMode {
+  #[default]
+  Off,
+  /// Tell the frontend, which offers to generate lyrics for it.
+  Offer,
+  /// Generate lyrics for it in the background.
+  Auto,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NowPlaying {
+  pub path: String,
+  pub title: Option<String>,
+  pub artist: Option<String>,
+  /// Player the track was found in (its MPRIS name).
+  pub player: String,
+  /// Lyrics exist next to the file or in its tags.
+  pub has_lyrics: bool,
+}
+
+/// The local file the system media player is currently playing, if any. Linux only;
+/// errors on Windows and macOS.
+pub fn current(app: &Host) -> Result<Option<NowPlaying>, String> {
+  let Some(mut track) = playing_track()? else { return Ok(None) };
+  let path = Path::new(&track.path);
+  let sidecar = library::output_path(app, path, settings::load(app).generate.output_format().extension());
+  track.has_lyrics = sidecar.exists() || tags::read_audio_tags(path).is_ok_and(|t| t.has_embedded_lyrics);
+  Ok(Some(track))
+}
+
+/// Poll the media player in the background and act on track changes per the
+/// `now_playing` setting. Does nothing where `current` isn't available.
+pub fn watch(app: Host) {
+  if cfg!(any(target_os = "windows", target_os = "macos")) {
+    return;
+  }
+  tauri::async_runtime::spawn(async move {
+    let mut last: Option<String> = None;
+    loop {
+      tokio::time::sleep(POLL_INTERVAL).await;
+      let mode = settings::load(&app).now_playing;
+      if mode == NowPlayingMode::Off {
+        last = None;
+        continue;
+      }
+
+      let host = app.clone();
+      let track = match tokio::task::spawn_blocking(move || current(&host)).await {
+        Ok(Ok(track)) => track,
+        _ => continue,
+      };
+      let path = track.as_ref().map(|t| t.path.clone());
+      if path == last {
+        continue;
+      }
+      last = path;
+      app.emit("now-playing://changed", &track);
+
+      let Some(track) = track else { continue };
+      if mode == NowPlayingMode::Auto && !track.has_lyrics && !whisper::is_running() {
+        let Ok((model, options)) = presets::resolve(&app, "auto".into(), None, None) else { continue };
+        let result = whisper::generate_lrc_next_to_audio(app.clone(), &track.path, &model, options).await;
+        notify::outputs(&app, &result);
+      }
+    }
+  });
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn playing_track() -> Result<Option<NowPlaying>, String> {
+  let names = dbus(&[
+    "--dest=org.freedesktop.DBus",
+    "/org/freedesktop/DBus",
+    "org.freedesktop.DBus.ListNames",
+  ])?;
+  let players = names
+    .lines()
+    .filter_map(quoted)
+    .filter(|name| name.starts_with("org.mpris.MediaPlayer2."));
+
+  for player in players {
+    let dest = format!("--dest={player}");
+    let property = |name: &str| {
+      dbus(&[
+        &dest,
+        "/org/mpris/MediaPlayer2",
+        "org.freedesktop.DBus.Properties.Get",
+        "string:org.mpris.MediaPlayer2.Player",
+        &format!("string:{name}"),
+      ])
+    };
+    let Ok(status) = property("PlaybackStatus") else { continue };
+    if !status.lines().filter_map(quoted).any(|s| s == "Playing") {
+      continue;
+    }
+    let Ok(metadata) = property("Metadata") else { continue };
+    // Streams and browser tabs have http(s) URLs or none at all; only local files count.
+    let Some(path) = metadata_value(&metadata, "xesam:url").and_then(|url| file_path(&url)) else { continue };
+    return Ok(Some(NowPlaying {
+      path: path.display().to_string(),
+      title: metadata_value(&metadata, "xesam:title"),
+      artist: metadata_value(&metadata, "xesam:artist"),
+      player: player.trim_start_matches("org.mpris.MediaPlayer2.").to_string(),
+      has_lyrics: false,
+    }));
+  }
+  Ok(None)
+}
+
+// Windows' media sessions and macOS' MediaRemote only expose title and artist, not the
+// file being played, so there is nothing to write lyrics next to.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn playing_track() -> Result<Option<NowPlaying>, String> {
+  Err("Now-playing detection is only available on Linux (MPRIS players) for now".into())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn dbus(args: &[&str]) -> Result<String, String> {
+  let out = std::process::Command::new("dbus-send")
+    .args(["--session", "--print-reply", "--reply-timeout=1000"])
+    .args(args)
+    .output()
+    .map_err(|e| format!("Failed running dbus-send: {e}"))?;
+  if !out.status.success() {
+    return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
+  }
+  Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+// `string "value"` in dbus-send's reply format.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn quoted(line: &str) -> Option<&str> {
+  let rest = &line[line.find("string \"")? + "string \"".len()..];
+  rest.rfind('"').map(|end| &rest[..end])
+}
+
+// The first string after `key` in a Metadata dict (artist is a list; its first entry).
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn metadata_value(metadata: &str, key: &str) -> Option<String> {
+  let needle = format!("\"{key}\"");
+  metadata
+    .lines()
+    .skip_while(|l| !l.contains(&needle))
+    .skip(1)
+    .find_map(quoted)
+    .map(str::to_string)
+    .filter(|v| !v.is_empty())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn file_path(url: &str) -> Option<std::path::PathBuf> {
+  let encoded = url.strip_prefix("file://")?;
+  let bytes = encoded.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = (bytes[i] == b'%').then(|| encoded.get(i + 1..i + 3)).flatten();
+    match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+      Some(b) => {
+        decoded.push(b);
+        i += 3;
+      }
+      None => {
+        decoded.push(bytes[i]);
+        i += 1;
+      }
+    }
+  }
+  let path = std::path::PathBuf::from(String::from_utf8(decoded).ok()?);
+  path.is_file().then_some(path)
+}
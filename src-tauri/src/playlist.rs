@@ -0,0 +1,61 @@
+use lyrictime_core::encoding;
+use std::path::{Path, PathBuf};
+
+pub fn is_playlist(path: &str) -> bool {
+  Path::new(path)
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| e.eq_ignore_ascii_case("m3u") || e.eq_ignore_ascii_case("m3u8"))
+}
+
+// `file:///music/a.flac` style entries; percent-escapes are decoded.
+fn file_url_path(entry: &str) -> Option<String> {
+  let rest = entry.strip_prefix("file://")?;
+  // file:///C:/Music -> C:/Music on Windows
+  let rest = if cfg!(windows) { rest.trim_start_matches('/') } else { rest };
+
+  let bytes = rest.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    match (bytes[i], rest.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok())) {
+      (b'%', Some(b)) => {
+        out.push(b);
+        i += 3;
+      }
+      (b, _) => {
+        out.push(b);
+        i += 1;
+      }
+    }
+  }
+  Some(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Local files listed in an .m3u/.m3u8 playlist, resolved against the playlist's folder.
+/// Comments/`#EXT` directives and remote streams are skipped; missing files are kept so
+/// the batch can report them.
+pub fn read_playlist(path: &Path) -> Result<Vec<PathBuf>, String> {
+  let bytes = std::fs::read(path).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  let text = encoding::decode(&bytes);
+  let dir = path.parent().unwrap_or(Path::new("."));
+
+  Ok(
+    text
+      .lines()
+      .map(str::trim)
+      .filter(|l| !l.is_empty() && !l.starts_with('#'))
+      .filter_map(|entry| {
+        let entry = match file_url_path(entry) {
+          Some(p) => p,
+          None if entry.contains("://") => return None,
+          None => entry.to_string(),
+        };
+        // Playlists written on Windows use backslashes.
+        let entry = if cfg!(windows) { entry } else { entry.replace('\\', "/") };
+        let p = PathBuf::from(entry);
+        Some(if p.is_absolute() { p } else { dir.join(p) })
+      })
+      .collect(),
+  )
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::host::Host;
+use crate::settings;
+use crate::whisper::{GenerateOptions, OutputFormat, Profile};
+
+/// A named bundle of model + generate options.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Preset {
+  pub name: String,
+  pub model: String,
+  #[serde(default)]
+  pub options: GenerateOptions,
+  /// Built-ins ship with the app and can't be overwritten or deleted.
+  #[serde(default)]
+  pub builtin: bool,
+}
+
+fn builtins() -> Vec<Preset> {
+  let karaoke = GenerateOptions {
+    gap_marker_seconds: 8,
+    refine_onsets: true,
+    ..Default::default()
+  };
+
+  let podcast = GenerateOptions {
+    profile: Profile::Speech,
+    output_format: Some(OutputFormat::Srt),
+    diarize: true,
+    ..Default::default()
+  };
+
+  let language_learning = GenerateOptions {
+    gap_marker_seconds: 5,
+    ..Default::default()
+  };
+
+  [
+    ("Karaoke", "hybrid", karaoke),
+    ("Podcast", "medium", podcast),
+    ("Language Learning", "medium", language_learning),
+  ]
+  .into_iter()
+  .map(|(name, model, options)| Preset {
+    name: name.into(),
+    model: model.into(),
+    options,
+    builtin: true,
+  })
+  .collect()
+}
+
+/// Built-ins first, then the user's presets.
+pub fn list(app: &Host) -> Vec<Preset> {
+  let mut all = builtins();
+  all.extend(settings::load(app).presets);
+  all
+}
+
+pub fn find(app: &Host, name: &str) -> Result<Preset, String> {
+  list(app)
+    .into_iter()
+    .find(|p| p.name.eq_ignore_ascii_case(name))
+    .ok_or_else(|| format!("Unknown preset: {name}"))
+}
+
+/// Model + options for a run: a preset supplies both, otherwise the saved defaults
+/// apply. Explicit options always win.
+pub fn resolve(
+  app: &Host,
+  model: String,
+  options: Option<GenerateOptions>,
+  preset: Option<String>,
+) -> Result<(String, GenerateOptions), String> {
+  let (model, preset_options) = match preset {
+    Some(name) => {
+      let p = find(app, &name)?;
+      (p.model, p.options)
+    }
+    None => (model, settings::load(app).generate),
+  };
+  // "auto" picks what this machine can run comfortably.
+  let model = if model.eq_ignore_ascii_case("auto") {
+    crate::capabilities::detect(settings::load(app).benchmarks).recommended_model
+  } else {
+    model
+  };
+  Ok((model, options.unwrap_or(preset_options)))
+}
+
+/// Save (or replace) a user preset by name.
+pub fn save(app: &Host, mut preset: Preset) -> Result<(), String> {
+  let name = preset.name.trim().to_string();
+  if name.is_empty() {
+    return Err("Preset name is empty".into());
+  }
+  if builtins().iter().any(|b| b.name.eq_ignore_ascii_case(&name)) {
+    return Err(format!("'{name}' is a built-in preset"));
+  }
+
+  preset.name = name;
+  preset.builtin = false;
+
+  let mut s = settings::load(app);
+  s.presets.retain(|p| !p.name.eq_ignore_ascii_case(&preset.name));
+  s.presets.push(preset);
+  settings::save(app, &s)
+}
+
+pub fn delete(app: &Host, name: &str) -> Result<(), String> {
+  if builtins().iter().any(|b| b.name.eq_ignore_ascii_case(name)) {
+    return Err(format!("'{name}' is a built-in preset"));
+  }
+
+  let mut s = settings::load(app);
+  let before = s.presets.len();
+  s.presets.retain(|p| !p.name.eq_ignore_ascii_case(name));
+  if s.presets.len() == before {
+    return Err(format!("Unknown preset: {name}"));
+  }
+  settings::save(app, &s)
+}
@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+
+/// Recent files kept; older ones drop off.
+const MAX_RECENT: usize = 30;
+
+/// An audio file LyricTime processed, for re-running without the file dialog.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentFile {
+  pub path: String,
+  /// Model of the last run.
+  pub model: String,
+  pub output: Option<String>,
+  /// Unix seconds.
+  pub processed_at: u64,
+  /// Filled in when listing; moved or deleted files stay until they drop off.
+  #[serde(skip_deserializing)]
+  pub exists: bool,
+}
+
+/// An audio file the user pinned.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Favorite {
+  pub path: String,
+  /// Unix seconds.
+  pub added_at: u64,
+  #[serde(skip_deserializing)]
+  pub exists: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Store {
+  recent: Vec<RecentFile>,
+  favorites: Vec<Favorite>,
+}
+
+fn store_path(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.app_data_dir()?.join("recents.json"))
+}
+
+fn unix_now() -> u64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map(|d| d.as_secs())
+    .unwrap_or(0)
+}
+
+// A missing or unreadable store starts empty; it only holds conveniences.
+fn load(app: &Host) -> Store {
+  store_path(app)
+    .and_then(|p| fs::read(&p).map_err(|e| e.to_string()))
+    .and_then(|b| serde_json::from_slice(&b).map_err(|e| e.to_string()))
+    .unwrap_or_default()
+}
+
+fn save(app: &Host, store: &Store) -> Result<(), String> {
+  let path = store_path(app)?;
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent).map_err(|e| format!("Failed creating {}: {e}", parent.display()))?;
+  }
+  let json = serde_json::to_vec_pretty(store).map_err(|e| e.to_string())?;
+  fs::write(&path, json).map_err(|e| format!("Failed writing {}: {e}", path.display()))
+}
+
+/// Put `path` at the top of the recent files.
+pub fn record(app: &Host, path: &str, model: &str, output: Option<&str>) -> Result<(), String> {
+  let mut store = load(app);
+  // A re-run moves the file to the top instead of repeating it.
+  store.recent.retain(|r| r.path != path);
+  store.recent.insert(
+    0,
+    RecentFile {
+      path: path.to_string(),
+      model: model.to_string(),
+      output: output.map(str::to_string),
+      processed_at: unix_now(),
+      exists: true,
+    },
+  );
+  store.recent.truncate(MAX_RECENT);
+  save(app, &store)
+}
+
+/// Most recent first.
+pub fn list(app: &Host) -> Vec<RecentFile> {
+  let mut recent = load(app).recent;
+  for r in &mut recent {
+    r.exists = Path::new(&r.path).exists();
+  }
+  recent
+}
+
+pub fn clear(app: &Host) -> Result<(), String> {
+  let mut store = load(app);
+  store.recent.clear();
+  save(app, &store)
+}
+
+/// In the order they were pinned.
+pub fn favorites(app: &Host) -> Vec<Favorite> {
+  let mut favorites = load(app).favorites;
+  for f in &mut favorites {
+    f.exists = Path::new(&f.path).exists();
+  }
+  favorites
+}
+
+/// Pin `path`; pinning it again is a no-op.
+pub fn add_favorite(app: &Host, path: &str) -> Result<(), String> {
+  if !Path::new(path).exists() {
+    return Err(format!("{path} doesn't exist"));
+  }
+  let mut store = load(app);
+  if store.favorites.iter().any(|f| f.path == path) {
+    return Ok(());
+  }
+  store.favorites.push(Favorite {
+    path: path.to_string(),
+    added_at: unix_now(),
+    exists: true,
+  });
+  save(app, &store)
+}
+
+pub fn remove_favorite(app: &Host, path: &str) -> Result<(), String> {
+  let mut store = load(app);
+  store.favorites.retain(|f| f.path != path);
+  save(app, &store)
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Open the system file manager with `path` selected (its folder, where selecting
+/// isn't supported).
+pub fn open_output_location(path: &Path) -> Result<(), String> {
+  if !path.exists() {
+    return Err(format!("{} no longer exists", path.display()));
+  }
+  let path = path.canonicalize().map_err(|e| format!("Failed resolving {}: {e}", path.display()))?;
+  reveal(&path)
+}
+
+#[cfg(target_os = "windows")]
+fn reveal(path: &Path) -> Result<(), String> {
+  use std::os::windows::process::CommandExt;
+  // explorer wants `/select,"C:\dir\file"` verbatim; the default quoting wraps the whole
+  // argument instead. Its exit code is 1 even on success, so only spawning can fail.
+  let path = crate::longpath::display(path);
+  Command::new("explorer")
+    .raw_arg(format!("/select,\"{path}\""))
+    .spawn()
+    .map(|_| ())
+    .map_err(|e| format!("Failed opening Explorer: {e}"))
+}
+
+#[cfg(target_os = "macos")]
+fn reveal(path: &Path) -> Result<(), String> {
+  run(Command::new("open").arg("-R").arg(path), "Finder")
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn reveal(path: &Path) -> Result<(), String> {
+  // Most file managers (Nautilus, Dolphin, Nemo, ...) implement FileManager1 and can
+  // select the file; otherwise just open the folder.
+  let show_items = Command::new("dbus-send")
+    .args([
+      "--session",
+      "--print-reply",
+      "--dest=org.freedesktop.FileManager1",
+      "--type=method_call",
+      "/org/freedesktop/FileManager1",
+      "org.freedesktop.FileManager1.ShowItems",
+    ])
+    .arg(format!("array:string:{}", file_uri(path)))
+    .arg("string:")
+    .output();
+  if show_items.is_ok_and(|o| o.status.success()) {
+    return Ok(());
+  }
+  let dir = path.parent().unwrap_or(path);
+  run(Command::new("xdg-open").arg(dir), "the file manager")
+}
+
+#[cfg(not(target_os = "windows"))]
+fn run(cmd: &mut Command, what: &str) -> Result<(), String> {
+  let status = cmd.status().map_err(|e| format!("Failed opening {what}: {e}"))?;
+  if status.success() {
+    Ok(())
+  } else {
+    Err(format!("Failed opening {what} ({status})"))
+  }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn file_uri(path: &Path) -> String {
+  let mut uri = String::from("file://");
+  for b in path.to_string_lossy().bytes() {
+    match b {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(b as char),
+      _ => uri.push_str(&format!("%{b:02X}")),
+    }
+  }
+  uri
+}
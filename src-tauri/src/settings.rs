@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::host::Host;
+use crate::library::LyricsLibrary;
+use crate::model_downloader::ModelSource;
+use crate::nowplaying::NowPlayingMode;
+use crate::presets::Preset;
+use crate::whisper::GenerateOptions;
+pub use lyrictime_core::vocabulary::VocabularyWord;
+
+/// Persisted user settings (app data /settings.json).
+/// Missing keys fall back to defaults so older files keep loading.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Settings {
+  /// Options used when the frontend doesn't pass any for a run.
+  pub generate: GenerateOptions,
+  /// User-defined presets (built-ins are not stored).
+  pub presets: Vec<Preset>,
+  /// User-registered model files, consulted before the bundled/downloaded search.
+  pub custom_models: Vec<CustomModel>,
+  /// Names, slang and invented words: fed to whisper's prompt and used to fix transcripts.
+  pub vocabulary: Vec<VocabularyWord>,
+  /// whisper executable to use instead of the bundled/downloaded one (`LYRICTIME_WHISPER`).
+  pub whisper_path: Option<String>,
+  /// ffmpeg executable; ffprobe is expected next to it (`LYRICTIME_FFMPEG`).
+  pub ffmpeg_path: Option<String>,
+  /// Directory models are downloaded to and searched first (`LYRICTIME_MODELS_DIR`).
+  pub models_dir: Option<String>,
+  /// Realtime factor per model from `run_benchmark` (processing time / audio length).
+  pub benchmarks: HashMap<String, f64>,
+  /// Log command lines, binary versions, models and platform with every run.
+  pub verbose_diagnostics: bool,
+  /// No desktop notification when a generation finishes or fails.
+  pub disable_notifications: bool,
+  /// Offer or run generation when the system media player starts a file without lyrics
+  /// (Linux, MPRIS players only).
+  pub now_playing: NowPlayingMode,
+  /// Write lyrics into a separate tree mirroring the music folders instead of next to the audio.
+  pub lyrics_library: Option<LyricsLibrary>,
+  /// Where outputs go when the folder next to the audio (or in the library) isn't writable.
+  pub fallback_output_dir: Option<String>,
+  /// Download rate limit in KiB/s for models and tools (0 = unlimited).
+  pub download_limit_kbps: u64,
+  /// Where whisper models are downloaded from.
+  pub model_source: ModelSource,
+  /// Hugging Face commit models are downloaded at (`pin_model_revision`); `main` if unset.
+  pub model_revision: Option<String>,
+  /// Never touch the network: downloads and URL inputs fail with an `offline_mode` error.
+  pub offline: bool,
+  /// The first-run setup finished; the wizard isn't shown again.
+  pub setup_complete: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CustomModel {
+  pub name: String,
+  pub path: String,
+}
+
+fn settings_path(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.app_data_dir()?.join("settings.json"))
+}
+
+// Environment variable first, then the settings key; blank values count as unset.
+fn path_override(var: &str, configured: Option<String>) -> Option<PathBuf> {
+  std::env::var(var)
+    .ok()
+    .filter(|v| !v.trim().is_empty())
+    .or(configured.filter(|v| !v.trim().is_empty()))
+    .map(|v| PathBuf::from(v.trim()))
+}
+
+pub fn whisper_override(app: &Host) -> Option<PathBuf> {
+  path_override("LYRICTIME_WHISPER", load(app).whisper_path)
+}
+
+pub fn ffmpeg_override(app: &Host) -> Option<PathBuf> {
+  path_override("LYRICTIME_FFMPEG", load(app).ffmpeg_path)
+}
+
+/// Where models are downloaded and looked up first: the override, else app data /models.
+pub fn models_dir(app: &Host) -> Result<PathBuf, String> {
+  match path_override("LYRICTIME_MODELS_DIR", load(app).models_dir) {
+    Some(dir) => Ok(dir),
+    None => Ok(app.app_data_dir().map_err(|e| e.to_string())?.join("models")),
+  }
+}
+
+/// Load settings, falling back to defaults if the file is missing or unreadable.
+pub fn load(app: &Host) -> Settings {
+  settings_path(app)
+    .ok()
+    .and_then(|p| std::fs::read_to_string(p).ok())
+    .and_then(|raw| serde_json::from_str(&raw).ok())
+    .unwrap_or_default()
+}
+
+pub fn save(app: &Host, settings: &Settings) -> Result<(), String> {
+  let path = settings_path(app).map_err(|e| e.to_string())?;
+  if let Some(parent) = path.parent() {
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+  }
+  let raw = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+  std::fs::write(&path, raw).map_err(|e| format!("Failed writing settings: {e}"))
+}
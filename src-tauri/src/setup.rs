@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::host::Host;
+use crate::{download, ffmpeg_downloader, model_downloader, models, settings, whisper};
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Rough memory tier, for which models the wizard suggests.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RamClass {
+  /// Under 8 GB: small or quantized models only.
+  Low,
+  /// 8–16 GB: medium fits, hybrid is tight.
+  Medium,
+  /// 16 GB and more: anything, hybrid included.
+  High,
+}
+
+/// What's installed, for the first-run wizard to offer the rest.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupStatus {
+  /// `run_setup` hasn't finished yet on this install.
+  pub first_run: bool,
+  pub ffmpeg: bool,
+  pub whisper: bool,
+  pub models: Vec<models::AvailableModel>,
+  /// Space left where models are stored; unset when the disk can't be determined.
+  pub free_disk_bytes: Option<u64>,
+  pub total_ram_bytes: u64,
+  pub ram_class: RamClass,
+}
+
+/// What the wizard chose to download.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SetupSelections {
+  pub ffmpeg: bool,
+  pub whisper: bool,
+  /// Model names, e.g. "small", "medium-q5_0".
+  pub models: Vec<String>,
+}
+
+/// Frontend listens to: `setup://progress`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupProgressEvent {
+  /// 1-based step being worked on.
+  pub step: usize,
+  pub total_steps: usize,
+  pub item: String,
+  pub status: String, // "running" | "done" | "failed"
+  /// Across all steps; tool sizes aren't known upfront, so this can pass `total_bytes`.
+  pub downloaded_bytes: u64,
+  pub total_bytes: u64,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
+fn ram_class(total: u64) -> RamClass {
+  match total {
+    t if t < 8 * GIB => RamClass::Low,
+    t if t < 16 * GIB => RamClass::Medium,
+    _ => RamClass::High,
+  }
+}
+
+// The disk holding `dir`: the one with the longest mount point it's under.
+fn free_disk_bytes(dir: &std::path::Path) -> Option<u64> {
+  let dir = dir.ancestors().find(|d| d.exists())?.canonicalize().ok()?;
+  sysinfo::Disks::new_with_refreshed_list()
+    .list()
+    .iter()
+    .filter(|d| dir.starts_with(d.mount_point()))
+    .max_by_key(|d| d.mount_point().as_os_str().len())
+    .map(|d| d.available_space())
+}
+
+pub fn status(app: &Host) -> Result<SetupStatus, String> {
+  let mut sys = sysinfo::System::new();
+  sys.refresh_memory();
+  let total_ram_bytes = sys.total_memory();
+  Ok(SetupStatus {
+    first_run: !settings::load(app).setup_complete,
+    ffmpeg: ffmpeg_downloader::installed(app),
+    whisper: whisper::whisper_installed(app),
+    models: models::available(app)?,
+    free_disk_bytes: free_disk_bytes(&settings::models_dir(app)?),
+    total_ram_bytes,
+    ram_class: ram_class(total_ram_bytes),
+  })
+}
+
+enum Step {
+  Ffmpeg,
+  Whisper,
+  Model(&'static model_downloader::ModelVariant),
+}
+
+impl Step {
+  fn label(&self) -> String {
+    match self {
+      Step::Ffmpeg => "ffmpeg".into(),
+      Step::Whisper => "whisper".into(),
+      Step::Model(v) => format!("{} model", v.name),
+    }
+  }
+
+  async fn run(&self, app: &Host) -> Result<(), String> {
+    match self {
+      Step::Ffmpeg => {
+        let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+        ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await.map(|_| ())
+      }
+      Step::Whisper => whisper::ensure_whisper_downloaded(app).await,
+      Step::Model(v) => model_downloader::ensure_model(app, v.name).await.map(|_| ()),
+    }
+  }
+}
+
+// Bytes of the files downloading right now; finished files are counted per step instead.
+fn in_flight_bytes() -> u64 {
+  download::status()
+    .iter()
+    .filter(|d| d.status == "downloading")
+    .map(|d| d.downloaded_bytes)
+    .sum()
+}
+
+/// Download exactly what was selected, reporting progress over the whole set. Stops at
+/// the first failure; what finished stays installed, so running it again resumes.
+pub async fn run(app: &Host, selections: SetupSelections) -> Result<(), String> {
+  let mut steps = Vec::new();
+  if selections.ffmpeg {
+    steps.push(Step::Ffmpeg);
+  }
+  if selections.whisper {
+    steps.push(Step::Whisper);
+  }
+  for name in &selections.models {
+    let variant = model_downloader::find_variant(name).ok_or_else(|| format!("Unknown model: {name}"))?;
+    steps.push(Step::Model(variant));
+  }
+  if !steps.is_empty() {
+    download::ensure_online(app, "downloading the selected components")?;
+  }
+
+  let installed = models::list(app)?;
+  let size = |step: &Step| match step {
+    Step::Model(v) if !installed.iter().any(|m| m.name == v.name) => v.download_bytes,
+    _ => 0,
+  };
+  let total_bytes: u64 = steps.iter().map(size).sum();
+  let total_steps = steps.len();
+  let mut done_bytes = 0;
+  let report = |step: usize, item: &str, status: &str, downloaded_bytes: u64, error: Option<String>| {
+    app.emit(
+      "setup://progress",
+      SetupProgressEvent {
+        step,
+        total_steps,
+        item: item.to_string(),
+        status: status.into(),
+        downloaded_bytes,
+        total_bytes,
+        error,
+      },
+    );
+  };
+
+  for (i, step) in steps.iter().enumerate() {
+    let item = step.label();
+    report(i + 1, &item, "running", done_bytes, None);
+    let running = step.run(app);
+    tokio::pin!(running);
+    let mut tick = tokio::time::interval(Duration::from_millis(500));
+    let result = loop {
+      tokio::select! {
+        result = &mut running => break result,
+        _ = tick.tick() => report(i + 1, &item, "running", done_bytes + in_flight_bytes(), None),
+      }
+    };
+    if let Err(e) = result {
+      report(i + 1, &item, "failed", done_bytes, Some(e.clone()));
+      return Err(format!("Setup failed at {item}: {e}"));
+    }
+    done_bytes += size(step);
+    report(i + 1, &item, "done", done_bytes, None);
+  }
+
+  let mut s = settings::load(app);
+  s.setup_complete = true;
+  settings::save(app, &s)
+}
@@ -0,0 +1,99 @@
+use lyrictime_core::progress::{Progress, ProgressEvent};
+use std::path::{Path, PathBuf};
+
+use crate::ffmpeg_downloader;
+use crate::host::Host;
+use crate::whisper::{self, GenerateOptions};
+
+/// One section of a longer file that gets its own output (a CD track, a chapter).
+pub struct Part {
+  pub start_ms: u64,
+  /// Unset runs to the end of the file.
+  pub end_ms: Option<u64>,
+  /// Output file name without extension.
+  pub name: String,
+  /// Tags for the cut file (title, artist, album) so the initial prompt still fits.
+  pub metadata: Vec<(String, String)>,
+}
+
+/// Make `name` safe to use as a file name on every platform.
+pub fn sanitize_file_name(name: &str) -> String {
+  let cleaned: String = name
+    .chars()
+    .map(|c| match c {
+      '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+      c if c.is_control() => '_',
+      c => c,
+    })
+    .collect();
+  let cleaned = cleaned.trim().trim_end_matches('.').to_string();
+  if cleaned.is_empty() {
+    "untitled".into()
+  } else {
+    cleaned
+  }
+}
+
+/// Cut `source` into `parts` and generate lyrics for each into `out_dir`. A failing part
+/// doesn't stop the rest; the written paths come back, or an error listing the failures.
+pub async fn generate_parts(
+  app: Host,
+  source: &Path,
+  parts: &[Part],
+  out_dir: &Path,
+  model: &str,
+  options: GenerateOptions,
+) -> Result<Vec<String>, String> {
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let ffmpeg = PathBuf::from(ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await?.ffmpeg_path);
+
+  // Splits don't take the single-flight guard while cutting, so each needs its own dir.
+  let started = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_err(|e| e.to_string())?;
+  let tmp_dir = whisper::RunDir::create(&format!("split-{}-{}", std::process::id(), started.as_nanos()))?;
+  // The cut keeps only the selected stream, so each part has just the one.
+  let audio_stream = options.audio_stream;
+  let run = whisper::RunContext::new(&app, &options);
+  let options = GenerateOptions { audio_stream: None, ..options };
+
+  let mut written = Vec::new();
+  let mut failed = Vec::new();
+  for (i, part) in parts.iter().enumerate() {
+    if run.cancel.is_cancelled() {
+      break;
+    }
+    app.report(ProgressEvent::Stage {
+      stage: format!("Part {}/{}", i + 1, parts.len()),
+      detail: Some(part.name.clone()),
+    });
+
+    let name = sanitize_file_name(&part.name);
+    let cut = tmp_dir.join(format!("{i:03}.flac"));
+    let cut_result =
+      whisper::run_ffmpeg_cut(&app, &run, &ffmpeg, source, part.start_ms, part.end_ms, audio_stream, &part.metadata, &cut).await;
+    let result = match cut_result {
+      Ok(()) => {
+        let out_path = out_dir.join(format!("{name}.lrc"));
+        whisper::generate_lrc(app.clone(), &cut.to_string_lossy(), &out_path, model, options.clone()).await
+      }
+      Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_file(&cut);
+
+    match result {
+      Ok(path) => written.push(path),
+      Err(_) if run.cancel.is_cancelled() => break,
+      Err(e) => {
+        app.report(ProgressEvent::Log { line: format!("{}: {e}", part.name) });
+        failed.push(format!("{}: {e}", part.name));
+      }
+    }
+  }
+
+  if run.cancel.is_cancelled() {
+    Err(format!("Cancelled after {} of {} parts", written.len(), parts.len()))
+  } else if failed.is_empty() {
+    Ok(written)
+  } else {
+    Err(format!("{} of {} parts failed:\n{}", failed.len(), parts.len(), failed.join("\n")))
+  }
+}
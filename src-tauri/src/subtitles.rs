@@ -0,0 +1,98 @@
+use lyrictime_core::encoding;
+use lyrictime_core::formats;
+use lyrictime_core::linebreak::TimedLine;
+use lyrictime_core::lrc::{
+  clean_lrc, format_ms_to_ts, lines_to_lrc, lines_to_timed_lrc, normalize_lrc_timestamps, parse_lrc, LrcLine,
+};
+use std::path::{Path, PathBuf};
+
+use crate::host::Host;
+use crate::{batch, library, settings};
+
+/// A cue ending this long before the next one starts gets a gap marker, so the line
+/// doesn't stay on screen through an instrumental break.
+const GAP_MS: u64 = 1500;
+
+pub fn is_subtitle(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| e.eq_ignore_ascii_case("srt") || e.eq_ignore_ascii_case("vtt"))
+}
+
+// Karaoke videos reveal a line word by word ("I", "I want", "I want it") or repeat a
+// cue across frames; keep one line per lyric.
+fn merge_reveals(cues: Vec<TimedLine>) -> Vec<TimedLine> {
+  let mut out: Vec<TimedLine> = Vec::with_capacity(cues.len());
+  for cue in cues {
+    if let Some(last) = out.last_mut() {
+      if cue.start_ms <= last.end_ms + 100 && cue.text.starts_with(&last.text) {
+        last.end_ms = last.end_ms.max(cue.end_ms);
+        last.text = cue.text;
+        continue;
+      }
+    }
+    out.push(cue);
+  }
+  out
+}
+
+// "Song.en.srt" belongs to "Song.mp3".
+fn matching_audio(subtitle: &Path) -> Option<PathBuf> {
+  let dir = subtitle.parent()?;
+  let stem = subtitle.file_stem()?.to_str()?;
+  std::fs::read_dir(dir)
+    .ok()?
+    .filter_map(|e| e.ok().map(|e| e.path()))
+    .filter(|p| batch::is_media_file(p) && !is_subtitle(p))
+    .filter_map(|p| {
+      let audio_stem = p.file_stem()?.to_str()?.to_string();
+      let matches = stem == audio_stem || stem.starts_with(&format!("{audio_stem}."));
+      matches.then_some((audio_stem.len(), p))
+    })
+    .max_by_key(|(len, _)| *len)
+    .map(|(_, p)| p)
+}
+
+/// Convert an SRT/VTT file to LRC through the usual cleanup (music cues dropped,
+/// timestamps de-duplicated) and write it next to the audio it belongs to, or next to
+/// the subtitle file if there is none, in the configured encoding. Returns the written path.
+pub fn import_subtitles(app: &Host, path: &Path) -> Result<String, String> {
+  let bytes = std::fs::read(path).map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  let cues = merge_reveals(formats::parse_subtitles(&encoding::decode(&bytes)));
+
+  let mut kept: Vec<TimedLine> = Vec::with_capacity(cues.len());
+  for cue in cues {
+    let cleaned = clean_lrc(&format!("{}{}", format_ms_to_ts(cue.start_ms as i64), cue.text), false);
+    if let Some(line) = parse_lrc(&cleaned).into_iter().next() {
+      kept.push(TimedLine { text: line.text, ..cue });
+    }
+  }
+  if kept.is_empty() {
+    return Err(format!("No lyric cues in {}", path.display()));
+  }
+
+  let lines: Vec<LrcLine> = kept
+    .iter()
+    .map(|c| LrcLine { ms: c.start_ms as i64, text: c.text.clone(), end_ms: Some(c.end_ms as i64) })
+    .collect();
+  let normalized = parse_lrc(&normalize_lrc_timestamps(&lines_to_timed_lrc(&lines), 250));
+
+  // Cue ends become gap markers where the next line isn't right behind.
+  let mut out = Vec::with_capacity(normalized.len() * 2);
+  for (i, line) in normalized.iter().enumerate() {
+    out.push(line.clone());
+    let Some(end) = line.end_ms else { continue };
+    let next = normalized.get(i + 1).map(|n| n.ms);
+    if next.is_none_or(|next| next > end + GAP_MS as i64) {
+      out.push(LrcLine { ms: end, text: String::new(), end_ms: None });
+    }
+  }
+
+  let out_path = matching_audio(path)
+    .unwrap_or_else(|| path.to_path_buf())
+    .with_extension("lrc");
+  let contents = encoding::encode(&lines_to_lrc(&out), settings::load(app).generate.encoding);
+  let out_path = library::write_with_fallback(app, &out_path, &contents)?;
+  Ok(out_path.display().to_string())
+}
@@ -0,0 +1,40 @@
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use serde::Serialize;
+use std::path::Path;
+
+/// What the UI shows before processing a file; also feeds LRC headers and name templates.
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTags {
+  pub artist: Option<String>,
+  pub title: Option<String>,
+  pub album: Option<String>,
+  pub duration_ms: u64,
+  /// Lyrics tag present (ID3 USLT, Vorbis LYRICS, MP4 ©lyr, …).
+  pub has_embedded_lyrics: bool,
+  /// An .lrc with the same name sits next to the file.
+  pub has_lrc_sidecar: bool,
+}
+
+/// Read tags and duration with lofty (no ffprobe needed, so it works before setup).
+pub fn read_audio_tags(path: &Path) -> Result<AudioTags, String> {
+  let tagged = Probe::open(path)
+    .and_then(|p| p.read())
+    .map_err(|e| format!("Failed reading tags from {}: {e}", path.display()))?;
+
+  let text = |v: Option<std::borrow::Cow<'_, str>>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+  let tag = tagged.primary_tag().or_else(|| tagged.first_tag());
+
+  Ok(AudioTags {
+    artist: tag.and_then(|t| text(t.artist())),
+    title: tag.and_then(|t| text(t.title())),
+    album: tag.and_then(|t| text(t.album())),
+    duration_ms: tagged.properties().duration().as_millis() as u64,
+    has_embedded_lyrics: tagged
+      .tags()
+      .iter()
+      .any(|t| t.get_string(&ItemKey::Lyrics).is_some_and(|l| !l.trim().is_empty())),
+    has_lrc_sidecar: path.with_extension("lrc").exists(),
+  })
+}
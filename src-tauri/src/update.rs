@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::host::Host;
+use crate::download;
+
+// Also lists the `deps` and `models` asset releases, which aren't app versions.
+const RELEASES_URL: &str = "https://api.github.com/repos/evilduck1/LyricTime/releases?per_page=30";
+
+#[derive(Deserialize)]
+struct Release {
+  tag_name: String,
+  html_url: String,
+  #[serde(default)]
+  body: Option<String>,
+  draft: bool,
+  prerelease: bool,
+  published_at: Option<String>,
+  #[serde(default)]
+  assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+  name: String,
+  browser_download_url: String,
+}
+
+/// The newest published LyricTime release compared with this build.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUpdate {
+  pub current_version: String,
+  pub latest_version: String,
+  pub update_available: bool,
+  /// The release notes, in Markdown.
+  pub changelog: String,
+  pub release_url: String,
+  /// This platform's installer, when the release has one; else use `release_url`.
+  pub download_url: Option<String>,
+  pub published_at: Option<String>,
+}
+
+/// "v3.1.0" -> (3, 1, 0); pre-release suffixes ("-beta.2") are ignored.
+fn parse_version(tag: &str) -> Option<(u64, u64, u64)> {
+  let core = tag.trim().trim_start_matches(['v', 'V']).split(['-', '+']).next()?;
+  let mut parts = core.split('.').map(|p| p.parse::<u64>());
+  let major = parts.next()?.ok()?;
+  let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+  let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+  Some((major, minor, patch))
+}
+
+// Installer types by preference for this platform, then a name hint for the CPU.
+fn installer(assets: &[Asset]) -> Option<String> {
+  let kinds: &[&str] = if cfg!(target_os = "windows") {
+    &[".msi", "-setup.exe", ".exe"]
+  } else if cfg!(target_os = "macos") {
+    &[".dmg", ".app.tar.gz"]
+  } else {
+    &[".appimage", ".deb", ".rpm"]
+  };
+  let arch: &[&str] = if cfg!(target_arch = "aarch64") { &["aarch64", "arm64"] } else { &["x64", "x86_64", "amd64"] };
+  kinds.iter().find_map(|kind| {
+    let matching: Vec<&Asset> = assets.iter().filter(|a| a.name.to_ascii_lowercase().ends_with(kind)).collect();
+    matching
+      .iter()
+      .find(|a| arch.iter().any(|h| a.name.to_ascii_lowercase().contains(h)))
+      .or(matching.first())
+      .map(|a| a.browser_download_url.clone())
+  })
+}
+
+/// Ask GitHub for the newest stable LyricTime release.
+pub async fn check(app: &Host) -> Result<AppUpdate, String> {
+  download::ensure_online(app, "checking for updates")?;
+  // GitHub's API rejects requests without a User-Agent.
+  let res = reqwest::Client::new()
+    .get(RELEASES_URL)
+    .header(reqwest::header::USER_AGENT, concat!("LyricTime/", env!("CARGO_PKG_VERSION")))
+    .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+    .send()
+    .await
+    .map_err(|e| format!("Couldn't reach GitHub: {e}"))?;
+  if !res.status().is_success() {
+    return Err(format!("GitHub answered HTTP {} when checking for updates", res.status()));
+  }
+  let body = res.bytes().await.map_err(|e| e.to_string())?;
+  let releases: Vec<Release> =
+    serde_json::from_slice(&body).map_err(|e| format!("Unexpected answer from GitHub: {e}"))?;
+
+  let current = env!("CARGO_PKG_VERSION");
+  let latest = releases
+    .into_iter()
+    .filter(|r| !r.draft && !r.prerelease)
+    .filter_map(|r| Some((parse_version(&r.tag_name)?, r)))
+    .max_by_key(|(version, _)| *version)
+    .ok_or("No LyricTime release found on GitHub")?;
+  let ((major, minor, patch), release) = latest;
+
+  Ok(AppUpdate {
+    current_version: current.to_string(),
+    latest_version: format!("{major}.{minor}.{patch}"),
+    update_available: parse_version(current).is_some_and(|c| c < (major, minor, patch)),
+    changelog: release.body.unwrap_or_default(),
+    release_url: release.html_url,
+    download_url: installer(&release.assets),
+    published_at: release.published_at,
+  })
+}
@@ -0,0 +1,93 @@
+use serde::Serialize;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::ffmpeg_downloader;
+use crate::host::Host;
+
+/// Decoded at this rate; plenty for drawing, and cheap even for long files.
+const SAMPLE_RATE: u32 = 8000;
+
+/// Min/max sample (-1.0..=1.0) of each `1 / peaks_per_second` slice of the audio.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformPeaks {
+  pub peaks_per_second: u32,
+  pub duration_ms: u64,
+  pub min: Vec<f32>,
+  pub max: Vec<f32>,
+}
+
+fn decode_peaks(ffmpeg: &Path, input: &Path, peaks_per_second: u32) -> Result<WaveformPeaks, String> {
+  let mut child = Command::new(ffmpeg)
+    .args(["-v", "error", "-i"])
+    .arg(input)
+    .args(["-vn", "-ac", "1", "-ar", &SAMPLE_RATE.to_string(), "-f", "s16le", "pipe:1"])
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+    .map_err(|e| format!("Failed spawning ffmpeg: {e}"))?;
+  let mut stdout = child.stdout.take().ok_or("ffmpeg has no stdout")?;
+
+  let bucket = (SAMPLE_RATE / peaks_per_second).max(1) as usize;
+  let (mut min, mut max) = (Vec::new(), Vec::new());
+  let (mut lo, mut hi, mut filled) = (0i16, 0i16, 0usize);
+  let mut samples = 0u64;
+  // Streamed in blocks so hour-long files never sit in memory as PCM.
+  let mut buf = vec![0u8; 64 * 1024];
+  let mut pending: Vec<u8> = Vec::with_capacity(buf.len() + 1);
+  loop {
+    let n = stdout.read(&mut buf).map_err(|e| format!("Failed reading ffmpeg output: {e}"))?;
+    if n == 0 {
+      break;
+    }
+    pending.extend_from_slice(&buf[..n]);
+    let whole = pending.len() / 2 * 2;
+    for c in pending[..whole].chunks_exact(2) {
+      let s = i16::from_le_bytes([c[0], c[1]]);
+      if filled == 0 {
+        (lo, hi) = (s, s);
+      } else {
+        (lo, hi) = (lo.min(s), hi.max(s));
+      }
+      filled += 1;
+      samples += 1;
+      if filled == bucket {
+        min.push(lo as f32 / i16::MAX as f32);
+        max.push(hi as f32 / i16::MAX as f32);
+        filled = 0;
+      }
+    }
+    pending.drain(..whole);
+  }
+  if filled > 0 {
+    min.push(lo as f32 / i16::MAX as f32);
+    max.push(hi as f32 / i16::MAX as f32);
+  }
+
+  let status = child.wait().map_err(|e| format!("Failed waiting for ffmpeg: {e}"))?;
+  if !status.success() && samples == 0 {
+    return Err(format!("ffmpeg could not decode {} ({status})", input.display()));
+  }
+
+  Ok(WaveformPeaks {
+    peaks_per_second,
+    duration_ms: samples * 1000 / SAMPLE_RATE as u64,
+    min,
+    max,
+  })
+}
+
+/// Peaks for drawing the waveform of `path`; `resolution` is peaks per second
+/// (clamped to 1..=1000).
+pub async fn get_waveform_peaks(app: Host, path: &Path, resolution: u32) -> Result<WaveformPeaks, String> {
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let ffmpeg = PathBuf::from(ffmpeg_downloader::ensure_ffmpeg(app, ffmpeg_url, ffprobe_url).await?.ffmpeg_path);
+  let path = path.to_path_buf();
+  let resolution = resolution.clamp(1, 1000);
+  tokio::task::spawn_blocking(move || decode_peaks(&ffmpeg, &path, resolution))
+    .await
+    .map_err(|e| format!("Waveform task failed: {e}"))?
+}
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use super::{emit, ProgressEvent};
+use crate::host::Host;
+use crate::{archive, download};
+
+/// A model's converted encoder for one of the accelerated builds (Core ML, OpenVINO).
+/// Each build only speeds up models whose encoder is installed next to them; the rest
+/// run on the default build.
+pub(super) struct Encoders {
+  /// For logs: "Core ML".
+  pub backend: &'static str,
+  /// Appended to the model's unquantized name: "-encoder.mlmodelc".
+  pub suffix: &'static str,
+  /// Extension of the weights file next to the encoder, if it has one.
+  pub weights: Option<&'static str>,
+  /// Appended likewise to name the zipped encoder.
+  pub zip_suffix: &'static str,
+  /// Where a zipped encoder is published, by file name.
+  pub url: fn(&Host, &str) -> String,
+}
+
+// "ggml-medium-q5_0.bin" -> "ggml-medium": quantized models share their encoder.
+fn base_name(model: &Path) -> String {
+  let stem = model.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+  match stem.rsplit_once('-') {
+    // "-q5_0", "-q8_0"
+    Some((base, quant)) if quant.len() == 4 && quant.starts_with('q') && quant.as_bytes()[2] == b'_' => {
+      base.to_string()
+    }
+    _ => stem,
+  }
+}
+
+pub(super) fn log(app: &Host, line: String) {
+  emit(app, ProgressEvent::Log { line });
+}
+
+impl Encoders {
+  /// Where whisper.cpp looks for `model`'s encoder: next to it, named after it without
+  /// the quantization.
+  pub fn path(&self, model: &Path) -> PathBuf {
+    model.with_file_name(format!("{}{}", base_name(model), self.suffix))
+  }
+
+  pub fn installed(&self, model: &Path) -> bool {
+    let encoder = self.path(model);
+    encoder.exists() && self.weights.is_none_or(|ext| encoder.with_extension(ext).is_file())
+  }
+
+  fn remove(&self, model: &Path) {
+    let encoder = self.path(model);
+    let _ = if encoder.is_dir() { std::fs::remove_dir_all(&encoder) } else { std::fs::remove_file(&encoder) };
+    if let Some(ext) = self.weights {
+      let _ = std::fs::remove_file(encoder.with_extension(ext));
+    }
+  }
+
+  /// Make sure `model`'s encoder is in place when the build should `fetch` missing ones.
+  /// Best-effort: a model without one runs on the default build.
+  pub async fn ensure(&self, app: &Host, fetch: bool, model: &Path) {
+    if !fetch || self.installed(model) {
+      return;
+    }
+    if let Err(e) = self.download(app, model).await {
+      self.remove(model);
+      let backend = self.backend;
+      log(app, format!("{backend} encoder for {} unavailable ({e}); running it without {backend}", model.display()));
+    }
+  }
+
+  async fn download(&self, app: &Host, model: &Path) -> Result<(), String> {
+    let dir = model.parent().ok_or("Invalid model path")?;
+    let zip_name = format!("{}{}", base_name(model), self.zip_suffix);
+    let zip = dir.join(&zip_name);
+    download::download_with_progress(app, "models", &(self.url)(app, &zip_name), &zip, &zip_name).await?;
+    let extracted = archive::extract_tree(&zip, dir);
+    let _ = std::fs::remove_file(&zip);
+    extracted?;
+    if !self.installed(model) {
+      return Err(format!("{zip_name} doesn't contain the {} encoder", self.backend));
+    }
+    Ok(())
+  }
+}
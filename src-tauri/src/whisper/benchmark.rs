@@ -0,0 +1,100 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::{emit, process, resolve_model, ProgressEvent, RunningGuard, Toolchain};
+use crate::host::Host;
+use crate::{model_downloader, settings};
+
+const SAMPLE_SECONDS: u64 = 30;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+  pub model: String,
+  pub audio_ms: u64,
+  pub elapsed_ms: u64,
+  /// Processing time / audio length; below 1.0 is faster than realtime.
+  pub realtime_factor: f64,
+}
+
+// The bundled 30 s song-like clip (16 kHz mono, synthesized; see resources/samples). Music
+// with a voice-like melody keeps the decoder busy the way a user's files do; silence or a
+// tone lets it stop early and flatters the realtime factor.
+fn benchmark_sample(resources_dir: &Path, fallback: Option<&PathBuf>) -> Result<PathBuf, String> {
+  std::iter::once(resources_dir)
+    .chain(fallback.map(PathBuf::as_path))
+    .map(|dir| dir.join("samples").join("benchmark.wav"))
+    .find(|p| p.exists())
+    .ok_or_else(|| "Benchmark sample is missing from the app's resources".to_string())
+}
+
+/// Time one whisper pass of `model` over a 30-second sample and remember its realtime
+/// factor in settings (ETAs and the "auto" model choice use it).
+pub async fn run_benchmark(app: Host, model: &str) -> Result<BenchmarkResult, String> {
+  if model.eq_ignore_ascii_case("hybrid") || model.eq_ignore_ascii_case("auto") {
+    return Err("Benchmark small and medium separately; hybrid runs both".into());
+  }
+  let _guard = RunningGuard::acquire()?;
+
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Benchmarking".into(),
+      detail: Some(format!("Preparing {model}")),
+    },
+  );
+
+  let Toolchain { resources_dir, fallback_resources_dir, whisper, .. } = Toolchain::prepare(&app).await?;
+  if model_downloader::find_variant(model).is_some() {
+    model_downloader::ensure_model(&app, model).await?;
+  }
+
+  // No VAD and no language tricks: every run decodes the same audio the same way.
+  let params = process::WhisperParams {
+    run: process::RunContext::for_app(&app),
+    ..Default::default()
+  };
+  let model_path = resolve_model(&app, &resources_dir, fallback_resources_dir.as_ref(), model, &params)?;
+
+  let tmp_dir = process::RunDir::create(&format!("benchmark-{}", std::process::id()))?;
+  let sample = benchmark_sample(&resources_dir, fallback_resources_dir.as_ref())?;
+
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Benchmarking".into(),
+      detail: Some(format!("Transcribing a {SAMPLE_SECONDS}-second sample with {model}")),
+    },
+  );
+  let started = Instant::now();
+  let result = process::run_whisper_lrc(&app, &whisper, &model_path, process::AudioInput::File(&sample), &tmp_dir.join("out"), &params).await;
+  let elapsed_ms = started.elapsed().as_millis() as u64;
+  result?;
+
+  let audio_ms = SAMPLE_SECONDS * 1000;
+  let realtime_factor = elapsed_ms as f64 / audio_ms as f64;
+
+  let mut s = settings::load(&app);
+  s.benchmarks.insert(model.to_string(), realtime_factor);
+  settings::save(&app, &s)?;
+
+  Ok(BenchmarkResult {
+    model: model.to_string(),
+    audio_ms,
+    elapsed_ms,
+    realtime_factor,
+  })
+}
+
+/// "about 3 min" for `audio_ms` of audio through `models`, if all were benchmarked.
+pub fn eta(app: &Host, models: &[&str], audio_ms: u64) -> Option<String> {
+  let benchmarks = settings::load(app).benchmarks;
+  let factor: f64 = models.iter().map(|m| benchmarks.get(*m).copied()).sum::<Option<f64>>()?;
+  let secs = (audio_ms as f64 / 1000.0 * factor).round() as u64;
+  Some(if secs < 90 {
+    format!("about {secs} s")
+  } else {
+    format!("about {} min", (secs + 30) / 60)
+  })
+}
@@ -0,0 +1,94 @@
+use lyrictime_core::diff::{self, DiffRow};
+use lyrictime_core::lrc::parse_lrc;
+use serde::Serialize;
+use std::path::Path;
+
+use super::{
+  converted_wav, emit, ffmpeg_args, process, resolve_model, transcribe, whisper_params, GenerateOptions, ProgressEvent,
+  RunningGuard, Toolchain, WhisperInput,
+};
+use crate::host::Host;
+use crate::model_downloader;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelComparison {
+  pub model_a: String,
+  pub model_b: String,
+  /// Cleaned LRC of each pass, before line-breaking and other post-processing.
+  pub lrc_a: String,
+  pub lrc_b: String,
+  pub diff: Vec<DiffRow>,
+}
+
+/// Transcribe `audio_path` with two models (one WAV conversion, same settings) and line
+/// up the results so the better one can be picked line by line.
+pub async fn compare_models(
+  app: Host,
+  audio_path: &Path,
+  model_a: &str,
+  model_b: &str,
+  options: GenerateOptions,
+) -> Result<ModelComparison, String> {
+  for model in [model_a, model_b] {
+    if model.eq_ignore_ascii_case("hybrid") || model.eq_ignore_ascii_case("auto") {
+      return Err(format!("Compare single models; '{model}' isn't one"));
+    }
+  }
+  let _guard = RunningGuard::acquire()?;
+
+  if !audio_path.exists() {
+    return Err("Audio file does not exist".into());
+  }
+
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Comparing".into(),
+      detail: Some("Preparing".into()),
+    },
+  );
+  let Toolchain { resources_dir, fallback_resources_dir, ffmpeg, ffprobe, whisper } = Toolchain::prepare(&app).await?;
+  for model in [model_a, model_b] {
+    if model_downloader::find_variant(model).is_some() {
+      model_downloader::ensure_model(&app, model).await?;
+    }
+  }
+
+  let streams = process::probe_audio_streams(&ffprobe, audio_path)?;
+  if streams.is_empty() {
+    return Err(format!("No audio track found in {}", audio_path.display()));
+  }
+  let ffmpeg_args = ffmpeg_args(&app, &streams, &options)?;
+
+  // No VAD: both passes should see exactly the same audio.
+  let params = whisper_params(&app, &ffprobe, audio_path, &options, process::RunContext::new(&app, &options)).await?;
+
+  let tmp_dir = process::RunDir::create(&format!("compare-{}", std::process::id()))?;
+  let wav = converted_wav(&app, &params.run, &ffmpeg, audio_path, &ffmpeg_args, &tmp_dir, None).await?;
+  let input = WhisperInput::Single(wav);
+
+  let mut outputs = Vec::with_capacity(2);
+  for (i, model) in [model_a, model_b].into_iter().enumerate() {
+    emit(
+      &app,
+      ProgressEvent::Stage {
+        stage: "Comparing".into(),
+        detail: Some(format!("Transcribing with {model}")),
+      },
+    );
+    let model_path = resolve_model(&app, &resources_dir, fallback_resources_dir.as_ref(), model, &params)?;
+    let prefix = tmp_dir.join(format!("out_{i}"));
+    outputs.push(transcribe(&app, &whisper, &model_path, &input, &prefix, &params, options.profile).await?.unwrap_or_default());
+  }
+  let lrc_b = outputs.pop().unwrap_or_default();
+  let lrc_a = outputs.pop().unwrap_or_default();
+
+  Ok(ModelComparison {
+    model_a: model_a.to_string(),
+    model_b: model_b.to_string(),
+    diff: diff::diff_lines(&parse_lrc(&lrc_a), &parse_lrc(&lrc_b)),
+    lrc_a,
+    lrc_b,
+  })
+}
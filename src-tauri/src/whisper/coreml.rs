@@ -0,0 +1,73 @@
+use std::path::PathBuf;
+
+use super::accel::{log, Encoders};
+use super::{whisper_bin_dir, Backend};
+use crate::host::Host;
+use crate::{ffmpeg_downloader, model_downloader};
+
+// A separate whisper build with Core ML enabled, in its own folder since its libwhisper
+// shares names with the default build's.
+const COREML_ARCHIVE_URL: &str = "https://github.com/evilduck1/LyricTime/releases/download/deps/whisper-coreml-macos-arm64.zip";
+const COREML_FILES: &[&str] = &[
+  "whisper-cli",
+  "libwhisper.1.dylib",
+  "libwhisper.coreml.dylib",
+  "libggml.0.dylib",
+  "libggml-base.0.dylib",
+  "libggml-cpu.0.dylib",
+  "libggml-metal.0.dylib",
+  "libggml-blas.0.dylib",
+];
+
+/// The Core ML build, and whether to fetch encoders it's missing.
+#[derive(Clone, Debug)]
+pub struct CoreMl {
+  pub whisper: PathBuf,
+  /// Core ML was chosen explicitly: download missing encoders instead of only using
+  /// installed ones.
+  pub fetch_encoders: bool,
+}
+
+/// Core ML runs whisper's encoder on the Neural Engine, which only M-series Macs have.
+pub fn supported() -> bool {
+  cfg!(all(target_os = "macos", target_arch = "aarch64"))
+}
+
+/// Core ML encoders: "ggml-medium-q5_0.bin" -> "ggml-medium-encoder.mlmodelc", a folder.
+/// whisper.cpp publishes them compiled and zipped, next to the models.
+pub(super) const ENCODERS: Encoders = Encoders {
+  backend: "Core ML",
+  suffix: "-encoder.mlmodelc",
+  weights: None,
+  zip_suffix: "-encoder.mlmodelc.zip",
+  url: |app, zip_name| {
+    let (repo, revision) = (model_downloader::HF_REPO, model_downloader::revision(app));
+    format!("https://huggingface.co/{repo}/resolve/{revision}/{zip_name}")
+  },
+};
+
+/// The Core ML build to run with `backend`, or `None` for the default build: on other
+/// machines, with Auto when it isn't installed yet, or when it can't be downloaded.
+pub(super) async fn prepare(app: &Host, backend: Backend) -> Option<CoreMl> {
+  if !matches!(backend, Backend::Auto | Backend::CoreMl) {
+    return None;
+  }
+  if !supported() {
+    if backend == Backend::CoreMl {
+      log(app, "Core ML needs an Apple Silicon Mac; using the default backend".into());
+    }
+    return None;
+  }
+
+  let whisper = whisper_bin_dir(app).ok()?.join("coreml").join("whisper-cli");
+  if backend == Backend::Auto {
+    return whisper.is_file().then_some(CoreMl { whisper, fetch_encoders: false });
+  }
+  match ffmpeg_downloader::ensure_binary(app, COREML_ARCHIVE_URL, &whisper, "whisper-cli", "--help", COREML_FILES).await {
+    Ok(()) => Some(CoreMl { whisper, fetch_encoders: true }),
+    Err(e) => {
+      log(app, format!("Core ML build unavailable ({e}); using the default backend"));
+      None
+    }
+  }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use super::{emit, process, ProgressEvent};
+use crate::host::Host;
+
+/// Binaries, versions and platform of a run, sent at its start when verbose diagnostics
+/// are on, so "whisper failed with status 1" reports come with something to go on.
+/// whisper.cpp builds without `--version` report no version.
+pub(super) fn report(app: &Host, run: &process::RunContext, whisper: &Path, ffmpeg: &Path) {
+  if !run.verbose {
+    return;
+  }
+  let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+  let platform = format!("{} {}, {threads} threads", std::env::consts::OS, std::env::consts::ARCH);
+  let whisper_version = process::binary_version(whisper, "--version");
+  let ffmpeg_version = process::binary_version(ffmpeg, "-version");
+
+  // Logged as well, so the details end up in copied logs and the CLI's --verbose output.
+  let unknown = || "version unknown".to_string();
+  for line in [
+    format!("LyricTime {} on {platform}", env!("CARGO_PKG_VERSION")),
+    format!("whisper: {} ({})", whisper.display(), whisper_version.clone().unwrap_or_else(unknown)),
+    format!("ffmpeg: {} ({})", ffmpeg.display(), ffmpeg_version.clone().unwrap_or_else(unknown)),
+  ] {
+    emit(app, ProgressEvent::Log { line });
+  }
+  emit(
+    app,
+    ProgressEvent::Diagnostics {
+      app_version: env!("CARGO_PKG_VERSION").into(),
+      platform,
+      whisper_path: whisper.display().to_string(),
+      whisper_version,
+      ffmpeg_path: ffmpeg.display().to_string(),
+      ffmpeg_version,
+    },
+  );
+}
+
+/// Log the path and size of a model a pass is about to use.
+pub(super) fn model(app: &Host, run: &process::RunContext, path: &Path) {
+  if !run.verbose {
+    return;
+  }
+  let size = match std::fs::metadata(path) {
+    Ok(m) => format!("{:.1} MB", m.len() as f64 / 1_000_000.0),
+    Err(e) => format!("unreadable: {e}"),
+  };
+  emit(
+    app,
+    ProgressEvent::Log {
+      line: format!("Model: {} ({size})", path.display()),
+    },
+  );
+}
@@ -0,0 +1,121 @@
+use std::process::ExitStatus;
+
+/// Why a whisper/ffmpeg child failed, worked out from its exit status and what it
+/// printed, so the user gets something to act on instead of a raw exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+  OutOfMemory,
+  /// The build uses CPU instructions (AVX, AVX2, F16C) this machine doesn't have.
+  UnsupportedCpu,
+  /// The model file is truncated, corrupt or not a ggml model.
+  BadModel,
+  /// An input, output directory or shared library couldn't be found.
+  FileNotFound,
+  /// ffmpeg couldn't decode the input.
+  BadInput,
+  /// Crashed for some other reason (access violation, assertion, abort).
+  Crashed,
+  /// Exited with an error code we don't know more about.
+  Failed,
+}
+
+impl Failure {
+  /// `code` of the `error` progress event.
+  pub fn code(self) -> &'static str {
+    match self {
+      Failure::OutOfMemory => "out_of_memory",
+      Failure::UnsupportedCpu => "unsupported_cpu",
+      Failure::BadModel => "model_invalid",
+      Failure::FileNotFound => "file_not_found",
+      Failure::BadInput => "bad_input",
+      Failure::Crashed => "crashed",
+      Failure::Failed => "failed",
+    }
+  }
+
+  fn advice(self) -> &'static str {
+    match self {
+      Failure::OutOfMemory => "close other programs or use a smaller model",
+      Failure::UnsupportedCpu => {
+        "this whisper build needs CPU features this machine lacks; set a whisper executable built for older CPUs in Settings"
+      }
+      Failure::BadModel => "the model file looks damaged; redownload it from the Models list",
+      Failure::FileNotFound => "a file it needs is missing; check the input still exists and reinstall if a library is missing",
+      Failure::BadInput => "the audio couldn't be decoded; the file may be corrupt or use an unsupported codec",
+      Failure::Crashed => "turn on verbose diagnostics and include the log when reporting this",
+      Failure::Failed => "see the log above for its last messages",
+    }
+  }
+}
+
+/// A failure a child's output line points at, if any.
+pub fn from_output_line(line: &str) -> Option<Failure> {
+  let l = line.to_ascii_lowercase();
+  let any = |patterns: &[&str]| patterns.iter().any(|p| l.contains(p));
+  if any(&["failed to allocate", "out of memory", "bad_alloc", "not enough space in the context", "erroroutofdevicememory"]) {
+    Some(Failure::OutOfMemory)
+  } else if any(&["invalid model data", "bad magic", "failed to load model", "failed to initialize whisper context"]) {
+    Some(Failure::BadModel)
+  } else if any(&["invalid data found when processing input", "could not find codec parameters", "moov atom not found"]) {
+    Some(Failure::BadInput)
+  } else if any(&["no such file or directory", "error while loading shared libraries", "failed to open"]) {
+    Some(Failure::FileNotFound)
+  } else {
+    None
+  }
+}
+
+// NTSTATUS codes Windows reports as the exit code of a crashed process.
+#[cfg(windows)]
+fn from_exit_code(code: i32) -> Failure {
+  match code as u32 {
+    0xC000_001D | 0xC000_0096 => Failure::UnsupportedCpu,
+    0xC000_0017 | 0xC000_012D => Failure::OutOfMemory,
+    0xC000_0135 | 0xC000_0139 | 0xC000_007B => Failure::FileNotFound,
+    0xC000_0005 | 0xC000_0409 | 0xC000_00FD => Failure::Crashed,
+    _ => Failure::Failed,
+  }
+}
+
+#[cfg(not(windows))]
+fn from_exit_code(_code: i32) -> Failure {
+  Failure::Failed
+}
+
+/// Classify a non-zero exit. What the child printed wins over the status, which on its
+/// own often only says "crashed".
+pub fn classify(status: &ExitStatus, hint: Option<Failure>) -> Failure {
+  if let Some(hint) = hint {
+    return hint;
+  }
+  #[cfg(unix)]
+  {
+    use std::os::unix::process::ExitStatusExt;
+    match status.signal() {
+      Some(4) => return Failure::UnsupportedCpu,
+      // Linux's OOM killer sends SIGKILL; nothing else in the pipeline does while we wait.
+      Some(9) => return Failure::OutOfMemory,
+      Some(_) => return Failure::Crashed,
+      None => {}
+    }
+  }
+  status.code().map_or(Failure::Crashed, from_exit_code)
+}
+
+/// What went wrong and what to do about it, ending in the raw status for bug reports.
+pub fn message(label: &str, failure: Failure, status: &ExitStatus) -> String {
+  let what = match failure {
+    Failure::OutOfMemory => super::process::OUT_OF_MEMORY,
+    Failure::UnsupportedCpu => "hit an unsupported CPU instruction",
+    Failure::BadModel => "couldn't load the model",
+    Failure::FileNotFound => "couldn't find a file",
+    Failure::BadInput => "couldn't read the input",
+    Failure::Crashed => "crashed",
+    Failure::Failed => "failed",
+  };
+  let raw = match status.code() {
+    Some(code) if cfg!(windows) && code < 0 => format!("exit code {:#010X}", code as u32),
+    _ => status.to_string(),
+  };
+  format!("{label} {what}: {} ({raw})", failure.advice())
+}
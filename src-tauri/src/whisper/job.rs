@@ -0,0 +1,160 @@
+use lyrictime_core::lrc::{normalize_lrc_timestamps, LrcLine, MergeDecision};
+use lyrictime_core::merge;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::{emit, write_merge_log, write_output, Analysis, GenerateOptions, ProgressEvent, RunningGuard};
+use crate::host::Host;
+
+/// Steps of a generation, in order. Each one's result is saved, so a failed or
+/// unsatisfying run can be picked up again from Merge on without re-transcribing.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+  #[default]
+  Probe,
+  Convert,
+  Transcribe,
+  Merge,
+  /// Post-processing: vocabulary, alignment, line shaping, markers.
+  Clean,
+  Write,
+  Done,
+}
+
+/// One whisper pass's cleaned transcript.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Pass {
+  pub model: String,
+  pub transcript: String,
+}
+
+/// What the last generation produced so far, saved to app data after every stage.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Job {
+  /// The stage reached; earlier stages finished.
+  pub stage: Stage,
+  pub audio_path: String,
+  pub out_path: String,
+  pub model: String,
+  pub options: GenerateOptions,
+  /// In run order: small then medium for hybrid.
+  pub passes: Vec<Pass>,
+  /// The merged transcript, before post-processing.
+  pub merged: Option<String>,
+  pub speaker_turns: Option<Vec<(i64, usize)>>,
+  pub translation: Option<Vec<LrcLine>>,
+  pub chapters: Vec<(i64, String)>,
+}
+
+fn job_path(app: &Host) -> Result<PathBuf, String> {
+  Ok(app.app_data_dir()?.join("jobs").join("last.json"))
+}
+
+impl Job {
+  pub fn new(audio_path: &Path, out_path: &Path, model: &str, options: &GenerateOptions) -> Self {
+    Job {
+      audio_path: audio_path.display().to_string(),
+      out_path: out_path.display().to_string(),
+      model: model.to_string(),
+      options: options.clone(),
+      ..Default::default()
+    }
+  }
+
+  /// Move on to `stage` and save. Best-effort: a job that can't be saved only loses
+  /// the ability to retry.
+  pub fn enter(&mut self, app: &Host, stage: Stage) {
+    self.stage = stage;
+    if let Err(e) = self.save(app) {
+      emit(app, ProgressEvent::Log { line: format!("Failed saving job state: {e}") });
+    }
+  }
+
+  pub fn add_pass(&mut self, app: &Host, model: &str, transcript: &str) {
+    self.passes.push(Pass {
+      model: model.to_string(),
+      transcript: transcript.to_string(),
+    });
+    self.enter(app, self.stage);
+  }
+
+  /// Hybrid's two passes merged with the chosen strategy; a lone hybrid pass (medium
+  /// missing or out of memory) only gets its timestamps normalized.
+  pub fn merge_passes(&self, options: &GenerateOptions) -> Result<(String, Vec<MergeDecision>), String> {
+    match self.passes.as_slice() {
+      [small, medium] => Ok(merge::strategy(options.merge, options.profile).merge(&small.transcript, &medium.transcript)),
+      [single] if self.model.eq_ignore_ascii_case("hybrid") => {
+        Ok((normalize_lrc_timestamps(&single.transcript, 250), Vec::new()))
+      }
+      [single] => Ok((single.transcript.clone(), Vec::new())),
+      _ => Err("The last generation didn't finish transcribing".into()),
+    }
+  }
+
+  fn save(&self, app: &Host) -> Result<(), String> {
+    let path = job_path(app)?;
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(|e| format!("Failed creating {}: {e}", parent.display()))?;
+    }
+    let json = serde_json::to_vec(self).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed writing {}: {e}", path.display()))
+  }
+}
+
+/// The last generation's saved state.
+pub fn last_job(app: &Host) -> Result<Job, String> {
+  let path = job_path(app)?;
+  let raw = std::fs::read(&path).map_err(|_| "No earlier generation to retry".to_string())?;
+  serde_json::from_slice(&raw).map_err(|e| format!("Failed reading {}: {e}", path.display()))
+}
+
+/// Re-run the last generation from `from` (Merge, Clean or Write) with its saved
+/// transcripts, optionally with different options, e.g. another merge strategy.
+/// Passes that need the audio (onset and beat snapping, multilingual) are skipped.
+pub async fn retry_generation(app: Host, from: Stage, options: Option<GenerateOptions>) -> Result<String, String> {
+  let _guard = RunningGuard::acquire()?;
+
+  let mut job = last_job(&app)?;
+  if let Some(options) = options {
+    job.options = options;
+  }
+  let options = job.options.clone();
+
+  let merged = match from {
+    Stage::Probe | Stage::Convert | Stage::Transcribe => {
+      return Err("Transcription can't be resumed; generate again instead".into());
+    }
+    Stage::Done => return Err("Nothing to retry after Done".into()),
+    Stage::Merge => {
+      job.enter(&app, Stage::Merge);
+      let (merged, decisions) = job.merge_passes(&options)?;
+      if options.merge_log && !decisions.is_empty() {
+        write_merge_log(&app, Path::new(&job.out_path), &decisions);
+      }
+      job.merged = Some(merged.clone());
+      merged
+    }
+    Stage::Clean | Stage::Write => job.merged.clone().ok_or("The last generation didn't finish merging")?,
+  };
+
+  for (skipped, on) in [
+    ("Onset refinement", options.refine_onsets),
+    ("Beat snapping", options.snap_to_beats),
+    ("Multilingual re-transcription", options.multilingual && from == Stage::Merge),
+  ] {
+    if on {
+      emit(&app, ProgressEvent::Log { line: format!("{skipped} needs the audio; skipped on retry") });
+    }
+  }
+
+  let analysis = Analysis {
+    wav: None,
+    speaker_turns: job.speaker_turns.clone(),
+    translation: job.translation.clone(),
+    chapters: job.chapters.clone(),
+  };
+  let out_path = PathBuf::from(&job.out_path);
+  write_output(&app, &out_path, &merged, &options, analysis, Vec::new(), &mut job)
+}
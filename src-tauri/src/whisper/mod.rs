@@ -1,49 +1,93 @@
-use serde::Serialize;
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::{AppHandle, Emitter, Manager};
-
-use crate::{download, ffmpeg_downloader, model_downloader};
 
+use lyrictime_core::lrc::{
+  apply_offset_tag, clean_lrc, format_ms_to_ts, is_instrumental, lines_to_timed_lrc, parse_lrc, render, retime_lrc, LrcLine,
+  MergeDecision,
+};
+use lyrictime_core::pipeline::{self, Extras};
+use lyrictime_core::progress::{Progress, ProgressEvent};
+use lyrictime_core::sections::{self, Section};
+use lyrictime_core::validate::FastLine;
+use lyrictime_core::{audio, chunk, diarize, encoding, parse, vocabulary};
+
+use crate::cache::{self, TranscriptCache, WavCache};
+use crate::host::Host;
+use crate::{edits, ffmpeg_downloader, formats, library, longpath, model_downloader, models, settings};
+
+mod accel;
+mod benchmark;
+mod compare;
+mod coreml;
+mod diagnostics;
+mod failure;
+mod job;
+mod multilingual;
+mod openvino;
 mod process;
+mod quantize;
+mod regenerate;
+mod resync;
+
+pub use benchmark::{run_benchmark, BenchmarkResult};
+pub use compare::{compare_models, ModelComparison};
+pub use coreml::supported as coreml_supported;
+pub use openvino::supported as openvino_supported;
+pub use job::{last_job, retry_generation, Job, Stage};
+pub use quantize::quantize_model;
+pub use regenerate::regenerate_range;
+pub use resync::{resync_lrc, ResyncResult};
+pub use lyrictime_core::options::{
+  Backend, ChapterMode, Decoding, ExistingLyrics, GenerateOptions, MergeMode, OutputFormat, Profile, SectionMode,
+};
+pub use process::{
+  cancel, find_system_executable, probe_audio_streams, probe_chapters, responds, run_ffmpeg_cut, run_ytdlp, shutdown,
+  AudioStream, CancelToken, Chapter, RunContext, RunDir,
+};
 
 static IS_RUNNING: AtomicBool = AtomicBool::new(false);
 
-#[derive(Serialize, Clone)]
-#[serde(tag = "kind")]
-enum ProgressEvent {
-  #[serde(rename = "stage")]
-  Stage { stage: String, detail: Option<String> },
-
-  #[serde(rename = "log")]
-  Log { line: String },
-
-  #[serde(rename = "done")]
-  Done { outputPath: String },
+/// A generation (or benchmark, resync, ...) holds the single-flight guard.
+pub fn is_running() -> bool {
+  IS_RUNNING.load(Ordering::SeqCst)
 }
 
-fn emit(app: &AppHandle, evt: ProgressEvent) {
-  let _ = app.emit("lyric_progress", evt);
+fn emit(app: &Host, evt: ProgressEvent) {
+  app.report(evt);
 }
 
 struct RunningGuard;
+impl RunningGuard {
+  /// Claim the single-flight slot, held until the guard drops.
+  fn acquire() -> Result<Self, String> {
+    if IS_RUNNING.swap(true, Ordering::SeqCst) {
+      return Err("Generation already running".into());
+    }
+    Ok(RunningGuard)
+  }
+}
 impl Drop for RunningGuard {
   fn drop(&mut self) {
     IS_RUNNING.store(false, Ordering::SeqCst);
   }
 }
 
-fn whisper_bin_dir(app: &AppHandle) -> Result<PathBuf, String> {
+fn whisper_bin_dir(app: &Host) -> Result<PathBuf, String> {
   Ok(
-    app.path()
+    app
       .app_data_dir()
       .map_err(|e| format!("app_data_dir error: {e}"))?
       .join("bin"),
   )
 }
 
-async fn ensure_whisper_downloaded(app: &AppHandle) -> Result<(), String> {
+/// Download whisper and its runtime libraries unless one is configured or installed.
+pub async fn ensure_whisper_downloaded(app: &Host) -> Result<(), String> {
+  // A configured or already-installed whisper-cli makes the download unnecessary.
+  if settings::whisper_override(app).is_some() {
+    return Ok(());
+  }
+
   let bin_dir = whisper_bin_dir(app)?;
 
   #[cfg(windows)]
@@ -51,39 +95,42 @@ async fn ensure_whisper_downloaded(app: &AppHandle) -> Result<(), String> {
   #[cfg(not(windows))]
   let whisper_name = "whisper";
 
-  // NOTE: deps tag assets
-  let base = "https://github.com/evilduck1/LyricTime/releases/download/deps/";
-
   let whisper_path = bin_dir.join(whisper_name);
-  if !whisper_path.exists() {
-    let url = format!("{}{}", base, whisper_name);
-    download::download_with_progress(app, "deps", &url, &whisper_path, whisper_name).await?;
-    #[cfg(unix)]
-    {
-      use std::os::unix::fs::PermissionsExt;
-      let mut perms = std::fs::metadata(&whisper_path).map_err(|e| e.to_string())?.permissions();
-      perms.set_mode(0o755);
-      std::fs::set_permissions(&whisper_path, perms).map_err(|e| e.to_string())?;
-    }
+  if !whisper_path.exists() && find_system_executable("whisper", "--help").is_some() {
+    return Ok(());
   }
 
+  ensure_whisper_libs(app).await?;
+  // Checked once the runtime libs are in place, since whisper can't start without them.
+  let url = format!("{DEPS_URL}{whisper_name}");
+  ffmpeg_downloader::ensure_binary(app, &url, &whisper_path, whisper_name, "--help", &[]).await
+}
+
+// NOTE: deps tag assets
+const DEPS_URL: &str = "https://github.com/evilduck1/LyricTime/releases/download/deps/";
+
+/// Download whisper.cpp's runtime libraries into the bin dir. The downloaded whisper and
+/// whisper-quantize both load them from there.
+async fn ensure_whisper_libs(app: &Host) -> Result<(), String> {
   // Windows: Whisper needs its DLLs next to the executable.
   #[cfg(windows)]
   {
+    let bin_dir = whisper_bin_dir(app)?;
     let dlls = ["whisper.dll", "ggml.dll", "ggml-base.dll", "ggml-cpu.dll"];
     for name in dlls {
       let p = bin_dir.join(name);
       if p.exists() {
         continue;
       }
-      let url = format!("{}{}", base, name);
-      download::download_with_progress(app, "deps", &url, &p, name).await?;
+      let url = format!("{DEPS_URL}{name}");
+      crate::download::download_with_progress(app, "deps", &url, &p, name).await?;
     }
   }
 
   // macOS: Whisper needs dylibs next to the whisper executable.
   #[cfg(target_os = "macos")]
   {
+    let bin_dir = whisper_bin_dir(app)?;
     let dylibs = [
       // Required (as referenced by whisper)
       "libwhisper.1.dylib",
@@ -105,50 +152,84 @@ async fn ensure_whisper_downloaded(app: &AppHandle) -> Result<(), String> {
       if p.exists() {
         continue;
       }
-      let url = format!("{}{}", base, name);
-      download::download_with_progress(app, "deps", &url, &p, name).await?;
+      let url = format!("{DEPS_URL}{name}");
+      crate::download::download_with_progress(app, "deps", &url, &p, name).await?;
     }
   }
 
+  let _ = app;
   Ok(())
 }
 
-fn whisper_supports_direct(path: &PathBuf) -> bool {
-  match path.extension().and_then(|e| e.to_str()).map(|s| s.to_ascii_lowercase()) {
-    Some(ext) if matches!(ext.as_str(), "mp3" | "wav" | "flac" | "ogg") => true,
-    _ => false,
-  }
+/// Fastest `speed_up`; beyond it whisper misses noticeably more words.
+const MAX_SPEED_UP: f32 = 1.5;
+
+/// The `speed_up` factor in effect, if any.
+fn speed_up(options: &GenerateOptions) -> Option<f32> {
+  (options.speed_up > 1.0).then(|| options.speed_up.min(MAX_SPEED_UP))
 }
 
-pub async fn generate_lrc_next_to_audio(
-  app: AppHandle,
-  audio_path: &str,
-  model: &str,
-) -> Result<String, String> {
-  // single-flight guard (prevents double-run from StrictMode / double-clicks)
-  if IS_RUNNING.swap(true, Ordering::SeqCst) {
-    return Err("Generation already running".into());
+/// Add `filter` to the `-af` chain ffmpeg will use (the last one), or start one.
+fn add_audio_filter(args: &mut Vec<String>, filter: &str) {
+  match args.iter().rposition(|a| a == "-af").filter(|i| i + 1 < args.len()) {
+    Some(i) => args[i + 1] = format!("{},{filter}", args[i + 1]),
+    None => args.extend(["-af".to_string(), filter.to_string()]),
   }
-  let _guard = RunningGuard;
+}
 
-  let audio_path = PathBuf::from(audio_path);
-  if !audio_path.exists() {
-    return Err("Audio file does not exist".into());
+/// `-map`/`-af` ffmpeg output args for the `audio_stream` / `center_channel_only` options.
+fn stream_selection_args(
+  app: &Host,
+  streams: &[process::AudioStream],
+  options: &GenerateOptions,
+) -> Result<Vec<String>, String> {
+  let mut args = Vec::new();
+
+  let stream = match options.audio_stream {
+    Some(i) => {
+      let s = streams
+        .get(i)
+        .ok_or_else(|| format!("Audio stream {i} not found ({} available)", streams.len()))?;
+      args.extend(["-map".to_string(), format!("0:a:{i}")]);
+      s
+    }
+    None => &streams[0],
+  };
+
+  if options.center_channel_only {
+    if stream.has_center() {
+      args.extend(["-af".to_string(), "pan=mono|c0=FC".to_string()]);
+    } else {
+      emit(
+        app,
+        ProgressEvent::Log {
+          line: format!("Stream {} has no center channel; using the full mix", stream.index),
+        },
+      );
+    }
   }
 
-  // Output path next to audio file
-  let out_path = audio_path.with_extension("lrc");
+  Ok(args)
+}
 
-  emit(
-    &app,
-    ProgressEvent::Stage {
-      stage: "Preparing".into(),
-      detail: Some("Locating resources".into()),
-    },
-  );
+/// Stream selection plus the user's extra ffmpeg args. Their `-af` filters join the
+/// chain rather than replacing the center-channel one.
+fn ffmpeg_args(app: &Host, streams: &[process::AudioStream], options: &GenerateOptions) -> Result<Vec<String>, String> {
+  let mut args = stream_selection_args(app, streams, options)?;
+  let extra = process::validate_extra_args(&options.extra_ffmpeg_args, process::FFMPEG_RESERVED_ARGS, "ffmpeg")?;
+  let mut extra = extra.into_iter();
+  while let Some(arg) = extra.next() {
+    let filter = if arg == "-af" || arg == "-filter:a" { extra.next() } else { None };
+    match filter {
+      Some(filter) => add_audio_filter(&mut args, &filter),
+      None => args.push(arg),
+    }
+  }
+  Ok(args)
+}
 
+fn resource_dirs(app: &Host) -> Result<(PathBuf, Option<PathBuf>), String> {
   let resources_dir = app
-    .path()
     .resource_dir()
     .map_err(|e| format!("resource_dir error: {e}"))?;
 
@@ -170,564 +251,1257 @@ pub async fn generate_lrc_next_to_audio(
     None
   });
 
-  let platform = if cfg!(target_os = "macos") {
-    "macos"
+  Ok((resources_dir, fallback_resources_dir))
+}
+
+fn current_platform() -> Result<&'static str, String> {
+  if cfg!(target_os = "macos") {
+    Ok("macos")
   } else if cfg!(target_os = "windows") {
-    "windows"
+    Ok("windows")
+  } else if cfg!(target_os = "linux") {
+    Ok("linux")
   } else {
-    return Err("Unsupported OS".into());
-  };
-
-  // Ensure runtime deps exist (download-on-first-use). This makes release builds work
-// even when nothing is bundled and PATH is empty.
-#[cfg(windows)]
-let (ffmpeg_url, ffprobe_url) = (
-  "https://github.com/evilduck1/LyricTime/releases/download/deps/ffmpeg.exe".to_string(),
-  "https://github.com/evilduck1/LyricTime/releases/download/deps/ffprobe.exe".to_string(),
-);
-
-#[cfg(not(windows))]
-let (ffmpeg_url, ffprobe_url) = (
-  "https://github.com/evilduck1/LyricTime/releases/download/deps/ffmpeg".to_string(),
-  "https://github.com/evilduck1/LyricTime/releases/download/deps/ffprobe".to_string(),
-);
+    Err("Unsupported OS".into())
+  }
+}
 
-let ffmpeg_paths = ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await?;
-let ffmpeg = PathBuf::from(ffmpeg_paths.ffmpeg_path);
+/// Whether a whisper executable is available without downloading one.
+pub fn whisper_installed(app: &Host) -> bool {
+  let found = resource_dirs(app).and_then(|(resources_dir, fallback)| {
+    locate_whisper(app, &resources_dir, fallback.as_ref(), current_platform()?)
+  });
+  found.is_ok() || find_system_executable("whisper", "--help").is_some()
+}
 
-// Ensure whisper models exist (downloaded into app data /models)
-let small_url = "https://github.com/evilduck1/LyricTime/releases/download/models/ggml-small.bin".to_string();
-let medium_url = "https://github.com/evilduck1/LyricTime/releases/download/models/ggml-medium.bin".to_string();
-let _ = model_downloader::ensure_models(app.clone(), small_url, medium_url).await?;
+/// ffmpeg, ffprobe and whisper for a run, and where bundled resources live.
+struct Toolchain {
+  resources_dir: PathBuf,
+  fallback_resources_dir: Option<PathBuf>,
+  ffmpeg: PathBuf,
+  ffprobe: PathBuf,
+  whisper: PathBuf,
+}
 
-// Ensure whisper + runtime libs exist (download-on-first-use)
-  ensure_whisper_downloaded(&app).await?;
+impl Toolchain {
+  // Download-on-first-use, so release builds work even when nothing is bundled and PATH is empty.
+  async fn prepare(app: &Host) -> Result<Self, String> {
+    let (resources_dir, fallback_resources_dir) = resource_dirs(app)?;
+    let platform = current_platform()?;
+    let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+    let paths = ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await?;
+    ensure_whisper_downloaded(app).await?;
+    let whisper = locate_whisper(app, &resources_dir, fallback_resources_dir.as_ref(), platform)?;
+    Ok(Toolchain {
+      resources_dir,
+      fallback_resources_dir,
+      ffmpeg: PathBuf::from(paths.ffmpeg_path),
+      ffprobe: PathBuf::from(paths.ffprobe_path),
+      whisper,
+    })
+  }
+}
 
 // Whisper executable: prefer app data (downloaded) then bundled resources.
 // NOTE: If you aren't bundling whisper, you'll need to upload it as a Release asset and download it like ffmpeg.
-let resources_bin_dir = resources_dir.join("bin").join(platform);
-let app_bin_dir = app
-  .path()
-  .app_data_dir()
-  .map_err(|e| format!("app_data_dir error: {e}"))?
-  .join("bin");
-
-// IMPORTANT: Windows must use the .exe name. Otherwise we'll download/launch the macOS/Linux asset
-// and Windows will fail to start the process.
-let whisper_exec_name = if cfg!(windows) { "whisper.exe" } else { "whisper" };
-
-let whisper = process::pick_executable_multi(
-  &app_bin_dir,
-  &resources_bin_dir,
-  fallback_resources_dir.as_ref(),
-  platform,
-  whisper_exec_name,
-)?;
-
-  // Temp workspace (unique per run)
-  let run_id = format!(
-    "{}",
-    std::time::SystemTime::now()
-      .duration_since(std::time::UNIX_EPOCH)
-      .map_err(|e| format!("time error: {e}"))?
-      .as_millis()
-  );
+fn locate_whisper(
+  app: &Host,
+  resources_dir: &Path,
+  fallback_resources_dir: Option<&PathBuf>,
+  platform: &str,
+) -> Result<PathBuf, String> {
+  let resources_bin_dir = resources_dir.join("bin").join(platform);
+  let app_bin_dir = app
+    .app_data_dir()
+    .map_err(|e| format!("app_data_dir error: {e}"))?
+    .join("bin");
+
+  // IMPORTANT: Windows must use the .exe name. Otherwise we'll download/launch the macOS/Linux asset
+  // and Windows will fail to start the process.
+  let whisper_exec_name = if cfg!(windows) { "whisper.exe" } else { "whisper" };
+
+  process::pick_executable_multi(
+    settings::whisper_override(app),
+    &app_bin_dir,
+    &resources_bin_dir,
+    fallback_resources_dir,
+    platform,
+    whisper_exec_name,
+  )
+}
 
-  let tmp_dir = std::env::temp_dir().join("lyrictime").join(run_id);
-  std::fs::create_dir_all(&tmp_dir).map_err(|e| format!("temp dir create failed: {e}"))?;
+/// Whether whisper's own decoders (WAV PCM, MP3, FLAC, Ogg Vorbis) read this input,
+/// judged by the detected format: an AAC stream named .mp3 still goes through ffmpeg,
+/// an extensionless MP3 doesn't need to.
+fn whisper_supports_direct(format: Option<&formats::MediaFormat>, streams: &[process::AudioStream]) -> bool {
+  streams.len() == 1 && format.is_some_and(|f| f.direct)
+}
 
-  // Choose input for whisper
-  let direct = whisper_supports_direct(&audio_path);
-  let wav_path = tmp_dir.join("input.wav");
+pub async fn generate_lrc_next_to_audio(
+  app: Host,
+  audio_path: &str,
+  model: &str,
+  options: GenerateOptions,
+) -> Result<String, String> {
+  let out_path = library::output_path(&app, Path::new(audio_path), "lrc");
+  generate_lrc(app, audio_path, &out_path, model, options).await
+}
 
-  let whisper_input = if direct {
-    emit(
-      &app,
-      ProgressEvent::Stage {
-        stage: "Preparing".into(),
-        detail: Some("Input format supported by whisper (skipping ffmpeg)".into()),
-      },
-    );
-    audio_path.clone()
-  } else {
-    emit(
-      &app,
-      ProgressEvent::Stage {
-        stage: "Converting".into(),
-        detail: Some("Unsupported format → ffmpeg → 16k mono WAV".into()),
-      },
-    );
-    process::run_ffmpeg_to_wav(&app, &ffmpeg, &audio_path, &wav_path)?;
-    wav_path.clone()
+/// Generate lyrics for `audio_path` into `out_path`; the extension is replaced by the
+/// output format's.
+pub async fn generate_lrc(
+  app: Host,
+  audio_path: &str,
+  out_path: &Path,
+  model: &str,
+  options: GenerateOptions,
+) -> Result<String, String> {
+  // single-flight guard (prevents double-run from StrictMode / double-clicks)
+  let _guard = RunningGuard::acquire()?;
+
+  let audio_path = longpath::extended(Path::new(audio_path));
+  if !audio_path.exists() {
+    return Err("Audio file does not exist".into());
+  }
+
+  // These work on the converted audio, which a speed-up leaves out of step with the song.
+  let mut options = options;
+  if speed_up(&options).is_some() {
+    for (skipped, on) in [
+      ("Onset refinement", &mut options.refine_onsets),
+      ("Beat snapping", &mut options.snap_to_beats),
+      ("Multilingual re-transcription", &mut options.multilingual),
+    ] {
+      if std::mem::take(on) {
+        emit(&app, ProgressEvent::Log { line: format!("{skipped} doesn't work on sped-up audio; skipped") });
+      }
+    }
+  }
+
+  let out_path = library::writable_destination(&app, &longpath::extended(&out_path.with_extension("lrc")))?;
+  let mut generation = Generation::prepare(app, audio_path, out_path, model, options).await?;
+  let cancel = generation.params.run.cancel.clone();
+  cancel.check()?;
+  let input = generation.convert().await?;
+  cancel.check()?;
+  let Some(model_path) = generation.transcribe(&input).await? else {
+    return generation.finish_instrumental();
   };
+  cancel.check()?;
+  let decisions = generation.merge(&model_path).await?;
+  cancel.check()?;
+  generation.finish(&input, &model_path, &decisions).await
+}
+
+/// One generation, run stage by stage. Each stage saves its result to `job`, so
+/// `retry_generation` can pick up from Merge on without transcribing again.
+struct Generation {
+  app: Host,
+  model: String,
+  options: GenerateOptions,
+  audio_path: PathBuf,
+  out_path: PathBuf,
+  tools: Toolchain,
+  streams: Vec<process::AudioStream>,
+  chapters: Vec<(i64, String)>,
+  params: process::WhisperParams,
+  ffmpeg_args: Vec<String>,
+  wav_cache: Option<WavCache>,
+  cache: Option<TranscriptCache>,
+  /// After any speed-up, for the per-pass ETA.
+  audio_ms: Option<u64>,
+  tmp_dir: process::RunDir,
+  run_id: String,
+  job: job::Job,
+}
 
-  // HYBRID+ (invisible):
-  // - When model == "hybrid", run small + (optional) medium.
-  // - Merge is chant-aware and timestamps are normalized.
-  let use_hybrid = model.eq_ignore_ascii_case("hybrid");
+impl Generation {
+  /// Probe: tools, models and the input's streams, plus everything every pass shares.
+  async fn prepare(
+    app: Host,
+    audio_path: PathBuf,
+    out_path: PathBuf,
+    model: &str,
+    options: GenerateOptions,
+  ) -> Result<Self, String> {
+    let run = process::RunContext::new(&app, &options);
+    let mut job = job::Job::new(&audio_path, &out_path, model, &options);
+    job.enter(&app, Stage::Probe);
 
-  if use_hybrid {
     emit(
       &app,
       ProgressEvent::Stage {
-        stage: "Transcribing".into(),
-        detail: Some("Hybrid+: small pass".into()),
+        stage: "Preparing".into(),
+        detail: Some("Locating resources".into()),
       },
     );
 
-    let small_model_path = process::resolve_model_path_with_fallback(
-      &app,
-      &resources_dir,
-      fallback_resources_dir.as_ref(),
-      "small",
-    )?;
+    let tools = Toolchain::prepare(&app).await?;
 
-    let out_small_prefix = tmp_dir.join("out_small");
-    process::run_whisper_lrc(&app, &whisper, &small_model_path, &whisper_input, &out_small_prefix)?;
+    // Music videos / concert recordings: fine as long as there's an audio track to extract.
+    let streams = process::probe_audio_streams(&tools.ffprobe, &audio_path)?;
+    if streams.is_empty() {
+      return Err(format!("No audio track found in {}", audio_path.display()));
+    }
+
+    // Per-chapter splitting happens before this; here chapters only become markers.
+    let chapters: Vec<(i64, String)> = if options.chapters == ChapterMode::Markers {
+      process::probe_chapters(&tools.ffprobe, &audio_path)?
+        .into_iter()
+        .map(|c| (c.start_ms as i64, c.title))
+        .collect()
+    } else {
+      Vec::new()
+    };
 
-    let small_lrc_path = out_small_prefix.with_extension("lrc");
-    if !small_lrc_path.exists() {
-      return Err("Whisper (small) did not produce LRC".into());
+    // Fetch only the models this run uses (hybrid's two passes); the rest are picked in the
+    // setup wizard or downloaded on first use, the OOM fallback once it's needed.
+    let needed: Vec<&str> = if model.eq_ignore_ascii_case("hybrid") { vec!["small", "medium"] } else { vec![model] };
+    for m in needed {
+      if model_downloader::find_variant(m).is_some() {
+        model_downloader::ensure_model(&app, m).await?;
+      }
     }
 
-    let raw_small = std::fs::read_to_string(&small_lrc_path)
-      .map_err(|e| format!("Failed reading small LRC: {e}"))?;
-    let small_clean = clean_lrc(&raw_small);
+    // VAD is best-effort: without the model we still transcribe, just without skipping non-speech.
+    let vad_model = if options.vad {
+      let vad_url = format!(
+        "https://github.com/evilduck1/LyricTime/releases/download/models/{}",
+        model_downloader::VAD_MODEL_FILE
+      );
+      match model_downloader::ensure_model_file(&app, model_downloader::VAD_MODEL_FILE, vad_url).await {
+        Ok(p) => Some(p),
+        Err(e) => {
+          emit(
+            &app,
+            ProgressEvent::Log {
+              line: format!("VAD model unavailable, continuing without VAD: {e}"),
+            },
+          );
+          None
+        }
+      }
+    } else {
+      None
+    };
 
-    emit(
-      &app,
-      ProgressEvent::Stage {
-        stage: "Transcribing".into(),
-        detail: Some("Hybrid+: medium pass".into()),
-      },
+    let params = process::WhisperParams {
+      vad_model,
+      speed_up: speed_up(&options),
+      ..whisper_params(&app, &tools.ffprobe, &audio_path, &options, run).await?
+    };
+
+    diagnostics::report(&app, &params.run, &tools.whisper, &tools.ffmpeg);
+    if let Some(c) = &params.coreml {
+      emit(&app, ProgressEvent::Log { line: format!("Using the Core ML build: {}", c.whisper.display()) });
+    }
+    if let Some(index) = params.gpu_index.filter(|_| !params.no_gpu) {
+      let line = match crate::capabilities::list_gpus().into_iter().find(|g| g.index == index) {
+        Some(g) => format!("Using GPU {index}: {}", g.name),
+        None => format!("GPU {index} not found; CUDA will see no card and whisper runs on the CPU"),
+      };
+      emit(&app, ProgressEvent::Log { line });
+    }
+    if let Some(o) = &params.openvino {
+      emit(
+        &app,
+        ProgressEvent::Log { line: format!("Using the OpenVINO build on {}: {}", o.device, o.whisper.display()) },
+      );
+    }
+
+    // Temp workspace (unique per run)
+    let run_id = format!(
+      "{}",
+      std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_err(|e| format!("time error: {e}"))?
+        .as_millis()
     );
 
-    // Medium is optional. If it's not installed, silently fall back to small-only.
-    let medium_model_path = match process::resolve_model_path_with_fallback(
-      &app,
-      &resources_dir,
-      fallback_resources_dir.as_ref(),
-      "medium",
-    ) {
-      Ok(p) => Some(p),
-      Err(_) => None,
+    let tmp_dir = process::RunDir::create(&run_id)?;
+
+    let mut ffmpeg_args = ffmpeg_args(&app, &streams, &options)?;
+    if let Some(speed) = params.speed_up {
+      add_audio_filter(&mut ffmpeg_args, &format!("atempo={speed}"));
+      emit(&app, ProgressEvent::Log { line: format!("Transcribing at {speed}× speed (draft)") });
+    }
+
+    let audio_hash = if options.cache || options.wav_cache_mb > 0 {
+      Some(cache::hash_file(&audio_path)?)
+    } else {
+      None
+    };
+    let wav_cache = match &audio_hash {
+      Some(hash) if options.wav_cache_mb > 0 => Some(WavCache::new(&app, hash, &ffmpeg_args, options.wav_cache_mb)?),
+      _ => None,
     };
 
-    let merged = if let Some(medium_model_path) = medium_model_path {
-      let out_medium_prefix = tmp_dir.join("out_medium");
-      process::run_whisper_lrc(
-        &app,
-        &whisper,
-        &medium_model_path,
-        &whisper_input,
-        &out_medium_prefix,
-      )?;
-
-      let medium_lrc_path = out_medium_prefix.with_extension("lrc");
-      if !medium_lrc_path.exists() {
-        normalize_lrc_timestamps(&small_clean, 250)
-      } else {
-        let raw_medium = std::fs::read_to_string(&medium_lrc_path)
-          .map_err(|e| format!("Failed reading medium LRC: {e}"))?;
-        let medium_clean = clean_lrc(&raw_medium);
+    // Same audio, model and settings as an earlier run: reuse its cleaned transcript.
+    let cache = if let Some(hash) = audio_hash.as_deref().filter(|_| options.cache) {
+      let settings = format!(
+        "{:?}",
+        (
+          &params.language,
+          &params.prompt,
+          params.decoding,
+          &params.extra_args,
+          params.vad_model.is_some(),
+          options.profile,
+          options.chunk_seconds,
+          options.chunk_overlap_seconds,
+          &ffmpeg_args,
+        )
+      );
+      Some(TranscriptCache::new(&app, hash, &settings)?)
+    } else {
+      None
+    };
 
-        emit(
-          &app,
-          ProgressEvent::Stage {
-            stage: "Merging".into(),
-            detail: Some("Hybrid+: chant-aware merge + timestamp normalization".into()),
-          },
-        );
+    // Benchmarked realtime factors turn into a rough ETA per pass.
+    let audio_ms = process::probe_duration_ms(&tools.ffprobe, &audio_path)
+      .ok()
+      .map(|ms| (ms as f64 / f64::from(params.speed_up.unwrap_or(1.0))) as u64);
+
+    Ok(Generation {
+      app,
+      model: model.to_string(),
+      options,
+      audio_path,
+      out_path,
+      tools,
+      streams,
+      chapters,
+      params,
+      ffmpeg_args,
+      wav_cache,
+      cache,
+      audio_ms,
+      tmp_dir,
+      run_id,
+      job,
+    })
+  }
 
-        merge_hybrid_plus(&small_clean, &medium_clean)
+  /// Convert: chunks for long inputs, the file itself when whisper reads it, or ffmpeg's
+  /// output (piped or a WAV).
+  async fn convert(&mut self) -> Result<WhisperInput, String> {
+    let (app, options, run) = (&self.app, &self.options, &self.params.run);
+    let (ffmpeg, ffprobe, audio_path) = (&self.tools.ffmpeg, &self.tools.ffprobe, &self.audio_path);
+
+    // Long inputs: cut into overlapping chunks once, shared by every whisper pass.
+    let overlap_ms = options.chunk_overlap_seconds * 1000;
+    let plan = if options.chunk_seconds > 0 {
+      match process::probe_duration_ms(ffprobe, audio_path) {
+        Ok(duration_ms) => chunk::plan_chunks(duration_ms, options.chunk_seconds * 1000, overlap_ms),
+        // Without a length there's nothing to cut by; transcribe it in one pass instead.
+        Err(e) => {
+          emit(app, ProgressEvent::Log { line: format!("Couldn't read the duration ({e}); not chunking") });
+          Vec::new()
+        }
       }
     } else {
-      normalize_lrc_timestamps(&small_clean, 250)
+      Vec::new()
     };
 
+    let container = process::probe_container(ffprobe, audio_path)?;
+    let codec = self.streams[0].codec.as_deref().unwrap_or("unknown codec");
+    let format = formats::detect(&container, codec, &self.audio_path);
     emit(
-      &app,
-      ProgressEvent::Stage {
-        stage: "Writing".into(),
-        detail: Some("Writing .lrc next to audio".into()),
+      app,
+      ProgressEvent::Log {
+        line: match format {
+          Some(f) => format!("Detected {} ({codec} in {container})", f.name),
+          None => format!("Detected {codec} in {container}, an untested format; converting with ffmpeg"),
+        },
       },
     );
+    // Stream selection, speed-up and extra ffmpeg args only apply if the input goes through ffmpeg.
+    let direct = whisper_supports_direct(format, &self.streams) && self.ffmpeg_args.is_empty();
+
+    // Piping saves a temp WAV unless a later pass writes one anyway or the cache has it.
+    let needs_wav = options.refine_onsets || options.snap_to_beats || options.multilingual;
+    let pipe = options.pipe_conversion && !needs_wav && !self.wav_cache.as_ref().is_some_and(WavCache::is_cached);
+    self.job.enter(app, Stage::Convert);
+    Ok(if !plan.is_empty() {
+      emit(
+        app,
+        ProgressEvent::Stage {
+          stage: "Converting".into(),
+          detail: Some(format!("Long input → ffmpeg → {} overlapping chunks", plan.len())),
+        },
+      );
+
+      let mut parts = Vec::with_capacity(plan.len());
+      for (i, c) in plan.into_iter().enumerate() {
+        let chunk_wav = self.tmp_dir.join(format!("chunk_{i}.wav"));
+        let args = &self.ffmpeg_args;
+        process::run_ffmpeg_chunk_to_wav(app, run, ffmpeg, audio_path, c.start_ms, c.len_ms, &chunk_wav, args).await?;
+        parts.push((c, chunk_wav));
+      }
+      WhisperInput::Chunked { parts, overlap_ms }
+    } else if direct {
+      emit(
+        app,
+        ProgressEvent::Stage {
+          stage: "Preparing".into(),
+          detail: Some("Input format supported by whisper (skipping ffmpeg)".into()),
+        },
+      );
+      WhisperInput::Single(audio_path.clone())
+    } else if pipe {
+      emit(
+        app,
+        ProgressEvent::Stage {
+          stage: "Converting".into(),
+          detail: Some("Unsupported format → ffmpeg → whisper (piped, no temp WAV)".into()),
+        },
+      );
+      WhisperInput::Piped(process::FfmpegPipe {
+        ffmpeg: ffmpeg.clone(),
+        input: audio_path.clone(),
+        extra_args: self.ffmpeg_args.clone(),
+      })
+    } else {
+      emit(
+        app,
+        ProgressEvent::Stage {
+          stage: "Converting".into(),
+          detail: Some("Unsupported format → ffmpeg → 16k mono WAV".into()),
+        },
+      );
+      let wav = converted_wav(app, run, ffmpeg, audio_path, &self.ffmpeg_args, &self.tmp_dir, self.wav_cache.as_ref());
+      WhisperInput::Single(wav.await?)
+    })
+  }
+
+  /// Transcribe: every whisper pass, each saved to the job under the model that actually
+  /// ran. Returns the model the transcript ends up with (for translation and multilingual
+  /// passes), or `None` for an instrumental.
+  async fn transcribe(&mut self, input: &WhisperInput) -> Result<Option<PathBuf>, String> {
+    self.job.enter(&self.app, Stage::Transcribe);
+    // HYBRID+ (invisible):
+    // - When model == "hybrid", run small + (optional) medium.
+    // - Merge is chant-aware and timestamps are normalized.
+    if self.model.eq_ignore_ascii_case("hybrid") {
+      self.transcribe_hybrid(input).await
+    } else {
+      self.transcribe_single(input).await
+    }
+  }
 
-    std::fs::write(&out_path, merged).map_err(|e| format!("Failed writing LRC: {e}"))?;
+  async fn transcribe_hybrid(&mut self, input: &WhisperInput) -> Result<Option<PathBuf>, String> {
+    self.stage("Transcribing", self.pass_detail("Hybrid+: small pass", "small"));
+    let small_model_path = self.resolve("small")?;
+    let small_clean = self.pass(&small_model_path, input, "out_small").await?;
+    let small_clean = small_clean.ok_or("Whisper (small) did not produce LRC")?;
+    self.job.add_pass(&self.app, "small", &small_clean);
 
-    emit(
-      &app,
-      ProgressEvent::Done {
-        outputPath: out_path.display().to_string(),
-      },
-    );
+    // No vocals in the small pass: skip medium entirely rather than decode silence twice.
+    if is_instrumental(&small_clean) {
+      return Ok(None);
+    }
+
+    self.stage("Transcribing", self.pass_detail("Hybrid+: medium pass", "medium"));
+    // Medium is optional. If it's not installed, silently fall back to small-only.
+    let Ok(medium_model_path) = self.resolve("medium") else {
+      return Ok(Some(small_model_path));
+    };
+    match self.pass(&medium_model_path, input, "out_medium").await {
+      // Small already finished, so a medium that doesn't fit just means small-only output.
+      Err(e) if process::is_out_of_memory(&e) && self.options.oom_fallback_model.is_some() => {
+        self.stage("Downgrading", "Medium ran out of memory; using the small pass only".into());
+        Ok(Some(small_model_path))
+      }
+      result => {
+        if let Some(medium_clean) = result? {
+          self.job.add_pass(&self.app, "medium", &medium_clean);
+        }
+        Ok(Some(medium_model_path))
+      }
+    }
+  }
 
-    return Ok(out_path.display().to_string());
+  // Single pass using the requested model ("small" or "medium"), or the OOM fallback.
+  async fn transcribe_single(&mut self, input: &WhisperInput) -> Result<Option<PathBuf>, String> {
+    self.stage("Transcribing", self.pass_detail("Running whisper", &self.model));
+    let mut model = self.model.clone();
+    let mut model_path = self.resolve(&model)?;
+    let fallback = self.options.oom_fallback_model.clone().filter(|f| !f.eq_ignore_ascii_case(&model));
+    let cleaned = match (self.pass(&model_path, input, "out").await, fallback) {
+      (Err(e), Some(fallback)) if process::is_out_of_memory(&e) => {
+        self.stage("Downgrading", format!("{model} ran out of memory; retrying with {fallback}"));
+        if model_downloader::find_variant(&fallback).is_some() {
+          model_downloader::ensure_model(&self.app, &fallback).await?;
+        }
+        model_path = self.resolve(&fallback)?;
+        model = fallback;
+        self.pass(&model_path, input, "out").await?
+      }
+      (result, _) => result?,
+    }
+    .ok_or_else(|| {
+      format!(
+        "Whisper did not produce an .lrc file at {}",
+        self.tmp_dir.join("out").with_extension("lrc").display()
+      )
+    })?;
+    self.job.add_pass(&self.app, &model, &cleaned);
+
+    if is_instrumental(&cleaned) {
+      return Ok(None);
+    }
+    Ok(Some(model_path))
   }
 
-  // NON-HYBRID: single pass using requested model ("small" or "medium")
-  emit(
-    &app,
-    ProgressEvent::Stage {
-      stage: "Transcribing".into(),
-      detail: Some("Running whisper".into()),
-    },
-  );
+  /// Merge: the passes into one transcript, then the multilingual re-transcription.
+  async fn merge(&mut self, model_path: &Path) -> Result<Vec<MergeDecision>, String> {
+    self.job.enter(&self.app, Stage::Merge);
+    if self.job.passes.len() == 2 {
+      let (mode, how) = match self.options.merge {
+        MergeMode::PreferMedium => ("Prefer medium", "medium-first merge"),
+        MergeMode::HybridPlus => ("Hybrid+", "chant-aware merge"),
+        MergeMode::Ensemble => ("Ensemble", "ensemble merge"),
+      };
+      self.stage("Merging", format!("{mode}: {how} + timestamp normalization"));
+    }
+    let (merged, decisions) = self.job.merge_passes(&self.options)?;
+
+    self.stage("Writing", "Writing .lrc next to audio".into());
+    let merged = if self.options.multilingual {
+      let run = multilingual::Run {
+        app: &self.app,
+        ffmpeg: &self.tools.ffmpeg,
+        whisper: &self.tools.whisper,
+        model_path,
+        audio_path: &self.audio_path,
+        ffmpeg_args: &self.ffmpeg_args,
+        tmp_dir: &self.tmp_dir,
+        wav_cache: self.wav_cache.as_ref(),
+        params: &self.params,
+        profile: self.options.profile,
+      };
+      multilingual::relanguage(&run, merged).await?
+    } else {
+      merged
+    };
+    self.job.merged = Some(merged);
+    Ok(decisions)
+  }
+
+  /// Clean and Write: the audio analysis passes, post-processing and the output files.
+  async fn finish(
+    mut self,
+    input: &WhisperInput,
+    model_path: &Path,
+    decisions: &[MergeDecision],
+  ) -> Result<String, String> {
+    let (app, options) = (&self.app, &self.options);
+    let mut analysis = analyze(
+      app,
+      &self.params.run,
+      &self.tools.ffmpeg,
+      &self.tools.whisper,
+      &self.audio_path,
+      &self.ffmpeg_args,
+      &self.tmp_dir,
+      self.wav_cache.as_ref(),
+      input,
+      options,
+    )
+    .await?;
+    analysis.chapters = std::mem::take(&mut self.chapters);
+    if options.bilingual {
+      let whisper = &self.tools.whisper;
+      let translation = translate(app, whisper, model_path, input, &self.tmp_dir, &self.params, options.profile).await?;
+      analysis.translation = Some(translation);
+    }
+    let raw_outputs = keep_raw_outputs(app, options, &self.tmp_dir, &self.run_id);
+    let merged = self.job.merged.clone().unwrap_or_default();
+    let written = write_output(app, &self.out_path, &merged, options, analysis, raw_outputs, &mut self.job)?;
+    if options.merge_log && !decisions.is_empty() {
+      write_merge_log(app, Path::new(&written), decisions);
+    }
+    Ok(written)
+  }
 
-  let model_path =
-    process::resolve_model_path_with_fallback(&app, &resources_dir, fallback_resources_dir.as_ref(), model)?;
+  fn finish_instrumental(mut self) -> Result<String, String> {
+    let raw_outputs = keep_raw_outputs(&self.app, &self.options, &self.tmp_dir, &self.run_id);
+    write_instrumental(&self.app, &self.out_path, &self.options, raw_outputs, &mut self.job)
+  }
 
-  let out_prefix = tmp_dir.join("out");
-  process::run_whisper_lrc(&app, &whisper, &model_path, &whisper_input, &out_prefix)?;
+  fn stage(&self, stage: &str, detail: String) {
+    emit(&self.app, ProgressEvent::Stage { stage: stage.into(), detail: Some(detail) });
+  }
 
-  emit(
-    &app,
-    ProgressEvent::Stage {
-      stage: "Writing".into(),
-      detail: Some("Copying .lrc next to audio".into()),
-    },
-  );
+  fn pass_detail(&self, detail: &str, model: &str) -> String {
+    match self.audio_ms.and_then(|ms| benchmark::eta(&self.app, &[model], ms)) {
+      Some(eta) => format!("{detail} ({eta})"),
+      None => detail.to_string(),
+    }
+  }
 
-  let produced_lrc = out_prefix.with_extension("lrc");
-  if !produced_lrc.exists() {
-    return Err(format!(
-      "Whisper did not produce an .lrc file at {}",
-      produced_lrc.display()
-    ));
+  fn resolve(&self, model: &str) -> Result<PathBuf, String> {
+    resolve_model(&self.app, &self.tools.resources_dir, self.tools.fallback_resources_dir.as_ref(), model, &self.params)
   }
 
-  let raw_lrc = std::fs::read_to_string(&produced_lrc)
-    .map_err(|e| format!("Failed reading produced LRC: {e}"))?;
+  async fn pass(&self, model_path: &Path, input: &WhisperInput, prefix: &str) -> Result<Option<String>, String> {
+    let out_prefix = self.tmp_dir.join(prefix);
+    transcribe_cached(
+      self.cache.as_ref(),
+      &self.app,
+      &self.tools.whisper,
+      model_path,
+      input,
+      &out_prefix,
+      &self.params,
+      self.options.profile,
+    )
+    .await
+  }
+}
 
-  let cleaned = clean_lrc(&raw_lrc);
+/// 16k mono WAV of the whole input: this run's, the WAV cache's, or freshly converted.
+async fn converted_wav(
+  app: &Host,
+  run: &process::RunContext,
+  ffmpeg: &Path,
+  audio_path: &Path,
+  ffmpeg_args: &[String],
+  tmp_dir: &Path,
+  wav_cache: Option<&WavCache>,
+) -> Result<PathBuf, String> {
+  let wav_path = tmp_dir.join("input.wav");
+  if wav_path.exists() {
+    return Ok(wav_path);
+  }
 
-  std::fs::write(&out_path, cleaned)
-    .map_err(|e| format!("Failed writing cleaned LRC: {e}"))?;
+  let Some(wav_cache) = wav_cache else {
+    process::run_ffmpeg_to_wav(app, run, ffmpeg, audio_path, &wav_path, ffmpeg_args).await?;
+    return Ok(wav_path);
+  };
+  let (wav, hit) = wav_cache
+    .get_or_convert(|out| async move { process::run_ffmpeg_to_wav(app, run, ffmpeg, audio_path, &out, ffmpeg_args).await })
+    .await?;
+  if hit {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: "Using cached WAV conversion".into(),
+      },
+    );
+  }
+  Ok(wav)
+}
+
+/// Side results of the optional analysis passes, consumed by write_output.
+#[derive(Default)]
+struct Analysis {
+  /// 16k mono WAV of the whole input (onset refinement).
+  wav: Option<PathBuf>,
+  /// Speaker changes as `(start_ms, speaker)` (diarization).
+  speaker_turns: Option<Vec<(i64, usize)>>,
+  /// English translation pass (bilingual output).
+  translation: Option<Vec<LrcLine>>,
+  /// Chapter starts as `(start_ms, title)`.
+  chapters: Vec<(i64, String)>,
+}
 
+/// Whisper's built-in translate task (`-tr`, to English) over the same input.
+async fn translate(
+  app: &Host,
+  whisper: &Path,
+  model_path: &Path,
+  input: &WhisperInput,
+  tmp_dir: &Path,
+  params: &process::WhisperParams,
+  profile: Profile,
+) -> Result<Vec<LrcLine>, String> {
   emit(
-    &app,
-    ProgressEvent::Done {
-      outputPath: out_path.display().to_string(),
+    app,
+    ProgressEvent::Stage {
+      stage: "Translating".into(),
+      detail: Some("Whisper translate pass for bilingual output".into()),
     },
   );
 
-  Ok(out_path.display().to_string())
+  let params = process::WhisperParams {
+    translate: true,
+    ..params.clone()
+  };
+  let cleaned = transcribe(app, whisper, model_path, input, &tmp_dir.join("out_translate"), &params, profile)
+    .await?
+    .unwrap_or_default();
+  Ok(parse_lrc(&cleaned))
 }
 
-/* -------------------- Hybrid+ merge helpers -------------------- */
+#[allow(clippy::too_many_arguments)]
+async fn analyze(
+  app: &Host,
+  run: &process::RunContext,
+  ffmpeg: &Path,
+  whisper: &Path,
+  audio_path: &Path,
+  ffmpeg_args: &[String],
+  tmp_dir: &Path,
+  wav_cache: Option<&WavCache>,
+  whisper_input: &WhisperInput,
+  options: &GenerateOptions,
+) -> Result<Analysis, String> {
+  let mut analysis = Analysis::default();
+
+  // Reuses the conversion step's WAV, if it ran.
+  if options.refine_onsets || options.snap_to_beats {
+    analysis.wav = Some(converted_wav(app, run, ffmpeg, audio_path, ffmpeg_args, tmp_dir, wav_cache).await?);
+  }
 
-#[derive(Clone, Debug)]
-struct LrcLine {
-  ms: i64,
-  text: String,
-}
+  if options.diarize {
+    emit(
+      app,
+      ProgressEvent::Stage {
+        stage: "Diarizing".into(),
+        detail: Some("tinydiarize speaker-turn pass".into()),
+      },
+    );
 
-fn normalize_text_key(s: &str) -> String {
-  let t = s.trim().to_ascii_lowercase();
-  let mut out = String::with_capacity(t.len());
-  let mut last_space = false;
+    let tdrz_model = model_downloader::ensure_tdrz_model(app).await?;
 
-  for ch in t.chars() {
-    let is_space = ch.is_whitespace();
-    if is_space {
-      if !last_space {
-        out.push(' ');
+    let mut segments = Vec::new();
+    let whole = match whisper_input {
+      WhisperInput::Single(audio) => Some(process::AudioInput::File(audio)),
+      WhisperInput::Piped(pipe) => Some(process::AudioInput::Pipe(pipe)),
+      WhisperInput::Chunked { .. } => None,
+    };
+    // Each run's times on the song's clock, before any chunk offset.
+    let speed = f64::from(speed_up(options).unwrap_or(1.0));
+    let unscaled = |segments: Vec<parse::Segment>| {
+      segments.into_iter().map(move |mut seg| {
+        seg.start_ms = (seg.start_ms as f64 * speed).round() as u64;
+        seg.end_ms = (seg.end_ms as f64 * speed).round() as u64;
+        seg
+      })
+    };
+    if let Some(input) = whole {
+      let prefix = tmp_dir.join("out_tdrz");
+      process::run_whisper_tdrz(app, run, whisper, &tdrz_model, input, &prefix).await?;
+      segments = unscaled(parse::read_whispercpp_json(&prefix.with_extension("json"))?).collect();
+    } else if let WhisperInput::Chunked { parts, .. } = whisper_input {
+      for (i, (c, wav)) in parts.iter().enumerate() {
+        let prefix = tmp_dir.join(format!("out_tdrz_{i}"));
+        process::run_whisper_tdrz(app, run, whisper, &tdrz_model, process::AudioInput::File(wav), &prefix).await?;
+        // Keep each chunk's segments up to where the next chunk takes over.
+        let next_start = parts.get(i + 1).map(|(n, _)| n.start_ms).unwrap_or(u64::MAX);
+        for mut seg in unscaled(parse::read_whispercpp_json(&prefix.with_extension("json")).unwrap_or_default()) {
+          seg.start_ms += c.start_ms;
+          seg.end_ms += c.start_ms;
+          if seg.start_ms < next_start {
+            segments.push(seg);
+          }
+        }
       }
-      last_space = true;
-      continue;
     }
 
-    if ch.is_ascii_alphanumeric() || matches!(ch, '\'' | '-' | ',' | '.' | '?' | '!') {
-      out.push(ch);
-      last_space = false;
-    }
+    analysis.speaker_turns = Some(diarize::speaker_turns(&segments));
   }
 
-  out.trim().to_string()
-}
-
-fn word_count(s: &str) -> usize {
-  s.split_whitespace().filter(|w| !w.is_empty()).count()
+  Ok(analysis)
 }
 
-fn parse_ts_to_ms(ts: &str) -> Option<i64> {
-  // expects like [mm:ss.xx] or [mm:ss.xxx]
-  let t = ts.trim().trim_start_matches('[').trim_end_matches(']');
-  let mut parts = t.split(':');
-  let mm = parts.next()?.parse::<i64>().ok()?;
-  let rest = parts.next()?;
-  let mut parts2 = rest.split('.');
-  let ss = parts2.next()?.parse::<i64>().ok()?;
-  let frac = parts2.next().unwrap_or("0");
+/// Run the core post-processing passes, write the output file(s) and report Done.
+fn write_output(
+  app: &Host,
+  out_path: &Path,
+  lrc: &str,
+  options: &GenerateOptions,
+  analysis: Analysis,
+  raw_outputs: Vec<String>,
+  job: &mut job::Job,
+) -> Result<String, String> {
+  job.speaker_turns = analysis.speaker_turns.clone();
+  job.translation = analysis.translation.clone();
+  job.chapters = analysis.chapters.clone();
+  job.enter(app, Stage::Clean);
+
+  let reference = match options.reference_lrc.as_deref().filter(|p| !p.trim().is_empty()) {
+    Some(reference_path) => {
+      let raw = std::fs::read(reference_path).map_err(|e| format!("Failed reading reference lyrics: {e}"))?;
+      Some(parse_lrc(&apply_offset_tag(&encoding::decode(&raw))))
+    }
+    None => None,
+  };
 
-  let frac_ms = match frac.len() {
-    0 => 0,
-    1 => frac.parse::<i64>().ok()? * 100,
-    2 => frac.parse::<i64>().ok()? * 10,
-    _ => frac.get(..3)?.parse::<i64>().ok()?,
+  let wav = match analysis.wav.as_deref() {
+    Some(wav) => Some(audio::read_wav(wav)?),
+    None => None,
+  };
+  let onsets = wav.as_ref().filter(|_| options.refine_onsets).map(audio::detect_onsets);
+  let beats = wav.as_ref().filter(|_| options.snap_to_beats).map(audio::detect_beats);
+
+  let extras = Extras {
+    vocabulary: settings::load(app).vocabulary,
+    reference,
+    onsets,
+    beats,
+    speaker_turns: analysis.speaker_turns,
+    translation: analysis.translation,
+    chapters: analysis.chapters,
   };
+  let finished = pipeline::finish(lrc, options, &extras, app)?;
+  job.enter(app, Stage::Write);
 
-  Some(mm * 60_000 + ss * 1000 + frac_ms)
-}
+  let format = finished.format;
+  let out_path = out_path.with_extension(format.extension());
 
-fn format_ms_to_ts(ms: i64) -> String {
-  let mut ms = ms;
-  if ms < 0 {
-    ms = 0;
+  if let Some(romanized) = &finished.romanized {
+    // song.lrc -> song.romanized.lrc, same timings.
+    let sidecar = out_path.with_extension(format!("romanized.{}", format.extension()));
+    library::write_with_fallback(app, &sidecar, &encoding::encode(romanized, options.encoding))?;
   }
-  let total_seconds = ms / 1000;
-  let mm = total_seconds / 60;
-  let ss = total_seconds % 60;
-  let cs = (ms % 1000) / 10; // centiseconds
-  format!("[{:02}:{:02}.{:02}]", mm, ss, cs)
-}
 
-fn parse_lrc(input: &str) -> Vec<LrcLine> {
-  let mut out = Vec::new();
-  for line in input.lines() {
-    let l = line.trim();
-    if !l.starts_with('[') {
-      continue;
+  // Hand corrections in the previous LRC survive regeneration.
+  let text = if options.preserve_edits && format == OutputFormat::Lrc {
+    let (text, kept) = edits::preserve(app, &out_path, &finished.text);
+    if kept > 0 {
+      emit(
+        app,
+        ProgressEvent::Log {
+          line: format!("Kept {kept} hand-edited lines from the previous output"),
+        },
+      );
     }
-    if let Some(end) = l.find(']') {
-      let ts = &l[..=end];
-      let text = l[end + 1..].trim().to_string();
-      if text.is_empty() {
-        continue;
-      }
-      if let Some(ms) = parse_ts_to_ms(ts) {
-        out.push(LrcLine { ms, text });
-      }
+    text
+  } else {
+    finished.text.clone()
+  };
+  // After preserving edits, which rebuilds the file from its timed lines.
+  let text = if options.sections == SectionMode::Comments && format == OutputFormat::Lrc {
+    sections::annotate_lrc(&text, &finished.sections)
+  } else {
+    text
+  };
+
+  let out_path = library::write_with_fallback(app, &out_path, &encoding::encode(&text, options.encoding))?;
+  if format == OutputFormat::Lrc {
+    if let Err(e) = edits::remember(app, &out_path, &finished.text) {
+      emit(app, ProgressEvent::Log { line: e });
     }
   }
-  out.sort_by_key(|x| x.ms);
-  out
+  if options.reading_speed_report {
+    write_reading_report(app, &out_path, &finished.fast_lines);
+  }
+  if options.sections != SectionMode::Off {
+    write_sections(app, &out_path, &finished.sections);
+  }
+
+  job.enter(app, Stage::Done);
+  emit(
+    app,
+    ProgressEvent::Done {
+      output_path: longpath::display(&out_path),
+      instrumental: false,
+      raw_outputs,
+    },
+  );
+
+  Ok(longpath::display(&out_path))
 }
 
-fn build_chant_set(lines: &[LrcLine]) -> HashSet<String> {
-  let mut counts: HashMap<String, usize> = HashMap::new();
-  for l in lines {
-    let key = normalize_text_key(&l.text);
-    if key.is_empty() {
-      continue;
-    }
-    *counts.entry(key).or_insert(0) += 1;
+// Best-effort: the lyrics are already written, so a failure here is only logged.
+fn write_merge_log(app: &Host, output: &Path, decisions: &[MergeDecision]) {
+  let path = output.with_extension("merge.json");
+  let result = serde_json::to_string_pretty(decisions)
+    .map_err(|e| e.to_string())
+    .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+  if let Err(e) = result {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: format!("Failed writing merge log {}: {e}", path.display()),
+      },
+    );
   }
+}
 
-  let mut chant = HashSet::new();
-  for (k, c) in counts {
-    // chant heuristic: repeated short lines
-    if c >= 3 && word_count(&k) <= 4 {
-      chant.insert(k);
-    }
+// Best-effort like the merge log; an empty list is still written so a stale report from
+// an earlier run doesn't linger.
+fn write_reading_report(app: &Host, output: &Path, fast_lines: &[FastLine]) {
+  let path = output.with_extension("reading.json");
+  if !fast_lines.is_empty() {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: format!("{} lines are too fast to sing; see {}", fast_lines.len(), longpath::display(&path)),
+      },
+    );
+  }
+  let result = serde_json::to_string_pretty(fast_lines)
+    .map_err(|e| e.to_string())
+    .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+  if let Err(e) = result {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: format!("Failed writing reading speed report {}: {e}", path.display()),
+      },
+    );
   }
-  chant
 }
 
-fn find_nearest_within(
-  lines: &[LrcLine],
-  target_ms: i64,
-  tol_ms: i64,
-  used: &HashSet<usize>,
-) -> Option<usize> {
-  let mut best: Option<(usize, i64)> = None; // (idx, abs_diff)
-  for (i, l) in lines.iter().enumerate() {
-    if used.contains(&i) {
-      continue;
-    }
-    let d = (l.ms - target_ms).abs();
-    if d <= tol_ms {
-      match best {
-        None => best = Some((i, d)),
-        Some((_, bd)) if d < bd => best = Some((i, d)),
-        _ => {}
-      }
-    }
+fn write_sections(app: &Host, output: &Path, sections: &[Section]) {
+  let path = output.with_extension("sections.json");
+  let result = serde_json::to_string_pretty(sections)
+    .map_err(|e| e.to_string())
+    .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+  if let Err(e) = result {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: format!("Failed writing sections {}: {e}", path.display()),
+      },
+    );
   }
-  best.map(|(i, _)| i)
 }
 
+// whisper writes `out*.lrc` / `.json` / `.srt` next to its inputs in the temp dir.
+const RAW_EXTENSIONS: &[&str] = &["lrc", "json", "srt", "vtt", "txt"];
 
-fn normalize_lrc_timestamps(input: &str, min_gap_ms: i64) -> String {
-  let mut lines = parse_lrc(input);
-  if lines.is_empty() {
-    return String::new();
+/// Debug: copy whisper's raw outputs out of the run's temp dir before it is deleted.
+/// Best-effort; returns the copies' paths for the Done event.
+fn keep_raw_outputs(app: &Host, options: &GenerateOptions, tmp_dir: &Path, run_id: &str) -> Vec<String> {
+  if !options.keep_raw_output {
+    return Vec::new();
   }
-
-  let mut last_ms = lines[0].ms;
-  for i in 1..lines.len() {
-    if lines[i].ms < last_ms {
-      lines[i].ms = last_ms;
+  let dir = match app.app_data_dir() {
+    Ok(d) => d.join("diagnostics").join(run_id),
+    Err(e) => {
+      emit(app, ProgressEvent::Log { line: format!("Can't keep raw whisper output: {e}") });
+      return Vec::new();
     }
-    if lines[i].ms - last_ms < min_gap_ms {
-      lines[i].ms = last_ms + min_gap_ms;
-    }
-    last_ms = lines[i].ms;
+  };
+
+  let mut raw: Vec<PathBuf> = std::fs::read_dir(tmp_dir)
+    .map(|entries| entries.flatten().map(|e| e.path()).collect())
+    .unwrap_or_default();
+  raw.retain(|p| {
+    let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let ext = p.extension().and_then(|e| e.to_str()).unwrap_or("");
+    name.starts_with("out") && RAW_EXTENSIONS.contains(&ext)
+  });
+  raw.sort();
+  if raw.is_empty() {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: "No raw whisper output to keep (the transcript came from the cache)".into(),
+      },
+    );
+    return Vec::new();
   }
 
-  let mut out = String::new();
-  for l in lines {
-    out.push_str(&format_ms_to_ts(l.ms));
-    out.push(' ');
-    out.push_str(l.text.trim());
-    out.push('\n');
+  if let Err(e) = std::fs::create_dir_all(&dir) {
+    emit(app, ProgressEvent::Log { line: format!("Failed creating {}: {e}", dir.display()) });
+    return Vec::new();
   }
-  out
+  let mut kept = Vec::with_capacity(raw.len());
+  for src in raw {
+    let Some(name) = src.file_name() else { continue };
+    let dest = dir.join(name);
+    match std::fs::copy(&src, &dest) {
+      Ok(_) => kept.push(dest.display().to_string()),
+      Err(e) => emit(app, ProgressEvent::Log { line: format!("Failed keeping {}: {e}", src.display()) }),
+    }
+  }
+  emit(
+    app,
+    ProgressEvent::Log {
+      line: format!("Kept {} raw whisper files in {}", kept.len(), dir.display()),
+    },
+  );
+  kept
 }
 
-fn merge_hybrid_plus(small_clean: &str, medium_clean: &str) -> String {
-  let small = parse_lrc(small_clean);
-  let medium = parse_lrc(medium_clean);
+/* -------------------- Instrumental detection -------------------- */
 
-  if small.is_empty() {
-    return normalize_lrc_timestamps(medium_clean, 250);
-  }
-  if medium.is_empty() {
-    return normalize_lrc_timestamps(small_clean, 250);
-  }
+fn write_instrumental(
+  app: &Host,
+  out_path: &Path,
+  options: &GenerateOptions,
+  raw_outputs: Vec<String>,
+  job: &mut job::Job,
+) -> Result<String, String> {
+  job.enter(app, Stage::Write);
+  emit(
+    app,
+    ProgressEvent::Stage {
+      stage: "Writing".into(),
+      detail: Some("Instrumental detected (no vocals found)".into()),
+    },
+  );
 
-  let chant = build_chant_set(&small);
+  let format = options.output_format();
+  let marker = options.instrumental_marker.trim();
+  let out = if format == OutputFormat::Lrc {
+    let mut out = String::from("[re:LyricTime]\n");
+    if !marker.is_empty() {
+      out.push_str(&format_ms_to_ts(0));
+      out.push_str(marker);
+      out.push('\n');
+    }
+    out
+  } else {
+    let lines: Vec<LrcLine> =
+      Some(marker).filter(|m| !m.is_empty()).map(|m| LrcLine { ms: 0, text: m.to_string(), end_ms: None }).into_iter().collect();
+    render(format, &lines, options.max_line_ms)
+  };
 
-  let tol_ms = 300;
-  let min_gap_ms = 250;
+  let out_path = out_path.with_extension(format.extension());
+  let out_path = library::write_with_fallback(app, &out_path, &encoding::encode(&out, options.encoding))?;
 
-  let mut used_medium: HashSet<usize> = HashSet::new();
-  let mut merged: Vec<LrcLine> = Vec::new();
+  job.enter(app, Stage::Done);
+  emit(
+    app,
+    ProgressEvent::Done {
+      output_path: longpath::display(&out_path),
+      instrumental: true,
+      raw_outputs,
+    },
+  );
 
-  // baseline: small order (coverage)
-  for s in &small {
-    let s_key = normalize_text_key(&s.text);
-    let is_chant = chant.contains(&s_key);
+  Ok(longpath::display(&out_path))
+}
 
-    if let Some(idx) = find_nearest_within(&medium, s.ms, tol_ms, &used_medium) {
-      let m = &medium[idx];
-      let chosen_text = if is_chant {
-        // keep small for chants to preserve repetition coverage
-        s.text.clone()
-      } else {
-        // prefer medium wording when available
-        m.text.clone()
-      };
+/* -------------------- Transcription -------------------- */
+
+/// Whisper settings from `options` that every pass over `audio_path` shares: language,
+/// prompt, decoding, extra arguments and the backend. VAD and speed-up are left off.
+async fn whisper_params(
+  app: &Host,
+  ffprobe: &Path,
+  audio_path: &Path,
+  options: &GenerateOptions,
+  run: process::RunContext,
+) -> Result<process::WhisperParams, String> {
+  Ok(process::WhisperParams {
+    language: options.language.clone().filter(|l| !l.trim().is_empty()),
+    prompt: initial_prompt(app, ffprobe, audio_path, options),
+    decoding: options.decoding,
+    extra_args: process::validate_extra_args(&options.extra_whisper_args, process::WHISPER_RESERVED_ARGS, "whisper")?,
+    no_gpu: options.backend == Backend::Cpu,
+    gpu_index: options.gpu_index,
+    coreml: coreml::prepare(app, options.backend).await,
+    openvino: openvino::prepare(app, options.backend).await,
+    run,
+    ..Default::default()
+  })
+}
 
-      used_medium.insert(idx);
-      merged.push(LrcLine {
-        ms: s.ms,
-        text: chosen_text,
-      });
-    } else {
-      merged.push(s.clone());
+/// "Artist - Title (Album)" from the file's tags, the user's hint and vocabulary, for whisper's
+/// `--prompt`. Missing tags are skipped; nothing at all gives `None`.
+fn initial_prompt(app: &Host, ffprobe: &Path, audio_path: &Path, options: &GenerateOptions) -> Option<String> {
+  let mut parts = Vec::new();
+
+  if options.prompt_from_tags {
+    match process::probe_tags(ffprobe, audio_path) {
+      Ok(tags) => {
+        let head = [tags.get("artist"), tags.get("title")]
+          .into_iter()
+          .flatten()
+          .map(String::as_str)
+          .collect::<Vec<_>>()
+          .join(" - ");
+        if !head.is_empty() {
+          parts.push(head);
+        }
+        if let Some(album) = tags.get("album") {
+          parts.push(format!("({album})"));
+        }
+      }
+      Err(e) => emit(
+        app,
+        ProgressEvent::Log {
+          line: format!("Couldn't read tags for the prompt: {e}"),
+        },
+      ),
     }
   }
 
-  // append medium-only lines (avoid chant spam)
-  for (i, m) in medium.iter().enumerate() {
-    if used_medium.contains(&i) {
-      continue;
-    }
-    let k = normalize_text_key(&m.text);
-    if chant.contains(&k) {
-      continue;
-    }
-    merged.push(m.clone());
+  if let Some(hint) = options.prompt_hint.as_deref().map(str::trim).filter(|h| !h.is_empty()) {
+    parts.push(hint.to_string());
   }
 
-  merged.sort_by_key(|x| x.ms);
+  let vocab = settings::load(app).vocabulary;
+  if let Some(words) = vocabulary::prompt_words(&vocab) {
+    parts.push(words);
+  }
 
-  // drop exact duplicates
-  let mut dedup: Vec<LrcLine> = Vec::new();
-  for l in merged {
-    if let Some(last) = dedup.last() {
-      if last.ms == l.ms && normalize_text_key(&last.text) == normalize_text_key(&l.text) {
-        continue;
-      }
-    }
-    dedup.push(l);
+  if parts.is_empty() {
+    return None;
   }
+  let prompt = parts.join(" ");
+  emit(app, ProgressEvent::Log { line: format!("Initial prompt: {prompt}") });
+  Some(prompt)
+}
 
-  // normalize timestamps (monotonic + minimum gap)
-  let mut last_ms = dedup[0].ms;
-  for i in 1..dedup.len() {
-    if dedup[i].ms < last_ms {
-      dedup[i].ms = last_ms;
-    }
-    if dedup[i].ms - last_ms < min_gap_ms {
-      dedup[i].ms = last_ms + min_gap_ms;
+/// With the language explicitly "en", prefer the English-only variant when it's installed
+/// (faster and more accurate on English), otherwise use the requested model as-is.
+fn resolve_model(
+  app: &Host,
+  resources_dir: &Path,
+  fallback: Option<&PathBuf>,
+  model: &str,
+  params: &process::WhisperParams,
+) -> Result<PathBuf, String> {
+  let english = params
+    .language
+    .as_deref()
+    .map(|l| l.eq_ignore_ascii_case("en"))
+    .unwrap_or(false);
+
+  if english && !params.translate && matches!(model, "small" | "medium") {
+    let en_model = format!("{model}.en");
+    if let Ok(p) = process::resolve_model_path_with_fallback(app, resources_dir, fallback, &en_model) {
+      emit(
+        app,
+        ProgressEvent::Log {
+          line: format!("Language is English: using {en_model}"),
+        },
+      );
+      return verified(app, &params.run, &en_model, p);
     }
-    last_ms = dedup[i].ms;
   }
 
-  let mut out = String::new();
-  for l in dedup {
-    out.push_str(&format_ms_to_ts(l.ms));
-    out.push(' ');
-    out.push_str(l.text.trim());
-    out.push('\n');
-  }
-  out
+  let p = process::resolve_model_path_with_fallback(app, resources_dir, fallback, model)?;
+  verified(app, &params.run, model, p)
 }
 
-/* -------------------- Cleaning -------------------- */
+fn verified(app: &Host, run: &process::RunContext, model: &str, path: PathBuf) -> Result<PathBuf, String> {
+  if let Err(reason) = models::verify_model_file(&path) {
+    let issue = models::issue_for(model, &path, reason);
+    let message = format!("Model '{model}' is corrupt: {}. Redownload it?", issue.reason);
+    emit(
+      app,
+      ProgressEvent::Error {
+        code: "model_corrupt".into(),
+        message: message.clone(),
+        detail: serde_json::to_value(&issue).ok(),
+      },
+    );
+    return Err(message);
+  }
+  diagnostics::model(app, run, &path);
+  Ok(path)
+}
 
-fn clean_lrc(input: &str) -> String {
-  let mut out = String::new();
+enum WhisperInput {
+  Single(PathBuf),
+  /// Decoded by ffmpeg for each pass, straight into whisper.
+  Piped(process::FfmpegPipe),
+  Chunked {
+    parts: Vec<(chunk::Chunk, PathBuf)>,
+    overlap_ms: u64,
+  },
+}
 
-  for line in input.lines() {
-    let l = line.trim();
-    if l.is_empty() {
-      continue;
-    }
+/// `transcribe`, answered from the transcript cache when this pass ran before.
+#[allow(clippy::too_many_arguments)]
+async fn transcribe_cached(
+  cache: Option<&TranscriptCache>,
+  app: &Host,
+  whisper: &Path,
+  model_path: &Path,
+  input: &WhisperInput,
+  out_prefix: &Path,
+  params: &process::WhisperParams,
+  profile: Profile,
+) -> Result<Option<String>, String> {
+  if let Some(cached) = cache.and_then(|c| c.get(model_path)) {
+    emit(
+      app,
+      ProgressEvent::Log {
+        line: format!("Using cached transcript for {}", model_path.display()),
+      },
+    );
+    return Ok(Some(cached));
+  }
 
-    // Drop metadata tags like [by:whisper.cpp], [ar:...], etc.
-    if l.starts_with('[') {
-      if let Some(end) = l.find(']') {
-        let inside = &l[1..end];
-        // If it's a tag (contains ':' and doesn't start with a digit), drop it.
-        if inside.contains(':')
-          && inside
-            .chars()
-            .next()
-            .map(|c| !c.is_ascii_digit())
-            .unwrap_or(false)
-        {
-          continue;
-        }
-      }
-    }
+  let cleaned = transcribe(app, whisper, model_path, input, out_prefix, params, profile).await?;
+  if let (Some(cache), Some(text)) = (cache, &cleaned) {
+    cache.put(model_path, text);
+  }
+  Ok(cleaned)
+}
 
-    // Timestamp line: [mm:ss.xx]text
-    if l.starts_with('[') {
-      if let Some(end) = l.find(']') {
-        let (ts, rest) = l.split_at(end + 1);
-        let mut text = rest.trim().replace('♪', "").trim().to_string();
+/// Run one whisper pass and return the cleaned LRC text.
+/// `None` means whisper exited cleanly but wrote no .lrc (single input only).
+async fn transcribe(
+  app: &Host,
+  whisper: &Path,
+  model_path: &Path,
+  input: &WhisperInput,
+  out_prefix: &Path,
+  params: &process::WhisperParams,
+  profile: Profile,
+) -> Result<Option<String>, String> {
+  let keep_cues = profile == Profile::Speech;
+  if let Some(c) = &params.coreml {
+    coreml::ENCODERS.ensure(app, c.fetch_encoders, model_path).await;
+  }
+  if let Some(o) = &params.openvino {
+    openvino::ENCODERS.ensure(app, o.fetch_encoders, model_path).await;
+  }
 
-        // Drop music cue lines like "(upbeat music)"
-        if text.starts_with('(') && text.ends_with(')') {
-          continue;
-        }
+  match input {
+    WhisperInput::Single(audio) => {
+      process::run_whisper_lrc(app, whisper, model_path, process::AudioInput::File(audio), out_prefix, params).await?;
+      Ok(read_clean_lrc(&out_prefix.with_extension("lrc"), keep_cues)?.map(|text| unscale(&text, params.speed_up)))
+    }
+    WhisperInput::Piped(pipe) => {
+      process::run_whisper_lrc(app, whisper, model_path, process::AudioInput::Pipe(pipe), out_prefix, params).await?;
+      Ok(read_clean_lrc(&out_prefix.with_extension("lrc"), keep_cues)?.map(|text| unscale(&text, params.speed_up)))
+    }
+    WhisperInput::Chunked { parts, overlap_ms } => {
+      let mut results = Vec::with_capacity(parts.len());
 
-        if text.is_empty() {
-          continue;
-        }
+      for (i, (c, wav)) in parts.iter().enumerate() {
+        emit(
+          app,
+          ProgressEvent::Log {
+            line: format!("Chunk {}/{}", i + 1, parts.len()),
+          },
+        );
 
-        while text.contains("  ") {
-          text = text.replace("  ", " ");
-        }
+        let prefix = PathBuf::from(format!("{}_{i}", out_prefix.display()));
+        process::run_whisper_lrc(app, whisper, model_path, process::AudioInput::File(wav), &prefix, params).await?;
 
-        out.push_str(ts);
-        out.push(' ');
-        out.push_str(text.trim());
-        out.push('\n');
-        continue;
+        // A chunk with no output is just a silent stretch.
+        let cleaned = unscale(&read_clean_lrc(&prefix.with_extension("lrc"), keep_cues)?.unwrap_or_default(), params.speed_up);
+        results.push((c.clone(), parse_lrc(&cleaned)));
       }
-    }
 
-    // Otherwise keep non-timestamp lines (rare), but also strip ♪
-    let cleaned = l.replace('♪', "").trim().to_string();
-    if !cleaned.is_empty() {
-      out.push_str(&cleaned);
-      out.push('\n');
+      Ok(Some(lines_to_timed_lrc(&chunk::merge_chunks(results, *overlap_ms))))
     }
   }
+}
+
+/// Whisper's timestamps on sped-up audio, back on the song's clock.
+fn unscale(lrc: &str, speed_up: Option<f32>) -> String {
+  match speed_up {
+    Some(speed) => retime_lrc(lrc, |ms| (ms as f64 * f64::from(speed)).round() as i64),
+    None => lrc.to_string(),
+  }
+}
 
-  out
+fn read_clean_lrc(path: &Path, keep_cues: bool) -> Result<Option<String>, String> {
+  if !path.exists() {
+    return Ok(None);
+  }
+  let raw = std::fs::read_to_string(path)
+    .map_err(|e| format!("Failed reading {}: {e}", path.display()))?;
+  let cleaned = clean_lrc(&raw, keep_cues);
+  // Ends come from the JSON written next to it; without one, later passes estimate them.
+  let Ok(segments) = parse::read_whispercpp_json(&path.with_extension("json")) else {
+    return Ok(Some(cleaned));
+  };
+  let mut lines = parse_lrc(&cleaned);
+  parse::attach_segment_ends(&mut lines, &segments);
+  Ok(Some(lines_to_timed_lrc(&lines)))
 }
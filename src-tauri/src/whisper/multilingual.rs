@@ -0,0 +1,159 @@
+use lyrictime_core::lrc::{lines_to_timed_lrc, parse_lrc, LrcLine};
+use std::path::Path;
+
+use super::{converted_wav, emit, process, transcribe, Profile, ProgressEvent, WhisperInput};
+use crate::cache::WavCache;
+use crate::host::Host;
+
+/// Whisper detects language from a 30 s window, so that's the resolution of a switch.
+const WINDOW_MS: u64 = 30_000;
+/// Detections below this are guesses (instrumental stretches, mumbling) and are ignored.
+const MIN_PROBABILITY: f32 = 0.5;
+
+/// Everything a re-transcription pass needs from the main run.
+pub(super) struct Run<'a> {
+  pub app: &'a Host,
+  pub ffmpeg: &'a Path,
+  pub whisper: &'a Path,
+  pub model_path: &'a Path,
+  pub audio_path: &'a Path,
+  pub ffmpeg_args: &'a [String],
+  pub tmp_dir: &'a Path,
+  pub wav_cache: Option<&'a WavCache>,
+  pub params: &'a process::WhisperParams,
+  pub profile: Profile,
+}
+
+/// A stretch of the song in a language other than the run's.
+struct Region {
+  start_ms: u64,
+  end_ms: u64,
+  language: String,
+}
+
+fn seconds(ms: u64) -> String {
+  format!("{:.0}", ms as f64 / 1000.0)
+}
+
+/// Detect the language of every 30 s window with lyrics in it, then re-transcribe the
+/// stretches that aren't in the song's main language with their own language and
+/// splice them into `lrc`.
+pub(super) async fn relanguage(run: &Run<'_>, lrc: String) -> Result<String, String> {
+  let lines = parse_lrc(&lrc);
+  let Some(last_ms) = lines.iter().map(|l| l.ms).max() else { return Ok(lrc) };
+
+  emit(
+    run.app,
+    ProgressEvent::Stage {
+      stage: "Detecting languages".into(),
+      detail: Some("Checking each 30 s window".into()),
+    },
+  );
+  let wav = converted_wav(run.app, &run.params.run, run.ffmpeg, run.audio_path, run.ffmpeg_args, run.tmp_dir, run.wav_cache).await?;
+
+  let mut windows: Vec<(u64, Option<String>)> = Vec::new();
+  let mut start = 0;
+  while start <= last_ms as u64 {
+    let end = start + WINDOW_MS;
+    let has_lyrics = lines.iter().any(|l| (start..end).contains(&(l.ms as u64)) && !l.text.trim().is_empty());
+    let language = if has_lyrics { detect(run, &wav, start).await? } else { None };
+    windows.push((start, language));
+    start = end;
+  }
+
+  let main = main_language(run.params.language.as_deref(), &windows);
+  let Some(main) = main else { return Ok(lrc) };
+  let regions = regions(&windows, &main);
+  if regions.is_empty() {
+    emit(run.app, ProgressEvent::Log { line: format!("Whole song detected as {main}") });
+    return Ok(lrc);
+  }
+
+  let mut out = lrc;
+  for (i, region) in regions.iter().enumerate() {
+    emit(
+      run.app,
+      ProgressEvent::Stage {
+        stage: "Re-transcribing".into(),
+        detail: Some(format!(
+          "{} s – {} s in {} ({}/{})",
+          seconds(region.start_ms),
+          seconds(region.end_ms),
+          region.language,
+          i + 1,
+          regions.len()
+        )),
+      },
+    );
+    let cut = run.tmp_dir.join(format!("lang_{i}.wav"));
+    process::run_ffmpeg_chunk_to_wav(run.app, &run.params.run, run.ffmpeg, &wav, region.start_ms, region.end_ms - region.start_ms, &cut, &[]).await?;
+    let params = process::WhisperParams {
+      language: Some(region.language.clone()),
+      ..run.params.clone()
+    };
+    let prefix = run.tmp_dir.join(format!("lang_{i}"));
+    let Some(text) = transcribe(run.app, run.whisper, run.model_path, &WhisperInput::Single(cut), &prefix, &params, run.profile).await?
+    else {
+      continue;
+    };
+    // Whisper's times are relative to the cut.
+    let lines: Vec<LrcLine> = parse_lrc(&text)
+      .into_iter()
+      .map(|l| LrcLine {
+        ms: l.ms + region.start_ms as i64,
+        text: l.text,
+        end_ms: l.end_ms.map(|e| e + region.start_ms as i64),
+      })
+      .collect();
+    if !lines.is_empty() {
+      // Not `splice_range`, which writes a file's lines: the transcript keeps end stamps.
+      let range = region.start_ms as i64..region.end_ms as i64;
+      let mut spliced: Vec<LrcLine> = parse_lrc(&out).into_iter().filter(|l| !range.contains(&l.ms)).collect();
+      spliced.extend(lines.into_iter().filter(|l| range.contains(&l.ms)));
+      spliced.sort_by_key(|l| l.ms);
+      out = lines_to_timed_lrc(&spliced);
+    }
+  }
+  Ok(out)
+}
+
+async fn detect(run: &Run<'_>, wav: &Path, start_ms: u64) -> Result<Option<String>, String> {
+  let window = run.tmp_dir.join("lang_window.wav");
+  process::run_ffmpeg_chunk_to_wav(run.app, &run.params.run, run.ffmpeg, wav, start_ms, WINDOW_MS, &window, &[]).await?;
+  let detected = process::detect_language(run.app, &run.params.run, run.whisper, run.model_path, &window).await?;
+  let _ = std::fs::remove_file(&window);
+  if let Some((language, p)) = &detected {
+    emit(
+      run.app,
+      ProgressEvent::Log { line: format!("{} s: {language} (p = {p:.2})", seconds(start_ms)) },
+    );
+  }
+  Ok(detected.filter(|(_, p)| *p >= MIN_PROBABILITY).map(|(language, _)| language))
+}
+
+// The language the main pass ran in: the requested one, whisper's default (English) when
+// none was, and with `auto` whatever whisper heard first.
+fn main_language(requested: Option<&str>, windows: &[(u64, Option<String>)]) -> Option<String> {
+  match requested {
+    None => Some("en".into()),
+    Some(l) if l.eq_ignore_ascii_case("auto") => windows.iter().find_map(|(_, l)| l.clone()),
+    Some(l) => Some(l.to_ascii_lowercase()),
+  }
+}
+
+// Neighbouring windows in the same other language become one region.
+fn regions(windows: &[(u64, Option<String>)], main: &str) -> Vec<Region> {
+  let mut out: Vec<Region> = Vec::new();
+  for (start, language) in windows {
+    let Some(language) = language.as_deref().filter(|l| *l != main) else { continue };
+    match out.last_mut() {
+      Some(r) if r.end_ms == *start && r.language == language => r.end_ms += WINDOW_MS,
+      _ => out.push(Region {
+        start_ms: *start,
+        end_ms: start + WINDOW_MS,
+        language: language.to_string(),
+      }),
+    }
+  }
+  out
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use super::accel::{log, Encoders};
+use super::{responds, whisper_bin_dir, Backend};
+use crate::host::Host;
+use crate::{archive, download};
+
+// A separate whisper build with OpenVINO enabled, unpacked whole (it brings the OpenVINO
+// runtime and its device plugins) into its own folder.
+#[cfg(windows)]
+const OPENVINO_ARCHIVE: &str = "whisper-openvino-windows-x64.zip";
+#[cfg(not(windows))]
+const OPENVINO_ARCHIVE: &str = "whisper-openvino-linux-x64.zip";
+#[cfg(windows)]
+const OPENVINO_WHISPER: &str = "whisper-cli.exe";
+#[cfg(not(windows))]
+const OPENVINO_WHISPER: &str = "whisper-cli";
+
+/// The OpenVINO build and the device it runs the encoder on.
+#[derive(Clone, Debug)]
+pub struct OpenVino {
+  pub whisper: PathBuf,
+  /// `-oved`: "GPU" for an Intel iGPU, else "CPU".
+  pub device: &'static str,
+  /// OpenVINO was chosen explicitly: download missing encoders instead of only using
+  /// installed ones.
+  pub fetch_encoders: bool,
+}
+
+fn intel_cpu() -> bool {
+  let mut sys = sysinfo::System::new();
+  sys.refresh_cpu_list(sysinfo::CpuRefreshKind::nothing());
+  sys.cpus().first().is_some_and(|c| c.vendor_id() == "GenuineIntel")
+}
+
+/// An Intel GPU (integrated Iris Xe/UHD or Arc) OpenVINO can run the encoder on.
+pub fn intel_gpu() -> bool {
+  #[cfg(target_os = "linux")]
+  {
+    // PCI vendor 0x8086 is Intel.
+    std::fs::read_dir("/sys/class/drm").is_ok_and(|entries| {
+      entries
+        .flatten()
+        .any(|e| std::fs::read_to_string(e.path().join("device").join("vendor")).is_ok_and(|v| v.trim() == "0x8086"))
+    })
+  }
+  #[cfg(windows)]
+  {
+    std::process::Command::new("powershell")
+      .args(["-NoProfile", "-Command", "(Get-CimInstance Win32_VideoController).Name"])
+      .output()
+      .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains("Intel"))
+  }
+  #[cfg(not(any(target_os = "linux", windows)))]
+  {
+    false
+  }
+}
+
+/// OpenVINO builds exist for x86-64 Windows and Linux, and only help on Intel hardware.
+pub fn supported() -> bool {
+  cfg!(all(target_arch = "x86_64", any(windows, target_os = "linux"))) && intel_cpu()
+}
+
+/// OpenVINO encoders: "ggml-medium-q5_0.bin" -> "ggml-medium-encoder-openvino.xml", with
+/// its weights in the matching .bin. Converted ones are zipped in the models release.
+pub(super) const ENCODERS: Encoders = Encoders {
+  backend: "OpenVINO",
+  suffix: "-encoder-openvino.xml",
+  weights: Some("bin"),
+  zip_suffix: "-encoder-openvino.zip",
+  url: |_, zip_name| format!("https://github.com/evilduck1/LyricTime/releases/download/models/{zip_name}"),
+};
+
+/// The OpenVINO build to run with `backend`, or `None` for the default build: on other
+/// machines, with Auto when it isn't installed yet, or when it can't be downloaded.
+pub(super) async fn prepare(app: &Host, backend: Backend) -> Option<OpenVino> {
+  if !matches!(backend, Backend::Auto | Backend::OpenVino) {
+    return None;
+  }
+  if !supported() {
+    if backend == Backend::OpenVino {
+      log(app, "OpenVINO needs an Intel CPU on Windows or Linux; using the default backend".into());
+    }
+    return None;
+  }
+
+  let dir = whisper_bin_dir(app).ok()?.join("openvino");
+  let whisper = dir.join(OPENVINO_WHISPER);
+  let device = if intel_gpu() { "GPU" } else { "CPU" };
+  if backend == Backend::Auto {
+    return whisper.is_file().then_some(OpenVino { whisper, device, fetch_encoders: false });
+  }
+  if !whisper.is_file() {
+    if let Err(e) = download_build(app, &dir, &whisper).await {
+      let _ = std::fs::remove_dir_all(&dir);
+      log(app, format!("OpenVINO build unavailable ({e}); using the default backend"));
+      return None;
+    }
+  }
+  Some(OpenVino { whisper, device, fetch_encoders: true })
+}
+
+async fn download_build(app: &Host, dir: &Path, whisper: &Path) -> Result<(), String> {
+  let url = format!("https://github.com/evilduck1/LyricTime/releases/download/deps/{OPENVINO_ARCHIVE}");
+  let zip = dir.with_extension("zip");
+  download::download_with_progress(app, "deps", &url, &zip, OPENVINO_ARCHIVE).await?;
+  let extracted = archive::extract_tree(&zip, dir);
+  let _ = std::fs::remove_file(&zip);
+  extracted?;
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = std::fs::set_permissions(whisper, std::fs::Permissions::from_mode(0o755));
+  }
+  if !responds(whisper, "--help") {
+    let msg = format!("{OPENVINO_ARCHIVE} doesn't run on this system");
+    download::report_error(app, "deps", OPENVINO_ARCHIVE, "binary_broken", &msg);
+    return Err(msg);
+  }
+  Ok(())
+}
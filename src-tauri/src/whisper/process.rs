@@ -1,11 +1,22 @@
-use super::{emit, ProgressEvent};
+use super::failure::{self, Failure};
+use super::{emit, Decoding, GenerateOptions, ProgressEvent};
+use serde::Serialize;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use tauri::{AppHandle, Manager};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::{Child, Command as AsyncCommand};
+
+use crate::host::Host;
+use crate::{longpath, settings};
 
 fn model_candidates(model: &str) -> Result<Vec<&'static str>, String> {
   match model {
+    "tiny" => Ok(vec!["ggml-tiny.bin", "ggml-model-whisper-tiny.bin", "ggml-tiny-q8_0.bin", "ggml-tiny-q5_1.bin"]),
     "small" => Ok(vec![
       "ggml-small.bin",
       "ggml-model-whisper-small.bin",
@@ -22,7 +33,22 @@ fn model_candidates(model: &str) -> Result<Vec<&'static str>, String> {
       "ggml-medium-q8_0.bin",
       "ggml-medium-q5_0.bin",
     ]),
-    _ => Err(format!("Unknown model: {model}")),
+    "small.en" => Ok(vec![
+      "ggml-small.en.bin",
+      "ggml-model-whisper-small.en.bin",
+      "ggml-small.en-q8_0.bin",
+      "ggml-small.en-q5_1.bin",
+    ]),
+    "medium.en" => Ok(vec![
+      "ggml-medium.en.bin",
+      "ggml-model-whisper-medium.en.bin",
+      "ggml-medium.en-q8_0.bin",
+      "ggml-medium.en-q5_0.bin",
+    ]),
+    // Quantized variants picked explicitly ("medium-q5_0") only match their own file.
+    _ => crate::model_downloader::find_variant(model)
+      .map(|v| vec![v.file])
+      .ok_or_else(|| format!("Unknown model: {model}")),
   }
 }
 
@@ -81,13 +107,56 @@ pub fn pick_executable_with_fallback(
   Err(format!("Executable not found: {base}"))
 }
 
+// GUI apps on macOS don't inherit the shell PATH, so also look where package managers install.
+const SYSTEM_BIN_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin", "/opt/local/bin", "/usr/bin"];
+
+/// True if `path` runs and exits 0 with `probe_arg` (`-version`, `--help`).
+pub fn responds(path: &Path, probe_arg: &str) -> bool {
+  Command::new(longpath::extended(path))
+    .arg(probe_arg)
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .map(|s| s.success())
+    .unwrap_or(false)
+}
+
+/// A system install on PATH (or in a common package-manager location) that responds to
+/// `probe_arg`. whisper.cpp installs as `whisper-cli`; a bare `whisper` on PATH is
+/// usually OpenAI's Python tool, which takes different flags, so it's not considered.
+pub fn find_system_executable(base: &str, probe_arg: &str) -> Option<PathBuf> {
+  let stem = base.trim_end_matches(".exe");
+  let name = exe_name(if stem == "whisper" { "whisper-cli" } else { stem });
+
+  let path_dirs: Vec<PathBuf> = std::env::var_os("PATH")
+    .map(|p| std::env::split_paths(&p).collect())
+    .unwrap_or_default();
+
+  path_dirs
+    .into_iter()
+    .chain(SYSTEM_BIN_DIRS.iter().map(PathBuf::from))
+    .map(|dir| dir.join(&name))
+    .filter(|p| p.is_file())
+    .find(|p| responds(p, probe_arg))
+}
+
 pub fn pick_executable_multi(
+  override_path: Option<PathBuf>,
   app_bin_dir: &Path,
   resources_bin_dir: &Path,
   fallback: Option<&PathBuf>,
   platform: &str,
   base: &str,
 ) -> Result<PathBuf, String> {
+  // 0) Explicit override (env var / settings) wins, and must exist
+  if let Some(p) = override_path {
+    if p.is_file() {
+      return Ok(p);
+    }
+    return Err(format!("Configured {base} not found: {}", p.display()));
+  }
+
   // 1) Downloaded binary in app data dir (preferred)
   let app_primary = app_bin_dir.join(exe_name(base));
   if app_primary.exists() {
@@ -108,22 +177,41 @@ pub fn pick_executable_multi(
     }
   }
 
+  // 4) System install (PATH, Homebrew, …)
+  let probe_arg = if base.starts_with("whisper") { "--help" } else { "-version" };
+  if let Some(system) = find_system_executable(base, probe_arg) {
+    return Ok(system);
+  }
+
   Err(format!("Executable not found: {base}"))
 }
 
 pub fn resolve_model_path_with_fallback(
-  app: &AppHandle,
+  app: &Host,
   resources_dir: &Path,
   fallback: Option<&PathBuf>,
   model: &str,
 ) -> Result<PathBuf, String> {
+  // User-registered files take precedence over the directory search.
+  if let Some(custom) = crate::settings::load(app)
+    .custom_models
+    .into_iter()
+    .find(|m| m.name.eq_ignore_ascii_case(model))
+  {
+    let p = PathBuf::from(&custom.path);
+    if p.is_file() {
+      return Ok(p);
+    }
+    return Err(format!("Registered model '{model}' is missing: {}", custom.path));
+  }
+
   let candidates = model_candidates(model)?;
 
   let mut dirs: Vec<PathBuf> = Vec::new();
 
-  // Downloaded models (app data)
-  if let Ok(app_data) = app.path().app_data_dir() {
-    dirs.push(app_data.join("models"));
+  // Downloaded models (app data, or the LYRICTIME_MODELS_DIR / settings override)
+  if let Ok(models_dir) = crate::settings::models_dir(app) {
+    dirs.push(models_dir);
   }
 
   // Bundled models
@@ -154,79 +242,968 @@ pub fn resolve_model_path_with_fallback(
   ))
 }
 
-fn spawn_and_stream(app: &AppHandle, mut cmd: Command, label: &str) -> Result<(), String> {
+/// What every child process of one run shares. Carried with the run rather than set
+/// globally, so overlapping runs (a batch and a quick regenerate) each keep their own.
+#[derive(Clone, Debug)]
+pub struct RunContext {
+  /// Seconds a child may go without printing anything before the watchdog kills it
+  /// (0 = never), from `GenerateOptions::stall_timeout_seconds`.
+  pub stall_timeout_secs: u64,
+  /// Log full command lines of every child process, from `--verbose` or the
+  /// `verbose_diagnostics` setting.
+  pub verbose: bool,
+  /// Set by the cancel command; children are killed and the run stops at its next step.
+  pub cancel: CancelToken,
+}
+
+impl RunContext {
+  pub fn new(app: &Host, options: &GenerateOptions) -> Self {
+    RunContext {
+      stall_timeout_secs: options.stall_timeout_seconds,
+      verbose: app.verbose() || settings::load(app).verbose_diagnostics,
+      cancel: CancelToken::new(),
+    }
+  }
+
+  /// For runs without options of their own (benchmarks, clips): the default timeout.
+  pub fn for_app(app: &Host) -> Self {
+    RunContext::new(app, &GenerateOptions::default())
+  }
+}
+
+impl Default for RunContext {
+  fn default() -> Self {
+    RunContext {
+      stall_timeout_secs: GenerateOptions::default().stall_timeout_seconds,
+      verbose: false,
+      cancel: CancelToken::new(),
+    }
+  }
+}
+
+/// One run's cancel flag, shared by its clones. `cancel` sets every live token; a token
+/// drops out of that registry when its run (all clones) is dropped.
+#[derive(Clone, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+  pub fn new() -> Self {
+    let flag = Arc::new(AtomicBool::new(false));
+    let mut active = locked(&ACTIVE_RUNS);
+    active.retain(|t| t.strong_count() > 0);
+    active.push(Arc::downgrade(&flag));
+    CancelToken(flag)
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.0.load(Ordering::SeqCst)
+  }
+
+  /// For the gaps between steps: `Err` once the run was cancelled.
+  pub fn check(&self) -> Result<(), String> {
+    if self.is_cancelled() {
+      return Err("Cancelled".into());
+    }
+    Ok(())
+  }
+}
+
+impl Default for CancelToken {
+  fn default() -> Self {
+    CancelToken::new()
+  }
+}
+
+/// `cmd` as it would be typed into a shell, for pasting into bug reports.
+pub fn command_line(cmd: &Command) -> String {
+  let quote = |s: &std::ffi::OsStr| {
+    let s = s.to_string_lossy();
+    if s.is_empty() || s.contains([' ', '"', '\'']) {
+      format!("\"{}\"", s.replace('"', "\\\""))
+    } else {
+      s.into_owned()
+    }
+  };
+  std::iter::once(quote(cmd.get_program()))
+    .chain(cmd.get_args().map(quote))
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// First line `path` prints for `version_arg`, if it runs and exits 0.
+pub fn binary_version(path: &Path, version_arg: &str) -> Option<String> {
+  let out = Command::new(longpath::extended(path))
+    .arg(version_arg)
+    .stdin(Stdio::null())
+    .output()
+    .ok()
+    .filter(|o| o.status.success())?;
+  let first_line = |bytes: &[u8]| {
+    String::from_utf8_lossy(bytes)
+      .lines()
+      .map(str::trim)
+      .find(|l| !l.is_empty())
+      .map(str::to_string)
+  };
+  first_line(&out.stdout).or_else(|| first_line(&out.stderr))
+}
+
+/// Every whisper/ffmpeg/yt-dlp child still running, by PID, so quitting can stop them.
+static CHILDREN: Mutex<Vec<(u32, Child)>> = Mutex::new(Vec::new());
+/// Cancel flags of the runs in progress.
+static ACTIVE_RUNS: Mutex<Vec<Weak<AtomicBool>>> = Mutex::new(Vec::new());
+/// Temp workspaces of runs in progress; removed on quit since their runs never finish.
+static RUN_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// A panic while holding a registry lock shouldn't stop shutdown from cleaning up.
+fn locked<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+  m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn untrack_child(pid: u32) -> Option<Child> {
+  let mut children = locked(&CHILDREN);
+  let i = children.iter().position(|(p, _)| *p == pid)?;
+  Some(children.swap_remove(i).1)
+}
+
+/// Cancel every run in progress (the cancel command): their children are killed and
+/// each fails with a "cancelled" error at whichever step it was.
+pub fn cancel() {
+  for flag in locked(&ACTIVE_RUNS).iter().filter_map(Weak::upgrade) {
+    flag.store(true, Ordering::SeqCst);
+  }
+}
+
+/// Kill every running child and delete the temp workspaces of unfinished runs.
+pub fn shutdown() {
+  for (_, mut child) in std::mem::take(&mut *locked(&CHILDREN)) {
+    let _ = child.start_kill();
+  }
+  for dir in std::mem::take(&mut *locked(&RUN_DIRS)) {
+    let _ = fs::remove_dir_all(dir);
+  }
+}
+
+/// A run's temp workspace under the system temp dir; deleted when dropped, or by
+/// `shutdown` if the app quits first.
+pub struct RunDir(PathBuf);
+
+impl RunDir {
+  pub fn create(name: &str) -> Result<Self, String> {
+    let dir = std::env::temp_dir().join("lyrictime").join(name);
+    fs::create_dir_all(&dir).map_err(|e| format!("temp dir create failed: {e}"))?;
+    locked(&RUN_DIRS).push(dir.clone());
+    Ok(RunDir(dir))
+  }
+}
+
+impl std::ops::Deref for RunDir {
+  type Target = Path;
+  fn deref(&self) -> &Path {
+    &self.0
+  }
+}
+
+impl Drop for RunDir {
+  fn drop(&mut self) {
+    locked(&RUN_DIRS).retain(|d| d != &self.0);
+    let _ = fs::remove_dir_all(&self.0);
+  }
+}
+
+pub(super) async fn spawn_and_stream(app: &Host, run: &RunContext, cmd: AsyncCommand, label: &str) -> Result<(), String> {
+  spawn_and_watch(app, run, cmd, label, |_| {}).await
+}
+
+// `spawn_and_stream`, also handing every line the child prints to `on_line`.
+async fn spawn_and_watch(
+  app: &Host,
+  run: &RunContext,
+  mut cmd: AsyncCommand,
+  label: &str,
+  on_line: impl Fn(&str) + Send + Sync + 'static,
+) -> Result<(), String> {
+  let on_line = Arc::new(on_line);
   emit(
     app,
     ProgressEvent::Log {
-      line: format!("Running {label}…"),
+      line: if run.verbose {
+        format!("Running {label}: {}", command_line(cmd.as_std()))
+      } else {
+        format!("Running {label}…")
+      },
     },
   );
 
+  run.cancel.check()?;
   let mut child = cmd
-    .stdout(Stdio::null())
+    .stdout(Stdio::piped())
     .stderr(Stdio::piped())
     .spawn()
-    .map_err(|e| format!("Failed spawning {label}: {e}"))?;
+    .map_err(|e| match e.kind() {
+      std::io::ErrorKind::NotFound => format!("{label} is missing ({e}); reinstall LyricTime or check its path in Settings"),
+      _ => format!("Failed spawning {label}: {e}"),
+    })?;
 
-  if let Some(stderr) = child.stderr.take() {
+  // Watchdog clock: ms since start at which the child last printed anything.
+  let started = Instant::now();
+  let last_output = Arc::new(AtomicU64::new(0));
+
+  // What the child's messages say went wrong, should it fail.
+  let hint: Arc<Mutex<Option<Failure>>> = Arc::new(Mutex::new(None));
+
+  let stderr_reader = child.stderr.take().map(|stderr| {
     let app2 = app.clone();
-    std::thread::spawn(move || {
-      use std::io::{BufRead, BufReader};
-      let reader = BufReader::new(stderr);
-      for line in reader.lines().flatten() {
+    let last_output = last_output.clone();
+    let hint = hint.clone();
+    let on_line = on_line.clone();
+    tokio::spawn(async move {
+      let mut lines = BufReader::new(stderr).lines();
+      while let Ok(Some(line)) = lines.next_line().await {
+        last_output.store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+        on_line(&line);
+        if let Some(found) = failure::from_output_line(&line) {
+          // Running out of memory causes the other symptoms, so it sticks.
+          let mut hint = locked(&hint);
+          if *hint != Some(Failure::OutOfMemory) {
+            *hint = Some(found);
+          }
+        }
         emit(&app2, ProgressEvent::Log { line });
       }
-    });
-  }
+    })
+  });
+  // stdout (whisper's segment echo) only counts as a sign of life.
+  let stdout_reader = child.stdout.take().map(|stdout| {
+    let last_output = last_output.clone();
+    tokio::spawn(async move {
+      let mut segments = BufReader::new(stdout).split(b'\n');
+      while let Ok(Some(segment)) = segments.next_segment().await {
+        last_output.store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+        on_line(&String::from_utf8_lossy(&segment));
+      }
+    })
+  });
+
+  let Some(pid) = child.id() else {
+    return Err(format!("{label} exited before it could be tracked"));
+  };
+  locked(&CHILDREN).push((pid, child));
+
+  let status = loop {
+    let polled = {
+      let mut children = locked(&CHILDREN);
+      match children.iter_mut().find(|(p, _)| *p == pid) {
+        Some((_, child)) => child.try_wait(),
+        // `shutdown` took it: the app is quitting.
+        None => return Err(format!("{label} was stopped because LyricTime is quitting")),
+      }
+    };
+    match polled {
+      Ok(Some(status)) => {
+        untrack_child(pid);
+        break status;
+      }
+      Ok(None) => {}
+      Err(e) => {
+        untrack_child(pid);
+        return Err(format!("Failed waiting for {label}: {e}"));
+      }
+    }
 
-  let status = child
-    .wait()
-    .map_err(|e| format!("Failed waiting for {label}: {e}"))?;
+    let timeout_secs = run.stall_timeout_secs;
+    let idle_ms = (started.elapsed().as_millis() as u64).saturating_sub(last_output.load(Ordering::Relaxed));
+    if timeout_secs > 0 && idle_ms > timeout_secs * 1000 {
+      if let Some(mut child) = untrack_child(pid) {
+        let _ = child.kill().await;
+      }
+      return Err(format!(
+        "{label} stalled (no output for {timeout_secs}s) and was stopped; the input may be corrupt"
+      ));
+    }
+    if run.cancel.is_cancelled() {
+      if let Some(mut child) = untrack_child(pid) {
+        let _ = child.kill().await;
+      }
+      return Err(format!("{label} was cancelled"));
+    }
+    tokio::time::sleep(Duration::from_millis(200)).await;
+  };
 
   if !status.success() {
-    return Err(format!("{label} failed with status: {status}"));
+    // Let the reader see the last lines before deciding why it failed.
+    if let Some(reader) = stderr_reader {
+      let _ = reader.await;
+    }
+    let failure = failure::classify(&status, *locked(&hint));
+    let message = failure::message(label, failure, &status);
+    // Running out of memory may still be recovered from with a smaller model.
+    if failure != Failure::OutOfMemory {
+      emit(
+        app,
+        ProgressEvent::Error {
+          code: failure.code().into(),
+          message: message.clone(),
+          detail: Some(serde_json::json!({ "label": label, "status": status.to_string() })),
+        },
+      );
+    }
+    return Err(message);
   }
 
+  // `on_line` has seen everything the child printed once this returns.
+  for reader in [stderr_reader, stdout_reader].into_iter().flatten() {
+    let _ = reader.await;
+  }
   Ok(())
 }
 
-pub fn run_ffmpeg_to_wav(
-  app: &AppHandle,
+pub(super) const OUT_OF_MEMORY: &str = "ran out of memory";
+
+/// True for the error `spawn_and_stream` returns when the child died allocating memory.
+pub fn is_out_of_memory(err: &str) -> bool {
+  err.contains(OUT_OF_MEMORY)
+}
+
+/// Flags the app sets itself; letting extra args repeat them would break the pipeline.
+pub const WHISPER_RESERVED_ARGS: &[&str] = &[
+  "-m", "--model", "-f", "--file", "-of", "--output-file", "-olrc", "--output-lrc", "-oj", "--output-json",
+];
+/// `-map` too: a second mapping would add another stream; `audio_stream` picks the track.
+pub const FFMPEG_RESERVED_ARGS: &[&str] = &["-i", "-y", "-n", "-ss", "-t", "-to", "-ac", "-ar", "-f", "-map"];
+
+/// Check user-supplied extra arguments. They are handed to the process as separate
+/// argv entries (never through a shell), so only control characters and flags we
+/// already manage are rejected.
+pub fn validate_extra_args(args: &[String], reserved: &[&str], label: &str) -> Result<Vec<String>, String> {
+  let mut out = Vec::with_capacity(args.len());
+  for arg in args.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+    if arg.chars().any(char::is_control) {
+      return Err(format!("Extra {label} argument contains control characters: {arg:?}"));
+    }
+    let flag = arg.split('=').next().unwrap_or(arg);
+    if reserved.contains(&flag) {
+      return Err(format!("Extra {label} argument {flag} is managed by LyricTime"));
+    }
+    out.push(arg.to_string());
+  }
+  Ok(out)
+}
+
+/// `path` as an argument for a spawned tool, extended-length where Windows needs it.
+fn path_arg(path: &Path, invalid: &'static str) -> Result<String, &'static str> {
+  longpath::extended(path).to_str().map(str::to_string).ok_or(invalid)
+}
+
+pub async fn run_ffmpeg_to_wav(
+  app: &Host,
+  run: &RunContext,
   ffmpeg: &Path,
   input: &Path,
   output_wav: &Path,
+  extra_args: &[String],
 ) -> Result<(), String> {
-  let mut cmd = Command::new(ffmpeg);
+  let mut cmd = AsyncCommand::new(longpath::extended(ffmpeg));
   cmd.args([
     "-y",
     "-i",
-    input.to_str().ok_or("Invalid input path")?,
+    &path_arg(input, "Invalid input path")?,
+  ]);
+  cmd.args(extra_args);
+  cmd.args([
+    // Video containers: drop the picture, keep the (default) audio stream.
+    "-vn",
     "-ac",
     "1",
     "-ar",
     "16000",
-    output_wav.to_str().ok_or("Invalid output path")?,
+    &path_arg(output_wav, "Invalid output path")?,
+  ]);
+
+  spawn_and_stream(app, run, cmd, "ffmpeg").await
+}
+
+/// Audio ffmpeg decodes to 16k mono WAV on its stdout for whisper to read from stdin,
+/// instead of converting to a temp file first.
+#[derive(Clone, Debug)]
+pub struct FfmpegPipe {
+  pub ffmpeg: PathBuf,
+  pub input: PathBuf,
+  pub extra_args: Vec<String>,
+}
+
+/// What a whisper pass reads.
+#[derive(Clone, Copy)]
+pub enum AudioInput<'a> {
+  File(&'a Path),
+  Pipe(&'a FfmpegPipe),
+}
+
+// Run whisper's `cmd` on `input`, with ffmpeg feeding its stdin when piped.
+async fn run_whisper_on(
+  app: &Host,
+  run: &RunContext,
+  mut cmd: AsyncCommand,
+  input: AudioInput<'_>,
+  label: &str,
+) -> Result<(), String> {
+  let pipe = match input {
+    AudioInput::File(path) => {
+      cmd.arg(path_arg(path, "Invalid input audio path")?);
+      return spawn_and_stream(app, run, cmd, label).await;
+    }
+    AudioInput::Pipe(pipe) => pipe,
+  };
+
+  let mut decoder = AsyncCommand::new(longpath::extended(&pipe.ffmpeg));
+  decoder.args(["-nostdin", "-loglevel", "error", "-i", &path_arg(&pipe.input, "Invalid input path")?]);
+  decoder.args(&pipe.extra_args);
+  // Unseekable, so the WAV header's sizes stay unset; whisper reads to the end regardless.
+  decoder.args(["-vn", "-ac", "1", "-ar", "16000", "-f", "wav", "-"]);
+  let mut ffmpeg = decoder
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()
+    .map_err(|e| format!("Failed spawning ffmpeg: {e}"))?;
+  let decoded: Stdio = ffmpeg
+    .stdout
+    .take()
+    .ok_or("ffmpeg has no output pipe")?
+    .try_into()
+    .map_err(|e| format!("Failed piping ffmpeg into {label}: {e}"))?;
+  // Kept for when the decode fails; quiet otherwise with `-loglevel error`.
+  let errors = ffmpeg.stderr.take().map(|stderr| {
+    tokio::spawn(async move {
+      let mut text = String::new();
+      let _ = BufReader::new(stderr).read_to_string(&mut text).await;
+      text
+    })
+  });
+
+  // Tracked like every child, so quitting stops it too.
+  let Some(ffmpeg_pid) = ffmpeg.id() else {
+    return Err("ffmpeg exited before it could be tracked".into());
+  };
+  locked(&CHILDREN).push((ffmpeg_pid, ffmpeg));
+
+  cmd.arg("-").stdin(decoded);
+  let result = spawn_and_stream(app, run, cmd, label).await;
+  // `shutdown` took it: the app is quitting, and whisper's result already says so.
+  let Some(mut ffmpeg) = untrack_child(ffmpeg_pid) else {
+    return result;
+  };
+  let decode_failed = match ffmpeg.try_wait() {
+    Ok(Some(status)) => !status.success(),
+    // Whisper stopped early (failed, cancelled, stalled) mid-decode: stop ffmpeg too.
+    _ if result.is_err() => {
+      let _ = ffmpeg.kill().await;
+      false
+    }
+    _ => !ffmpeg.wait().await.map_err(|e| format!("Failed waiting for ffmpeg: {e}"))?.success(),
+  };
+  let errors = match errors {
+    Some(reader) => reader.await.unwrap_or_default(),
+    None => String::new(),
+  };
+  for line in errors.lines().filter(|l| !l.trim().is_empty()) {
+    emit(app, ProgressEvent::Log { line: line.to_string() });
+  }
+  // A failed decode is why whisper got no (or short) audio.
+  if decode_failed {
+    let reason = errors.lines().rfind(|l| !l.trim().is_empty()).unwrap_or("no details");
+    return Err(format!("ffmpeg failed decoding {}: {reason}", pipe.input.display()));
+  }
+  result
+}
+
+/// Decoding settings shared by every whisper pass of a run.
+#[derive(Default, Clone)]
+pub struct WhisperParams {
+  /// Silero VAD model; non-speech regions are skipped instead of hallucinated over.
+  pub vad_model: Option<PathBuf>,
+  /// Spoken language code (`en`, `ja`, `auto`, …); unset keeps whisper's default.
+  pub language: Option<String>,
+  /// Translate to English instead of transcribing (`-tr`).
+  pub translate: bool,
+  /// Initial prompt (`--prompt`); biases spelling of names and rare words.
+  pub prompt: Option<String>,
+  pub decoding: Decoding,
+  /// Already validated with `validate_extra_args`.
+  pub extra_args: Vec<String>,
+  /// Keep off the GPU (`-ng`).
+  pub no_gpu: bool,
+  /// NVIDIA card to run on, by `nvidia-smi` index.
+  pub gpu_index: Option<u32>,
+  /// The input was sped up by this factor; whisper's timestamps are scaled back.
+  pub speed_up: Option<f32>,
+  /// Core ML build, run instead for models whose encoder is installed.
+  pub coreml: Option<super::coreml::CoreMl>,
+  /// OpenVINO build, likewise.
+  pub openvino: Option<super::openvino::OpenVino>,
+  pub run: RunContext,
+}
+
+pub async fn run_whisper_lrc(
+  app: &Host,
+  whisper: &Path,
+  model: &Path,
+  input: AudioInput<'_>,
+  out_prefix: &Path,
+  params: &WhisperParams,
+) -> Result<(), String> {
+  // The accelerated builds can't start without the model's encoder, so others use the default.
+  let (whisper, openvino_device) = match (&params.coreml, &params.openvino) {
+    (Some(c), _) if super::coreml::ENCODERS.installed(model) => (c.whisper.as_path(), None),
+    (_, Some(o)) if super::openvino::ENCODERS.installed(model) => (o.whisper.as_path(), Some(o.device)),
+    _ => (whisper, None),
+  };
+  let mut cmd = AsyncCommand::new(longpath::extended(whisper));
+  cmd.args([
+    "-m",
+    &path_arg(model, "Invalid model path")?,
+    "-olrc",
+    // The JSON has the segment ends the LRC leaves out.
+    "-oj",
+    // Progress lines keep the watchdog fed during long decodes.
+    "-pp",
+    "-of",
+    &path_arg(out_prefix, "Invalid output prefix")?,
   ]);
 
-  spawn_and_stream(app, cmd, "ffmpeg")
+  if let Some(vad_model) = &params.vad_model {
+    cmd.args(["--vad", "-vm", &path_arg(vad_model, "Invalid VAD model path")?]);
+  }
+
+  if let Some(language) = &params.language {
+    cmd.args(["-l", language]);
+  }
+  if params.translate {
+    cmd.arg("-tr");
+  }
+  if params.no_gpu {
+    cmd.arg("-ng");
+  } else if let Some(index) = params.gpu_index {
+    // CUDA numbers cards fastest-first unless told to use bus order, which nvidia-smi uses.
+    cmd.env("CUDA_DEVICE_ORDER", "PCI_BUS_ID").env("CUDA_VISIBLE_DEVICES", index.to_string());
+  }
+  if let Some(device) = openvino_device {
+    cmd.args(["-oved", device]);
+  }
+  if let Some(prompt) = &params.prompt {
+    cmd.args(["--prompt", prompt]);
+  }
+
+  let d = &params.decoding;
+  if let Some(v) = d.beam_size {
+    cmd.args(["-bs", &v.to_string()]);
+  }
+  if let Some(v) = d.best_of {
+    cmd.args(["-bo", &v.to_string()]);
+  }
+  if let Some(v) = d.temperature {
+    cmd.args(["-tp", &v.to_string()]);
+  }
+  if let Some(v) = d.entropy_threshold {
+    cmd.args(["-et", &v.to_string()]);
+  }
+  cmd.args(&params.extra_args);
+
+  run_whisper_on(app, &params.run, cmd, input, "whisper").await
 }
 
-pub fn run_whisper_lrc(
-  app: &AppHandle,
+/// Language whisper hears in the first 30 s of `input_audio` and its probability.
+/// `-dl` stops right after detection, so this costs a single encoder pass.
+pub async fn detect_language(
+  app: &Host,
+  run: &RunContext,
   whisper: &Path,
   model: &Path,
   input_audio: &Path,
+) -> Result<Option<(String, f32)>, String> {
+  let mut cmd = AsyncCommand::new(longpath::extended(whisper));
+  cmd
+    .args([
+      "-m",
+      &path_arg(model, "Invalid model path")?,
+      "-l",
+      "auto",
+      "-dl",
+      &path_arg(input_audio, "Invalid input audio path")?,
+    ])
+    .stdin(Stdio::null());
+  let detected = Arc::new(Mutex::new(None));
+  let found = detected.clone();
+  spawn_and_watch(app, run, cmd, "whisper language detection", move |line| {
+    if let Some(language) = parse_detected_language(line) {
+      *locked(&found) = Some(language);
+    }
+  })
+  .await?;
+  let detected = locked(&detected).take();
+  Ok(detected)
+}
+
+// "whisper_full_with_state: auto-detected language: ko (p = 0.873412)"
+fn parse_detected_language(line: &str) -> Option<(String, f32)> {
+  const MARKER: &str = "auto-detected language:";
+  let rest = line[line.find(MARKER)? + MARKER.len()..].trim();
+  let (code, p) = rest.split_once(' ')?;
+  let p = p.trim_start_matches("(p =").trim_end_matches(')').trim().parse().ok()?;
+  Some((code.to_string(), p))
+}
+
+/// Container duration in milliseconds, via ffprobe.
+pub fn probe_duration_ms(ffprobe: &Path, input: &Path) -> Result<u64, String> {
+  let out = Command::new(longpath::extended(ffprobe))
+    .args([
+      "-v",
+      "error",
+      "-show_entries",
+      "format=duration",
+      "-of",
+      "default=noprint_wrappers=1:nokey=1",
+      &path_arg(input, "Invalid input path")?,
+    ])
+    .output()
+    .map_err(|e| format!("Failed spawning ffprobe: {e}"))?;
+
+  if !out.status.success() {
+    return Err(format!("ffprobe failed with status: {}", out.status));
+  }
+
+  let secs = String::from_utf8_lossy(&out.stdout)
+    .trim()
+    .parse::<f64>()
+    .map_err(|e| format!("ffprobe returned no usable duration: {e}"))?;
+
+  Ok((secs.max(0.0) * 1000.0) as u64)
+}
+
+/// The container ffprobe detects from the content ("wav", "mp3", "mov,mp4,m4a,…"),
+/// whatever the file's extension says.
+pub fn probe_container(ffprobe: &Path, input: &Path) -> Result<String, String> {
+  let out = Command::new(longpath::extended(ffprobe))
+    .args([
+      "-v",
+      "error",
+      "-show_entries",
+      "format=format_name",
+      "-of",
+      "default=noprint_wrappers=1:nokey=1",
+      &path_arg(input, "Invalid input path")?,
+    ])
+    .output()
+    .map_err(|e| format!("Failed spawning ffprobe: {e}"))?;
+
+  if !out.status.success() {
+    return Err(format!("ffprobe couldn't read {}", input.display()));
+  }
+  Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// One audio stream of a container, as listed by ffprobe.
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioStream {
+  /// Position among the audio streams (`-map 0:a:<index>`), not the container index.
+  pub index: usize,
+  pub codec: Option<String>,
+  pub channels: u32,
+  /// e.g. "stereo", "5.1(side)".
+  pub channel_layout: Option<String>,
+  pub language: Option<String>,
+  pub title: Option<String>,
+}
+
+impl AudioStream {
+  /// Has a front-center channel (5.1/7.1 mixes), where dialogue and vocals usually sit.
+  pub fn has_center(&self) -> bool {
+    self.channels >= 3
+      && self
+        .channel_layout
+        .as_deref()
+        .map(|l| !l.starts_with("stereo") && !l.starts_with("mono") && !l.starts_with("2.1"))
+        .unwrap_or(true)
+  }
+}
+
+/// Audio streams in the container (empty for a silent video).
+pub fn probe_audio_streams(ffprobe: &Path, input: &Path) -> Result<Vec<AudioStream>, String> {
+  let out = Command::new(longpath::extended(ffprobe))
+    .args([
+      "-v",
+      "error",
+      "-select_streams",
+      "a",
+      "-show_entries",
+      "stream=codec_name,channels,channel_layout:stream_tags=language,title",
+      "-of",
+      "json",
+      &path_arg(input, "Invalid input path")?,
+    ])
+    .output()
+    .map_err(|e| format!("Failed spawning ffprobe: {e}"))?;
+
+  if !out.status.success() {
+    return Err(format!("ffprobe couldn't read {}", input.display()));
+  }
+
+  let json: serde_json::Value =
+    serde_json::from_slice(&out.stdout).map_err(|e| format!("ffprobe returned invalid JSON: {e}"))?;
+  let text = |v: &serde_json::Value| v.as_str().map(str::to_string);
+
+  Ok(
+    json["streams"]
+      .as_array()
+      .map(|streams| {
+        streams
+          .iter()
+          .enumerate()
+          .map(|(index, s)| AudioStream {
+            index,
+            codec: text(&s["codec_name"]),
+            channels: s["channels"].as_u64().unwrap_or(0) as u32,
+            channel_layout: text(&s["channel_layout"]),
+            language: text(&s["tags"]["language"]),
+            title: text(&s["tags"]["title"]),
+          })
+          .collect()
+      })
+      .unwrap_or_default(),
+  )
+}
+
+/// A chapter mark (m4b audiobooks, DJ mixes, concert videos).
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+  pub start_ms: u64,
+  pub end_ms: u64,
+  /// "Chapter N" when the file doesn't name it.
+  pub title: String,
+}
+
+/// Chapters in the container, in order (empty when there are none).
+pub fn probe_chapters(ffprobe: &Path, input: &Path) -> Result<Vec<Chapter>, String> {
+  let out = Command::new(longpath::extended(ffprobe))
+    .args([
+      "-v",
+      "error",
+      "-show_chapters",
+      "-of",
+      "json",
+      &path_arg(input, "Invalid input path")?,
+    ])
+    .output()
+    .map_err(|e| format!("Failed spawning ffprobe: {e}"))?;
+
+  if !out.status.success() {
+    return Err(format!("ffprobe couldn't read {}", input.display()));
+  }
+
+  let json: serde_json::Value =
+    serde_json::from_slice(&out.stdout).map_err(|e| format!("ffprobe returned invalid JSON: {e}"))?;
+  // Times come back as decimal-second strings.
+  let ms = |v: &serde_json::Value| {
+    v.as_str().and_then(|s| s.parse::<f64>().ok()).map(|s| (s * 1000.0).round().max(0.0) as u64)
+  };
+
+  Ok(
+    json["chapters"]
+      .as_array()
+      .map(|chapters| {
+        chapters
+          .iter()
+          .enumerate()
+          .filter_map(|(i, c)| {
+            Some(Chapter {
+              start_ms: ms(&c["start_time"])?,
+              end_ms: ms(&c["end_time"])?,
+              title: c["tags"]["title"]
+                .as_str()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+            })
+          })
+          .collect()
+      })
+      .unwrap_or_default(),
+  )
+}
+
+/// Container-level metadata tags, keys lowercased (`artist`, `title`, `album`, …).
+pub fn probe_tags(ffprobe: &Path, input: &Path) -> Result<HashMap<String, String>, String> {
+  let out = Command::new(longpath::extended(ffprobe))
+    .args([
+      "-v",
+      "error",
+      "-show_entries",
+      "format_tags",
+      "-of",
+      "json",
+      &path_arg(input, "Invalid input path")?,
+    ])
+    .output()
+    .map_err(|e| format!("Failed spawning ffprobe: {e}"))?;
+
+  if !out.status.success() {
+    return Err(format!("ffprobe failed with status: {}", out.status));
+  }
+
+  let json: serde_json::Value =
+    serde_json::from_slice(&out.stdout).map_err(|e| format!("ffprobe returned invalid JSON: {e}"))?;
+
+  Ok(
+    json["format"]["tags"]
+      .as_object()
+      .map(|tags| {
+        tags
+          .iter()
+          .filter_map(|(k, v)| Some((k.to_lowercase(), v.as_str()?.trim().to_string())))
+          .filter(|(_, v)| !v.is_empty())
+          .collect()
+      })
+      .unwrap_or_default(),
+  )
+}
+
+/// Cut `[start_ms, start_ms + len_ms)` out of `input` as 16k mono WAV.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_ffmpeg_chunk_to_wav(
+  app: &Host,
+  run: &RunContext,
+  ffmpeg: &Path,
+  input: &Path,
+  start_ms: u64,
+  len_ms: u64,
+  output_wav: &Path,
+  extra_args: &[String],
+) -> Result<(), String> {
+  let mut cmd = AsyncCommand::new(longpath::extended(ffmpeg));
+  cmd.args([
+    "-y",
+    "-ss",
+    &format!("{:.3}", start_ms as f64 / 1000.0),
+    "-t",
+    &format!("{:.3}", len_ms as f64 / 1000.0),
+    "-i",
+    &path_arg(input, "Invalid input path")?,
+  ]);
+  cmd.args(extra_args);
+  cmd.args([
+    // Video containers: drop the picture, keep the (default) audio stream.
+    "-vn",
+    "-ac",
+    "1",
+    "-ar",
+    "16000",
+    &path_arg(output_wav, "Invalid output path")?,
+  ]);
+
+  spawn_and_stream(app, run, cmd, "ffmpeg").await
+}
+
+/// Cut `[start_ms, end_ms)` of `input` into a standalone FLAC (to the end when `end_ms`
+/// is unset), keeping only `audio_stream` when set. `metadata` is written as tags so
+/// prompt-from-tags still works per part.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_ffmpeg_cut(
+  app: &Host,
+  run: &RunContext,
+  ffmpeg: &Path,
+  input: &Path,
+  start_ms: u64,
+  end_ms: Option<u64>,
+  audio_stream: Option<usize>,
+  metadata: &[(String, String)],
+  output: &Path,
+) -> Result<(), String> {
+  let mut cmd = AsyncCommand::new(longpath::extended(ffmpeg));
+  cmd.args(["-y", "-ss", &format!("{:.3}", start_ms as f64 / 1000.0)]);
+  if let Some(end) = end_ms {
+    cmd.args(["-t", &format!("{:.3}", end.saturating_sub(start_ms) as f64 / 1000.0)]);
+  }
+  cmd.args(["-i", &path_arg(input, "Invalid input path")?]);
+  if let Some(i) = audio_stream {
+    cmd.args(["-map", &format!("0:a:{i}")]);
+  }
+  // Source tags describe the whole album/mix, not this part.
+  cmd.args(["-map_metadata", "-1", "-map_chapters", "-1"]);
+  for (key, value) in metadata {
+    cmd.args(["-metadata", &format!("{key}={value}")]);
+  }
+  cmd.args(["-vn", "-c:a", "flac", &path_arg(output, "Invalid output path")?]);
+
+  spawn_and_stream(app, run, cmd, "ffmpeg").await
+}
+
+/// Download the audio of `url` into `out_dir` with yt-dlp and return the file it wrote.
+/// ffmpeg (from `ffmpeg_dir`) does the audio extraction; the best audio stream is kept
+/// as-is rather than re-encoded.
+pub async fn run_ytdlp(
+  app: &Host,
+  run: &RunContext,
+  ytdlp: &Path,
+  ffmpeg_dir: &Path,
+  url: &str,
+  out_dir: &Path,
+) -> Result<PathBuf, String> {
+  fs::create_dir_all(out_dir).map_err(|e| format!("Failed creating {}: {e}", out_dir.display()))?;
+  let path_file = out_dir.join(".lyrictime-ytdlp-path.txt");
+  let _ = fs::remove_file(&path_file);
+
+  let mut cmd = AsyncCommand::new(longpath::extended(ytdlp));
+  cmd.args([
+    "--no-playlist",
+    "-f",
+    "bestaudio/best",
+    "-x",
+    "--ffmpeg-location",
+    &path_arg(ffmpeg_dir, "Invalid ffmpeg path")?,
+    "-P",
+    &path_arg(out_dir, "Invalid output folder")?,
+    "-o",
+    "%(artist,uploader)s - %(track,title)s.%(ext)s",
+    "--print-to-file",
+    "after_move:filepath",
+    &path_arg(&path_file, "Invalid output folder")?,
+    url,
+  ]);
+
+  spawn_and_stream(app, run, cmd, "yt-dlp").await?;
+
+  let written = fs::read_to_string(&path_file).map_err(|e| format!("yt-dlp didn't report its output file: {e}"))?;
+  let _ = fs::remove_file(&path_file);
+  written
+    .lines()
+    .map(str::trim)
+    .find(|l| !l.is_empty())
+    .map(PathBuf::from)
+    .ok_or_else(|| "yt-dlp didn't report its output file".into())
+}
+
+/// tinydiarize pass: JSON output with `speaker_turn_next` flags per segment.
+pub async fn run_whisper_tdrz(
+  app: &Host,
+  run: &RunContext,
+  whisper: &Path,
+  model: &Path,
+  input: AudioInput<'_>,
   out_prefix: &Path,
 ) -> Result<(), String> {
-  let mut cmd = Command::new(whisper);
+  let mut cmd = AsyncCommand::new(longpath::extended(whisper));
   cmd.args([
     "-m",
-    model.to_str().ok_or("Invalid model path")?,
-    "-olrc",
+    &path_arg(model, "Invalid model path")?,
+    "-tdrz",
+    "-pp",
+    "-oj",
     "-of",
-    out_prefix.to_str().ok_or("Invalid output prefix")?,
-    input_audio.to_str().ok_or("Invalid input audio path")?,
+    &path_arg(out_prefix, "Invalid output prefix")?,
   ]);
 
-  spawn_and_stream(app, cmd, "whisper")
+  run_whisper_on(app, run, cmd, input, "whisper (diarize)").await
 }
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use super::{emit, ensure_whisper_libs, process, whisper_bin_dir, ProgressEvent, RunningGuard, DEPS_URL};
+use crate::host::Host;
+use crate::{download, longpath, model_downloader, models, settings};
+
+#[cfg(windows)]
+const QUANTIZE_NAME: &str = "whisper-quantize.exe";
+#[cfg(not(windows))]
+const QUANTIZE_NAME: &str = "whisper-quantize";
+
+// whisper.cpp's quantize tool, downloaded with the deps on first use. It exits non-zero
+// without arguments, so unlike whisper it can't be probed before running.
+async fn ensure_quantize_tool(app: &Host) -> Result<PathBuf, String> {
+  // It links the same runtime libraries as whisper, which a configured or system whisper
+  // doesn't bring along.
+  ensure_whisper_libs(app).await?;
+  let path = whisper_bin_dir(app)?.join(QUANTIZE_NAME);
+  if path.exists() {
+    return Ok(path);
+  }
+
+  let url = format!("{DEPS_URL}{QUANTIZE_NAME}");
+  download::download_with_progress(app, "deps", &url, &path, QUANTIZE_NAME).await?;
+  #[cfg(unix)]
+  {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = std::fs::metadata(&path).map_err(|e| e.to_string())?.permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(&path, perms).map_err(|e| e.to_string())?;
+  }
+  Ok(path)
+}
+
+/// Quantize an installed full-precision model to `target` ("q5_0", "q5_1", "q8_0") in
+/// the models dir, instead of downloading the quantized file. Returns its path.
+pub async fn quantize_model(app: Host, name: &str, target: &str) -> Result<String, String> {
+  let variant_name = format!("{name}-{target}");
+  let variant = model_downloader::find_variant(&variant_name).ok_or_else(|| {
+    let targets: Vec<&str> = model_downloader::MODEL_VARIANTS
+      .iter()
+      .filter_map(|v| v.name.strip_prefix(name)?.strip_prefix('-'))
+      .collect();
+    if targets.is_empty() {
+      format!("{name} has no quantized variants")
+    } else {
+      format!("No {target} variant of {name}; pick one of {}", targets.join(", "))
+    }
+  })?;
+  let source = models::list(&app)?
+    .into_iter()
+    .find(|m| m.name == name)
+    .ok_or_else(|| format!("{name} isn't installed; download it first"))?;
+  let models_dir = settings::models_dir(&app)?;
+  let out = models_dir.join(variant.file);
+  if out.exists() {
+    return Err(format!("{variant_name} is already installed"));
+  }
+
+  let _guard = RunningGuard::acquire()?;
+
+  let tool = ensure_quantize_tool(&app).await?;
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Quantizing".into(),
+      detail: Some(format!("{name} → {target}")),
+    },
+  );
+
+  std::fs::create_dir_all(&models_dir).map_err(|e| format!("Failed creating {}: {e}", models_dir.display()))?;
+  // Written under a temp name so a failed run never leaves a model that looks usable.
+  let part = out.with_extension("part");
+  let mut cmd = tokio::process::Command::new(longpath::extended(&tool));
+  cmd.arg(longpath::extended(std::path::Path::new(&source.path)))
+    .arg(longpath::extended(&part))
+    .arg(target);
+  let written = match process::spawn_and_stream(&app, &process::RunContext::for_app(&app), cmd, "quantize").await {
+    Ok(()) => models::verify_model_file(&part).map_err(|reason| format!("Quantized {variant_name} is unusable: {reason}")),
+    Err(e) => Err(e),
+  };
+  if let Err(e) = written {
+    let _ = std::fs::remove_file(&part);
+    return Err(e);
+  }
+  std::fs::rename(&part, &out).map_err(|e| format!("Failed saving {}: {e}", out.display()))?;
+
+  emit(
+    &app,
+    ProgressEvent::Log {
+      line: format!("Saved {variant_name} to {}", out.display()),
+    },
+  );
+  Ok(out.display().to_string())
+}
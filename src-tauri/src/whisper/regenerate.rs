@@ -0,0 +1,120 @@
+use lyrictime_core::encoding;
+use lyrictime_core::lrc::{
+  apply_offset_tag, offset_tag, parse_lrc, retime_lrc, set_offset_tag, splice_range, LrcLine,
+};
+use lyrictime_core::merge;
+use std::path::Path;
+
+use super::{
+  emit, ffmpeg_args, process, resolve_model, transcribe, whisper_params, GenerateOptions, ProgressEvent, RunningGuard,
+  Toolchain, WhisperInput,
+};
+use crate::host::Host;
+use crate::{edits, library, longpath, model_downloader};
+
+fn seconds(ms: u64) -> String {
+  format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Re-transcribe `start_ms..end_ms` of `audio_path` and splice the result into the
+/// existing `<audio>.lrc`, replacing only the lines that start inside the range.
+/// Returns the LRC path.
+pub async fn regenerate_range(
+  app: Host,
+  audio_path: &Path,
+  start_ms: u64,
+  end_ms: u64,
+  model: &str,
+  options: GenerateOptions,
+) -> Result<String, String> {
+  if end_ms <= start_ms {
+    return Err("The range must end after it starts".into());
+  }
+  let lrc_path = library::output_path(&app, audio_path, "lrc");
+  let bytes =
+    std::fs::read(longpath::extended(&lrc_path)).map_err(|e| format!("Failed reading {}: {e}", lrc_path.display()))?;
+  // Spliced with the offset baked in, so the range and the kept lines are both in the
+  // audio's time; the tag is put back on writing.
+  let decoded = encoding::decode(&bytes);
+  let offset = offset_tag(&decoded);
+  let existing = apply_offset_tag(&decoded);
+
+  let _guard = RunningGuard::acquire()?;
+
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Regenerating".into(),
+      detail: Some(format!("{} s – {} s", seconds(start_ms), seconds(end_ms))),
+    },
+  );
+  let Toolchain { resources_dir, fallback_resources_dir, ffmpeg, ffprobe, whisper } = Toolchain::prepare(&app).await?;
+
+  // Hybrid re-runs both passes on the span and merges them like a full run.
+  let models: Vec<&str> = if model.eq_ignore_ascii_case("hybrid") { vec!["small", "medium"] } else { vec![model] };
+  for m in &models {
+    if model_downloader::find_variant(m).is_some() {
+      model_downloader::ensure_model(&app, m).await?;
+    }
+  }
+
+  let streams = process::probe_audio_streams(&ffprobe, audio_path)?;
+  let mut ffmpeg_args = ffmpeg_args(&app, &streams, &options)?;
+  // As output options, so the cut is sample-accurate rather than keyframe-aligned.
+  ffmpeg_args.extend(["-ss".into(), seconds(start_ms), "-to".into(), seconds(end_ms)]);
+
+  let run = process::RunContext::new(&app, &options);
+  let tmp_dir = process::RunDir::create(&format!("range-{}", std::process::id()))?;
+  let wav = tmp_dir.join("range.wav");
+  process::run_ffmpeg_to_wav(&app, &run, &ffmpeg, audio_path, &wav, &ffmpeg_args).await?;
+  let input = WhisperInput::Single(wav);
+
+  let params = whisper_params(&app, &ffprobe, audio_path, &options, run).await?;
+
+  let mut passes = Vec::with_capacity(models.len());
+  for (i, m) in models.iter().enumerate() {
+    let model_path = resolve_model(&app, &resources_dir, fallback_resources_dir.as_ref(), m, &params)?;
+    let prefix = tmp_dir.join(format!("out_{i}"));
+    passes.push(transcribe(&app, &whisper, &model_path, &input, &prefix, &params, options.profile).await?.unwrap_or_default());
+  }
+  let cleaned = match passes.as_slice() {
+    [small, medium] => merge::strategy(options.merge, options.profile).merge(small, medium).0,
+    [single] => single.clone(),
+    _ => String::new(),
+  };
+
+  // Whisper's times are relative to the cut.
+  let lines: Vec<LrcLine> = parse_lrc(&cleaned)
+    .into_iter()
+    .map(|l| LrcLine {
+      ms: l.ms + start_ms as i64,
+      text: l.text,
+      end_ms: l.end_ms.map(|e| e + start_ms as i64),
+    })
+    .collect();
+  emit(
+    &app,
+    ProgressEvent::Log {
+      line: format!("Replacing the range with {} new lines", lines.len()),
+    },
+  );
+
+  let mut spliced = splice_range(&existing, start_ms as i64, end_ms as i64, &lines);
+  if offset != 0 {
+    spliced = set_offset_tag(&retime_lrc(&spliced, |ms| ms + offset), offset);
+  }
+  std::fs::write(longpath::extended(&lrc_path), encoding::encode(&spliced, encoding::detect(&bytes)))
+    .map_err(|e| format!("Failed writing {}: {e}", lrc_path.display()))?;
+  if let Err(e) = edits::remember_range(&app, &lrc_path, start_ms as i64, end_ms as i64, &lines) {
+    emit(&app, ProgressEvent::Log { line: e });
+  }
+  emit(
+    &app,
+    ProgressEvent::Done {
+      output_path: lrc_path.display().to_string(),
+      instrumental: false,
+      raw_outputs: Vec::new(),
+    },
+  );
+  Ok(lrc_path.display().to_string())
+}
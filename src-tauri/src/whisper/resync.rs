@@ -0,0 +1,119 @@
+use lyrictime_core::align;
+use lyrictime_core::encoding;
+use lyrictime_core::lrc::{self, parse_lrc};
+use serde::Serialize;
+use std::path::Path;
+
+use super::{emit, process, resolve_model, transcribe, Profile, ProgressEvent, RunningGuard, Toolchain, WhisperInput};
+use crate::host::Host;
+use crate::{library, longpath, model_downloader};
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ResyncResult {
+  pub output_path: String,
+  /// `new = old * stretch + offset_ms`
+  pub offset_ms: f64,
+  pub stretch: f64,
+  /// Reference lines the fit was based on.
+  pub support: usize,
+}
+
+/// Re-time an LRC synced to one release (album cut, original master) to `audio_path`
+/// (radio edit, remaster): transcribe the new audio with the fastest model installed,
+/// fit offset + stretch by matching text, and rewrite every timestamp. Headers and
+/// untimed lines are kept. Writes next to the audio unless `out_path` is given.
+pub async fn resync_lrc(
+  app: Host,
+  lrc_path: &Path,
+  audio_path: &Path,
+  out_path: Option<&Path>,
+) -> Result<ResyncResult, String> {
+  let _guard = RunningGuard::acquire()?;
+
+  let bytes =
+    std::fs::read(longpath::extended(lrc_path)).map_err(|e| format!("Failed reading {}: {e}", lrc_path.display()))?;
+  let text = lrc::apply_offset_tag(&encoding::decode(&bytes));
+  let reference = parse_lrc(&text);
+  if reference.is_empty() {
+    return Err(format!("No timed lines in {}", lrc_path.display()));
+  }
+  if !audio_path.exists() {
+    return Err("Audio file does not exist".into());
+  }
+
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Resyncing".into(),
+      detail: Some("Preparing".into()),
+    },
+  );
+  let Toolchain { resources_dir, fallback_resources_dir, ffmpeg, whisper, .. } = Toolchain::prepare(&app).await?;
+
+  // Only line starts matter here, so tiny is plenty when it's installed; small is
+  // always available as the download-on-first-use default.
+  let params = process::WhisperParams {
+    run: process::RunContext::for_app(&app),
+    ..Default::default()
+  };
+  let model_path = match resolve_model(&app, &resources_dir, fallback_resources_dir.as_ref(), "tiny", &params) {
+    Ok(p) => p,
+    Err(_) => {
+      model_downloader::ensure_model(&app, "small").await?;
+      resolve_model(&app, &resources_dir, fallback_resources_dir.as_ref(), "small", &params)?
+    }
+  };
+
+  let tmp_dir = process::RunDir::create(&format!("resync-{}", std::process::id()))?;
+  let wav = tmp_dir.join("input.wav");
+  process::run_ffmpeg_to_wav(&app, &params.run, &ffmpeg, audio_path, &wav, &[]).await?;
+
+  emit(
+    &app,
+    ProgressEvent::Stage {
+      stage: "Resyncing".into(),
+      detail: Some("Transcribing the new audio".into()),
+    },
+  );
+  let heard = transcribe(
+    &app,
+    &whisper,
+    &model_path,
+    &WhisperInput::Single(wav),
+    &tmp_dir.join("out"),
+    &params,
+    Profile::Lyrics,
+  )
+  .await?
+  .map(|clean| parse_lrc(&clean))
+  .unwrap_or_default();
+  if heard.is_empty() {
+    return Err("No vocals recognized in the new audio".into());
+  }
+
+  let map = align::estimate(&reference, &heard).ok_or("Couldn't match the lyrics to the new audio")?;
+  emit(
+    &app,
+    ProgressEvent::Log {
+      line: format!(
+        "Resync: offset {:+.0} ms, stretch {:.4} (from {} lines)",
+        map.offset_ms, map.stretch, map.support
+      ),
+    },
+  );
+
+  let out_path = out_path
+    .map(Path::to_path_buf)
+    .unwrap_or_else(|| library::output_path(&app, audio_path, "lrc"));
+  let retimed = lrc::retime_lrc(&text, |ms| map.apply(ms).max(0));
+  std::fs::write(longpath::extended(&out_path), encoding::encode(&retimed, encoding::detect(&bytes)))
+    .map_err(|e| format!("Failed writing {}: {e}", out_path.display()))?;
+
+  Ok(ResyncResult {
+    output_path: out_path.display().to_string(),
+    offset_ms: map.offset_ms,
+    stretch: map.stretch,
+    support: map.support,
+  })
+}
@@ -0,0 +1,64 @@
+use lyrictime_core::progress::{Progress, ProgressEvent};
+use std::path::{Path, PathBuf};
+
+use crate::{download, ffmpeg_downloader};
+use crate::host::Host;
+use crate::whisper::{self, find_system_executable, GenerateOptions};
+
+#[cfg(windows)]
+const YTDLP_NAME: &str = "yt-dlp.exe";
+#[cfg(not(windows))]
+const YTDLP_NAME: &str = "yt-dlp";
+
+// Upstream standalone builds (no Python needed). yt-dlp has to keep up with site
+// changes, so this follows the latest release instead of a re-hosted copy.
+#[cfg(windows)]
+const YTDLP_ASSET: &str = "yt-dlp.exe";
+#[cfg(target_os = "macos")]
+const YTDLP_ASSET: &str = "yt-dlp_macos";
+#[cfg(all(not(windows), not(target_os = "macos")))]
+const YTDLP_ASSET: &str = "yt-dlp_linux";
+
+/// True for inputs that should go through yt-dlp rather than be opened as a file.
+pub fn is_url(input: &str) -> bool {
+  let s = input.trim().to_ascii_lowercase();
+  s.starts_with("http://") || s.starts_with("https://")
+}
+
+/// yt-dlp from app data, a system install, or downloaded on first use.
+pub async fn ensure_ytdlp(app: &Host) -> Result<PathBuf, String> {
+  let path = ffmpeg_downloader::bin_dir(app)?.join(YTDLP_NAME);
+  if !path.exists() {
+    if let Some(system) = find_system_executable("yt-dlp", "--version") {
+      return Ok(system);
+    }
+  }
+
+  let url = format!("https://github.com/yt-dlp/yt-dlp/releases/latest/download/{YTDLP_ASSET}");
+  ffmpeg_downloader::ensure_binary(app, &url, &path, YTDLP_NAME, "--version", &[]).await?;
+  Ok(path)
+}
+
+/// Download the audio behind `url` into `out_dir`, then generate lyrics next to it.
+pub async fn generate_from_url(
+  app: Host,
+  url: &str,
+  out_dir: &Path,
+  model: &str,
+  options: GenerateOptions,
+) -> Result<String, String> {
+  download::ensure_online(&app, "downloading from a URL")?;
+  let (ffmpeg_url, ffprobe_url) = ffmpeg_downloader::default_urls();
+  let ffmpeg = PathBuf::from(ffmpeg_downloader::ensure_ffmpeg(app.clone(), ffmpeg_url, ffprobe_url).await?.ffmpeg_path);
+  let ffmpeg_dir = ffmpeg.parent().ok_or("Invalid ffmpeg path")?;
+
+  let ytdlp = ensure_ytdlp(&app).await?;
+  app.report(ProgressEvent::Stage {
+    stage: "Downloading".into(),
+    detail: Some(format!("Fetching audio from {url}")),
+  });
+  let run = whisper::RunContext::new(&app, &options);
+  let audio = whisper::run_ytdlp(&app, &run, &ytdlp, ffmpeg_dir, url, out_dir).await?;
+
+  whisper::generate_lrc_next_to_audio(app, &audio.to_string_lossy(), model, options).await
+}
@@ -6,9 +6,132 @@ import { listen } from "@tauri-apps/api/event";
 type ProgressEvent =
   | { kind: "stage"; stage: string; detail?: string }
   | { kind: "log"; line: string }
-  | { kind: "done"; outputPath: string };
+  | { kind: "done"; outputPath: string; rawOutputs?: string[] }
+  | { kind: "redirected"; from: string; to: string; reason: string }
+  | { kind: "diagnostics"; appVersion: string; platform: string; whisperPath: string; ffmpegPath: string }
+  | { kind: "error"; code: string; message: string; detail?: any };
+
+type BatchProgress = {
+  index: number;
+  total: number;
+  input: string;
+  status: "running" | "done" | "planned" | "skipped" | "duplicate" | "rejected" | "failed" | "cancelled";
+  error?: string;
+};
+
+type BatchReport = {
+  items: {
+    input: string;
+    status: "done" | "planned" | "skipped" | "duplicate" | "rejected" | "failed";
+    model: string;
+    outputs: string[];
+    error: string | null;
+    duplicateOf?: string;
+    runtimeMs: number;
+    warnings: string[];
+  }[];
+  model: string;
+  startedAt: number;
+  finishedAt: number;
+  dryRun: boolean;
+  succeeded: number;
+  planned: number;
+  skipped: number;
+  duplicates: number;
+  rejected: number;
+  failed: number;
+};
+
+type AudioTags = {
+  artist: string | null;
+  title: string | null;
+  album: string | null;
+  durationMs: number;
+  hasEmbeddedLyrics: boolean;
+  hasLrcSidecar: boolean;
+};
+
+type BenchmarkResult = {
+  model: string;
+  audioMs: number;
+  elapsedMs: number;
+  realtimeFactor: number;
+};
+
+type NowPlaying = {
+  path: string;
+  title: string | null;
+  artist: string | null;
+  player: string;
+  hasLyrics: boolean;
+};
+
+type DownloadProgress = {
+  group: string;
+  file: string;
+  downloaded_bytes: number;
+  total_bytes: number | null;
+  status: "downloading" | "done" | "error";
+  error?: string | null;
+  bytes_per_second?: number;
+};
+
+type MediaFormat = {
+  name: string;
+  extensions: string[];
+  direct: boolean;
+  video: boolean;
+};
+
+type ModelIssue = {
+  name: string;
+  path: string;
+  reason: string;
+  redownloadable: boolean;
+};
+
+type AppUpdate = {
+  currentVersion: string;
+  latestVersion: string;
+  updateAvailable: boolean;
+  changelog: string;
+  releaseUrl: string;
+  downloadUrl: string | null;
+};
+
+type SetupStatus = {
+  firstRun: boolean;
+  ffmpeg: boolean;
+  whisper: boolean;
+  models: { name: string; downloadBytes: number; installed: boolean; recommended: boolean }[];
+  freeDiskBytes: number | null;
+  totalRamBytes: number;
+  ramClass: "low" | "medium" | "high";
+};
+
+type SetupProgress = {
+  step: number;
+  totalSteps: number;
+  item: string;
+  status: "running" | "done" | "failed";
+  downloadedBytes: number;
+  totalBytes: number;
+  error?: string;
+};
+
+type Model = "auto" | "hybrid" | "small" | "medium" | "small.en" | "medium.en";
+
+// The models a run with `model` transcribes with; "auto" runs what this computer is recommended.
+function modelsFor(model: string, recommended: string): string[] {
+  if (model === "auto") return recommended ? modelsFor(recommended, "") : [];
+  return model === "hybrid" ? ["small", "medium"] : [model];
+}
 
-type Model = "hybrid" | "small" | "medium";
+// Until list_media_formats answers (or if it fails), so the file picker isn't empty.
+const DEFAULT_MEDIA_EXTENSIONS = [
+  "wav", "mp3", "flac", "ogg", "oga", "opus", "m4a", "aac", "m4b", "wma", "aiff", "aif", "ape", "wv", "mka",
+  "mp4", "m4v", "mov", "mkv", "webm", "avi",
+];
 
 export default function App() {
   const [audioPath, setAudioPath] = useState<string>("");
@@ -17,6 +140,88 @@ export default function App() {
   const [status, setStatus] = useState("Idle");
   const [log, setLog] = useState<string[]>([]);
   const [outputPath, setOutputPath] = useState<string>("");
+  const [url, setUrl] = useState<string>("");
+  const [tags, setTags] = useState<AudioTags | null>(null);
+  const [recommendedModel, setRecommendedModel] = useState<string>("");
+  const [benchmarks, setBenchmarks] = useState<Record<string, number>>({});
+  const [mediaExtensions, setMediaExtensions] = useState<string[]>(DEFAULT_MEDIA_EXTENSIONS);
+  const [update, setUpdate] = useState<AppUpdate | null>(null);
+  const [setup, setSetup] = useState<SetupStatus | null>(null);
+  const [setupModels, setSetupModels] = useState<string[]>([]);
+
+  function refreshCapabilities() {
+    invoke<{ recommendedModel: string; benchmarks: Record<string, number> }>("detect_system_capabilities")
+      .then((caps) => {
+        setRecommendedModel(caps.recommendedModel);
+        setBenchmarks(caps.benchmarks);
+      })
+      .catch(() => {});
+  }
+
+  useEffect(refreshCapabilities, []);
+
+  useEffect(() => {
+    // Quietly skipped when offline or GitHub is unreachable.
+    invoke<AppUpdate>("check_app_update")
+      .then((u) => setUpdate(u.updateAvailable ? u : null))
+      .catch(() => {});
+  }, []);
+
+  useEffect(() => {
+    // Catch truncated or corrupt models before a run fails on one.
+    invoke<ModelIssue[]>("verify_models")
+      .then((issues) => {
+        for (const m of issues) {
+          const message = `Model ${m.name} is damaged: ${m.reason}`;
+          setLog((l) => [...l, message]);
+          if (m.redownloadable && window.confirm(`${message}. Redownload it?`)) {
+            setStatus(`Redownloading ${m.name}…`);
+            invoke("redownload_model", { name: m.name })
+              .then(() => setStatus("Model redownloaded"))
+              .catch((err) => setLog((l) => [...l, String(err)]));
+          }
+        }
+      })
+      .catch(() => {});
+  }, []);
+
+  useEffect(() => {
+    invoke<SetupStatus>("get_setup_status")
+      .then((st) => {
+        if (!st.firstRun) return;
+        setSetup(st);
+        // Preselect what the chosen model needs that isn't there yet.
+        const needed = modelsFor(model, recommendedModel);
+        setSetupModels(st.models.filter((m) => needed.includes(m.name) && !m.installed).map((m) => m.name));
+      })
+      .catch(() => {});
+  }, []);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    (async () => {
+      unlisten = await listen<SetupProgress>("setup://progress", (event) => {
+        const p = event.payload;
+        const mb = (b: number) => (b / 1e6).toFixed(0);
+        if (p.status === "running") {
+          setStatus(`Setup ${p.step}/${p.totalSteps}: ${p.item} (${mb(p.downloadedBytes)} / ${mb(p.totalBytes)} MB)`);
+        } else if (p.status === "failed") {
+          setLog((l) => [...l, `Setup failed at ${p.item}: ${p.error ?? ""}`]);
+        }
+      });
+    })();
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
+  useEffect(() => {
+    invoke<MediaFormat[]>("get_supported_formats")
+      .then((formats) => setMediaExtensions([...new Set(formats.flatMap((f) => f.extensions))]))
+      .catch(() => {});
+  }, []);
 
   useEffect(() => {
     let unlisten: (() => void) | null = null;
@@ -30,8 +235,21 @@ export default function App() {
           setLog((l) => [...l.slice(-400), p.line]);
         } else if (p.kind === "done") {
           setOutputPath(p.outputPath);
+          if (p.rawOutputs?.length) {
+            setLog((l) => [...l.slice(-400), ...p.rawOutputs!.map((r) => `Raw output: ${r}`)]);
+          }
           setStatus("Done");
           setBusy(false);
+        } else if (p.kind === "redirected") {
+          setLog((l) => [...l.slice(-400), `${p.reason}; writing to ${p.to} instead`]);
+        } else if (p.kind === "error") {
+          setLog((l) => [...l.slice(-400), p.message]);
+          if (p.code === "model_corrupt" && p.detail?.redownloadable && window.confirm(p.message)) {
+            setStatus(`Redownloading ${p.detail.name}…`);
+            invoke("redownload_model", { name: p.detail.name })
+              .then(() => setStatus("Model redownloaded"))
+              .catch((err) => setLog((l) => [...l, String(err)]));
+          }
         }
       });
     })();
@@ -46,42 +264,66 @@ export default function App() {
     let unlisten: (() => void) | null = null;
 
     (async () => {
-      unlisten = await listen<{
-        group: string;
-        file: string;
-        downloaded_bytes: number;
-        total_bytes: number | null;
-        status: "downloading" | "done" | "error";
-        error?: string | null;
-      }>("download://progress", (event) => {
+      unlisten = await listen<BatchProgress>("batch://progress", (event) => {
         const p = event.payload;
+        if (p.status === "running") {
+          setLog((l) => [...l.slice(-400), `[${p.index}/${p.total}] ${p.input}`]);
+        } else if (p.status === "rejected") {
+          setLog((l) => [...l.slice(-400), `Rejected: ${p.input} (${p.error ?? ""})`]);
+        } else if (p.status === "skipped") {
+          setLog((l) => [...l.slice(-400), `Skipped: ${p.input} (${p.error ?? ""})`]);
+        } else if (p.status === "duplicate") {
+          setLog((l) => [...l.slice(-400), `Copied: ${p.input} (${p.error ?? ""})`]);
+        } else if (p.status === "failed") {
+          setLog((l) => [...l.slice(-400), `Failed: ${p.input}: ${p.error ?? ""}`]);
+        }
+      });
+    })();
 
-        const fmt = (bytes: number) => {
-          const units = ["B", "KB", "MB", "GB", "TB"];
-          let v = bytes;
-          let i = 0;
-          while (v >= 1024 && i < units.length - 1) {
-            v /= 1024;
-            i++;
-          }
-          return `${v.toFixed(i === 0 ? 0 : 1)}${units[i]}`;
-        };
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
 
-        if (p.status === "downloading") {
-          const left = fmt(p.downloaded_bytes);
-          const right = p.total_bytes ? fmt(p.total_bytes) : "?";
-          setStatus(`Downloading ${p.file}: ${left} / ${right}`);
-        }
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
 
-        if (p.status === "done") {
-          setStatus(`Downloaded ${p.file}`);
-        }
+    const fmt = (bytes: number) => {
+      const units = ["B", "KB", "MB", "GB", "TB"];
+      let v = bytes;
+      let i = 0;
+      while (v >= 1024 && i < units.length - 1) {
+        v /= 1024;
+        i++;
+      }
+      return `${v.toFixed(i === 0 ? 0 : 1)}${units[i]}`;
+    };
 
-        if (p.status === "error") {
-          setStatus(`Error downloading ${p.file}`);
-          setLog((l) => [...l, p.error ?? "Unknown download error"]);
-        }
-      });
+    const show = (p: DownloadProgress) => {
+      if (p.status === "downloading") {
+        const left = fmt(p.downloaded_bytes);
+        const right = p.total_bytes ? fmt(p.total_bytes) : "?";
+        const speed = p.bytes_per_second ? ` (${fmt(p.bytes_per_second)}/s)` : "";
+        setStatus(`Downloading ${p.file}: ${left} / ${right}${speed}`);
+      }
+
+      if (p.status === "done") {
+        setStatus(`Downloaded ${p.file}`);
+      }
+
+      if (p.status === "error") {
+        setStatus(`Error downloading ${p.file}`);
+        setLog((l) => [...l, p.error ?? "Unknown download error"]);
+      }
+    };
+
+    (async () => {
+      unlisten = await listen<DownloadProgress>("download://progress", (event) => show(event.payload));
+
+      // After a reload, pick up a download that is still running.
+      const downloads: DownloadProgress[] = await invoke("get_download_status");
+      const running = downloads.filter((d) => d.status === "downloading").pop();
+      if (running) show(running);
     })();
 
     return () => {
@@ -90,6 +332,24 @@ export default function App() {
   }, []);
 
 
+  // Offer mode: pick up the track the media player just started if it has no lyrics yet.
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+
+    (async () => {
+      unlisten = await listen<NowPlaying | null>("now-playing://changed", (event) => {
+        const p = event.payload;
+        if (!p || p.hasLyrics || busy) return;
+        setAudioPath(p.path);
+        setStatus(`Now playing in ${p.player}: ${p.title ?? p.path} (no lyrics yet, press Generate)`);
+      });
+    })();
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, [busy]);
+
   const canRun = useMemo(() => !!audioPath && !busy, [audioPath, busy]);
 
   async function chooseFile() {
@@ -98,18 +358,8 @@ export default function App() {
       directory: false,
       filters: [
         {
-          name: "Audio",
-          extensions: [
-            "mp3",
-            "m4a",
-            "aac",
-            "flac",
-            "wav",
-            "ogg",
-            "opus",
-            "aiff",
-            "aif",
-          ],
+          name: "Audio & video",
+          extensions: [...mediaExtensions, "cue", "m3u", "m3u8"],
         },
       ],
     });
@@ -119,6 +369,11 @@ export default function App() {
       setStatus("Ready");
       setLog([]);
       setOutputPath("");
+      setTags(null);
+      // Playlists, cue sheets and untagged formats just show the path.
+      invoke<AudioTags>("read_audio_tags", { path: selected })
+        .then(setTags)
+        .catch(() => {});
     }
   }
 
@@ -131,8 +386,101 @@ export default function App() {
     setOutputPath("");
 
     try {
-      const out: string = await invoke("generate_lrc_next_to_audio", {
-        audioPath,
+      const lower = audioPath.toLowerCase();
+      if (lower.endsWith(".m3u") || lower.endsWith(".m3u8")) {
+        const report: BatchReport = await invoke("generate_batch", {
+          inputs: [audioPath],
+          outDir: ".",
+          model,
+        });
+        setOutputPath(report.items.flatMap((i) => i.outputs).join("\n"));
+        setLog((l) => [
+          ...l,
+          `${report.succeeded} succeeded, ${report.skipped} skipped, ${report.duplicates} duplicates, ` +
+            `${report.rejected} rejected, ${report.failed} failed`,
+        ]);
+      } else if (lower.endsWith(".cue")) {
+        const outs: string[] = await invoke("generate_lrc_from_cue", {
+          cuePath: audioPath,
+          model,
+        });
+        setOutputPath(outs.join("\n"));
+      } else {
+        const out: string = await invoke("generate_lrc_next_to_audio", {
+          audioPath,
+          model,
+        });
+        setOutputPath(out);
+      }
+      setBusy(false);
+      setStatus("Done");
+    } catch (err) {
+      setBusy(false);
+      setStatus("Error");
+      setLog((l) => [...l, String(err)]);
+    }
+  }
+
+  async function benchmark() {
+    if (busy || model === "auto" || model === "hybrid") return;
+
+    setBusy(true);
+    setStatus("Starting…");
+    setLog([]);
+
+    try {
+      const r: BenchmarkResult = await invoke("run_benchmark", { model });
+      setLog((l) => [
+        ...l,
+        `${r.model}: ${(r.elapsedMs / 1000).toFixed(1)} s for ${r.audioMs / 1000} s of audio ` +
+          `(realtime factor ${r.realtimeFactor.toFixed(2)})`,
+      ]);
+      refreshCapabilities();
+      setBusy(false);
+      setStatus("Done");
+    } catch (err) {
+      setBusy(false);
+      setStatus("Error");
+      setLog((l) => [...l, String(err)]);
+    }
+  }
+
+  // "· 0.40× realtime" once the model has been benchmarked.
+  const speed = (m: string) => (benchmarks[m] ? ` · ${benchmarks[m].toFixed(2)}× realtime` : "");
+
+  async function cancel() {
+    try {
+      await invoke("cancel_generation");
+    } catch (err) {
+      setLog((l) => [...l, String(err)]);
+    }
+  }
+
+  async function showOutput() {
+    // Multi-file runs list one path per line; reveal the first.
+    const first = outputPath.split("\n")[0];
+    try {
+      await invoke("open_output_location", { path: first });
+    } catch (err) {
+      setLog((l) => [...l, String(err)]);
+    }
+  }
+
+  async function generateFromUrl() {
+    if (!url.trim() || busy) return;
+
+    const outDir = await open({ multiple: false, directory: true });
+    if (typeof outDir !== "string") return;
+
+    setBusy(true);
+    setStatus("Starting…");
+    setLog([]);
+    setOutputPath("");
+
+    try {
+      const out: string = await invoke("generate_lrc_from_url", {
+        url: url.trim(),
+        outDir,
         model,
       });
       setOutputPath(out);
@@ -145,16 +493,68 @@ export default function App() {
     }
   }
 
+  async function runSetup() {
+    if (!setup) return;
+    setBusy(true);
+    try {
+      await invoke("run_setup", {
+        selections: { ffmpeg: !setup.ffmpeg, whisper: !setup.whisper, models: setupModels },
+      });
+      setSetup(null);
+      setStatus("Setup complete");
+    } catch (err) {
+      setStatus("Error");
+      setLog((l) => [...l, String(err)]);
+    } finally {
+      setBusy(false);
+    }
+  }
+
   return (
     <div style={page()}>
       <h1 style={{ margin: 0 }}>LyricTime</h1>
       <p style={{ opacity: 0.8, marginTop: 6 }}>
         Offline line-timed lyrics generator (.lrc)
       </p>
+      {update && (
+        <p style={{ marginTop: 0 }}>
+          LyricTime {update.latestVersion} is available (you have {update.currentVersion}):{" "}
+          {update.downloadUrl ?? update.releaseUrl}
+        </p>
+      )}
+
+      {setup && (
+        <div style={{ marginTop: 0 }}>
+          <p>
+            First run: pick what to download. {setup.ramClass} memory ({(setup.totalRamBytes / 1e9).toFixed(0)} GB)
+            {setup.freeDiskBytes !== null ? `, ${(setup.freeDiskBytes / 1e9).toFixed(1)} GB free` : ""}.
+          </p>
+          {setup.models.map((m) => (
+            <label key={m.name} style={{ display: "block" }}>
+              <input
+                type="checkbox"
+                disabled={m.installed || busy}
+                checked={m.installed || setupModels.includes(m.name)}
+                onChange={(e) =>
+                  setSetupModels((s) => (e.target.checked ? [...s, m.name] : s.filter((n) => n !== m.name)))
+                }
+              />
+              {m.name} ({(m.downloadBytes / 1e6).toFixed(0)} MB){m.recommended ? "" : ", may not fit in memory"}
+            </label>
+          ))}
+          <button
+            onClick={runSetup}
+            disabled={busy}
+            style={btn("primary")}
+          >
+            Download{!setup.ffmpeg ? " ffmpeg," : ""}{!setup.whisper ? " whisper," : ""} {setupModels.length} model(s)
+          </button>
+        </div>
+      )}
 
       <div style={row()}>
         <button onClick={chooseFile} disabled={busy} style={btn()}>
-          Choose audio file
+          Choose audio or video file
         </button>
 
         <select
@@ -163,9 +563,26 @@ export default function App() {
           disabled={busy}
           style={select()}
         >
+          <option value="auto">
+            Model: auto{recommendedModel ? ` (${recommendedModel} on this computer)` : ""}
+          </option>
           <option value="hybrid">Model: hybrid (best overall)</option>
-          <option value="small">Model: small (fast & complete)</option>
-          <option value="medium">Model: medium (best accuracy, may miss lines)</option>
+          <option value="small">
+            Model: small (fast & complete)
+            {speed("small")}
+          </option>
+          <option value="medium">
+            Model: medium (best accuracy, may miss lines)
+            {speed("medium")}
+          </option>
+          <option value="small.en">
+            Model: small.en (English only, faster)
+            {speed("small.en")}
+          </option>
+          <option value="medium.en">
+            Model: medium.en (English only, most accurate)
+            {speed("medium.en")}
+          </option>
         </select>
 
         <button
@@ -175,11 +592,51 @@ export default function App() {
         >
           {busy ? "Working…" : "Generate .lrc"}
         </button>
+
+        {busy && (
+          <button onClick={cancel} style={btn()}>
+            Cancel
+          </button>
+        )}
+
+        <button
+          onClick={benchmark}
+          disabled={busy || model === "auto" || model === "hybrid"}
+          style={btn()}
+          title="Time this model on a 30-second sample"
+        >
+          Benchmark
+        </button>
+      </div>
+
+      <div style={row()}>
+        <input
+          value={url}
+          onChange={(e) => setUrl(e.target.value)}
+          placeholder="…or paste a YouTube / SoundCloud URL"
+          disabled={busy}
+          style={{ ...select(), flex: 1, minWidth: 260 }}
+        />
+        <button
+          onClick={generateFromUrl}
+          disabled={busy || !url.trim()}
+          style={btn(!busy && url.trim() ? "default" : "disabled")}
+        >
+          Download & generate
+        </button>
       </div>
 
       <Section title="Selected audio">{audioPath || "—"}</Section>
+      {tags && <Section title="Tags">{describeTags(tags)}</Section>}
       <Section title="Status">{status}</Section>
       <Section title="Output">{outputPath || "—"}</Section>
+      {outputPath && !busy && (
+        <div style={row()}>
+          <button onClick={showOutput} style={btn()}>
+            Show file
+          </button>
+        </div>
+      )}
 
       <div style={{ marginTop: 16 }}>
         <div style={label()}>Log</div>
@@ -303,3 +760,16 @@ function logBox(): React.CSSProperties {
     wordBreak: "break-word",
   };
 }
+
+function describeTags(t: AudioTags) {
+  const secs = Math.round(t.durationMs / 1000);
+  const length = `${Math.floor(secs / 60)}:${String(secs % 60).padStart(2, "0")}`;
+  const name = [t.artist, t.title].filter(Boolean).join(" – ") || "Untitled";
+  const notes = [
+    t.album,
+    length,
+    t.hasEmbeddedLyrics ? "has embedded lyrics" : null,
+    t.hasLrcSidecar ? ".lrc already exists" : null,
+  ].filter(Boolean);
+  return `${name} (${notes.join(", ")})`;
+}